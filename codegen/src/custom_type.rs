@@ -0,0 +1,368 @@
+use quote::{quote, quote_spanned};
+use syn::{parse::ParseStream, spanned::Spanned};
+
+use crate::attrs::{AttrItem, ExportInfo, ExportedParams};
+
+pub const ATTR_NAME: &str = "rhai";
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub(crate) struct CustomTypeParams {
+    pub name: Option<String>,
+    pub constructor: Option<String>,
+    pub display: bool,
+    pub debug: bool,
+    pub eq: bool,
+}
+
+impl ExportedParams for CustomTypeParams {
+    fn parse_stream(args: ParseStream) -> syn::Result<Self> {
+        Self::from_info(crate::attrs::parse_attr_items(args)?)
+    }
+
+    fn no_attrs() -> Self {
+        Default::default()
+    }
+
+    fn from_info(info: ExportInfo) -> syn::Result<Self> {
+        let ExportInfo { items: attrs, .. } = info;
+        let mut params = Self::default();
+        for attr in attrs {
+            let AttrItem { key, value, .. } = attr;
+            match (key.to_string().as_ref(), value) {
+                ("name", Some(_)) if params.name.is_some() => {
+                    return Err(syn::Error::new(key.span(), "conflicting name"))
+                }
+                ("name", Some(s)) => params.name = Some(s.value()),
+                ("name", None) => return Err(syn::Error::new(key.span(), "requires value")),
+
+                ("constructor", _) if params.constructor.is_some() => {
+                    return Err(syn::Error::new(key.span(), "conflicting constructor"))
+                }
+                ("constructor", Some(s)) => params.constructor = Some(s.value()),
+                ("constructor", None) => params.constructor = Some(String::new()),
+
+                ("display", None) => params.display = true,
+                ("debug", None) => params.debug = true,
+                ("eq", None) => params.eq = true,
+                ("display", Some(s)) | ("debug", Some(s)) | ("eq", Some(s)) => {
+                    return Err(syn::Error::new(s.span(), "extraneous value"))
+                }
+
+                (attr, _) => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown attribute '{}'", attr),
+                    ))
+                }
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub(crate) struct FieldParams {
+    pub name: Option<String>,
+    pub skip: bool,
+    pub readonly: bool,
+}
+
+impl ExportedParams for FieldParams {
+    fn parse_stream(args: ParseStream) -> syn::Result<Self> {
+        Self::from_info(crate::attrs::parse_attr_items(args)?)
+    }
+
+    fn no_attrs() -> Self {
+        Default::default()
+    }
+
+    fn from_info(info: ExportInfo) -> syn::Result<Self> {
+        let ExportInfo {
+            item_span: span,
+            items: attrs,
+        } = info;
+        let mut params = Self::default();
+        for attr in attrs {
+            let AttrItem { key, value, .. } = attr;
+            match (key.to_string().as_ref(), value) {
+                ("name", Some(_)) if params.name.is_some() => {
+                    return Err(syn::Error::new(key.span(), "conflicting name"))
+                }
+                ("name", Some(s)) => params.name = Some(s.value()),
+                ("name", None) => return Err(syn::Error::new(key.span(), "requires value")),
+
+                ("skip", None) => params.skip = true,
+                ("readonly", None) => params.readonly = true,
+                ("skip", Some(s)) | ("readonly", Some(s)) => {
+                    return Err(syn::Error::new(s.span(), "extraneous value"))
+                }
+
+                (attr, _) => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown attribute '{}'", attr),
+                    ))
+                }
+            }
+        }
+
+        if params.skip && (params.readonly || params.name.is_some()) {
+            return Err(syn::Error::new(
+                span,
+                "skipped fields cannot have other attributes",
+            ));
+        }
+
+        Ok(params)
+    }
+}
+
+pub(crate) fn derive_custom_type(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let params: CustomTypeParams =
+        crate::attrs::inner_item_attributes(&mut input.attrs.clone(), ATTR_NAME)?;
+
+    let type_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let pretty_name = params.name.clone().unwrap_or_else(|| type_name.to_string());
+
+    let mut registrations = match input.data {
+        syn::Data::Struct(ref data) => generate_struct(data, &params, &pretty_name)?,
+        syn::Data::Enum(ref data) => generate_enum(data, &params, &pretty_name)?,
+        syn::Data::Union(ref data) => {
+            return Err(syn::Error::new(
+                data.union_token.span(),
+                "CustomType cannot be derived for unions",
+            ))
+        }
+    };
+
+    if params.display {
+        registrations.push(quote! {
+            engine.register_fn("to_string", |obj: &mut Self| obj.to_string());
+            engine.register_fn("print", |obj: &mut Self| obj.to_string());
+        });
+    }
+    if params.debug {
+        registrations.push(quote! {
+            engine.register_fn("to_debug", |obj: &mut Self| format!("{:?}", obj));
+            engine.register_fn("debug", |obj: &mut Self| format!("{:?}", obj));
+        });
+    }
+    if params.eq {
+        registrations.push(quote! {
+            engine.register_fn("==", |obj: &mut Self, other: Self| *obj == other);
+            engine.register_fn("!=", |obj: &mut Self, other: Self| *obj != other);
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics CustomType for #type_name #ty_generics #where_clause {
+            fn build(engine: &mut Engine) {
+                engine.register_type_with_name::<Self>(#pretty_name);
+                #(#registrations)*
+            }
+        }
+    })
+}
+
+fn generate_struct(
+    data: &syn::DataStruct,
+    params: &CustomTypeParams,
+    pretty_name: &str,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let fields: Vec<_> = match data.fields {
+        syn::Fields::Named(ref fields) => fields.named.iter().collect(),
+        syn::Fields::Unit => Vec::new(),
+        syn::Fields::Unnamed(ref fields) => {
+            return Err(syn::Error::new(
+                fields.span(),
+                "CustomType can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let mut registrations = Vec::new();
+    let mut ctor_params = Vec::new();
+    let mut ctor_fields = Vec::new();
+
+    for field in fields {
+        let field_params: FieldParams =
+            crate::attrs::inner_item_attributes(&mut field.attrs.clone(), ATTR_NAME)?;
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+        let is_pub = matches!(field.vis, syn::Visibility::Public(_));
+
+        if field_params.skip || !is_pub {
+            ctor_fields.push(quote! { #field_name: Default::default() });
+            continue;
+        }
+
+        let prop_name = field_params.name.unwrap_or_else(|| field_name.to_string());
+
+        registrations.push(quote_spanned! { field.span() =>
+            engine.register_get(#prop_name, |obj: &mut Self| obj.#field_name.clone());
+        });
+        if !field_params.readonly {
+            registrations.push(quote_spanned! { field.span() =>
+                engine.register_set(#prop_name, |obj: &mut Self, value: #field_type| obj.#field_name = value);
+            });
+        }
+
+        ctor_params.push(quote! { #field_name: #field_type });
+        ctor_fields.push(quote! { #field_name });
+    }
+
+    if let Some(ref ctor_name) = params.constructor {
+        let ctor_name = if ctor_name.is_empty() {
+            pretty_name
+        } else {
+            ctor_name
+        };
+        registrations.push(quote! {
+            engine.register_fn(#ctor_name, |#(#ctor_params),*| Self { #(#ctor_fields),* });
+        });
+    }
+
+    Ok(registrations)
+}
+
+fn generate_enum(
+    data: &syn::DataEnum,
+    params: &CustomTypeParams,
+    pretty_name: &str,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    if params.constructor.is_some() {
+        return Err(syn::Error::new(
+            data.enum_token.span(),
+            "enum variants always have constructors",
+        ));
+    }
+
+    let mut variant_arms = Vec::new();
+    let mut getters: Vec<(String, Vec<proc_macro2::TokenStream>)> = Vec::new();
+    let mut ctors = Vec::new();
+
+    for variant in data.variants.iter() {
+        let variant_params: FieldParams =
+            crate::attrs::inner_item_attributes(&mut variant.attrs.clone(), ATTR_NAME)?;
+        if variant_params.readonly {
+            return Err(syn::Error::new(
+                variant.span(),
+                "enum variants are always read-only",
+            ));
+        }
+        if variant_params.skip {
+            continue;
+        }
+        let variant_ident = &variant.ident;
+        let variant_name = variant_params
+            .name
+            .unwrap_or_else(|| variant_ident.to_string());
+
+        let is_named = matches!(variant.fields, syn::Fields::Named(_));
+        let wildcard = match variant.fields {
+            syn::Fields::Named(_) => quote! { Self::#variant_ident { .. } },
+            syn::Fields::Unnamed(_) => quote! { Self::#variant_ident(..) },
+            syn::Fields::Unit => quote! { Self::#variant_ident },
+        };
+        variant_arms.push(quote! { #wildcard => #variant_name });
+
+        let mut arg_names = Vec::new();
+        let mut arg_types = Vec::new();
+        let mut ctor_fields = Vec::new();
+        let mut arg_index = 0usize;
+
+        for (index, field) in variant.fields.iter().enumerate() {
+            let field_params: FieldParams =
+                crate::attrs::inner_item_attributes(&mut field.attrs.clone(), ATTR_NAME)?;
+            let field_type = &field.ty;
+            let member = match field.ident {
+                Some(ref ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(syn::Index::from(index)),
+            };
+
+            if field_params.skip {
+                ctor_fields.push(quote! { #member: Default::default() });
+                continue;
+            }
+
+            let field_name = match field.ident {
+                Some(ref ident) => ident.to_string(),
+                None => format!("field{}", index),
+            };
+            let prop_name = field_params.name.unwrap_or_else(|| field_name.clone());
+
+            // Bind the field and ignore all others
+            let pattern = if is_named {
+                quote! { Self::#variant_ident { #member: value, .. } }
+            } else {
+                let skipped = (0..index).map(|_| quote! { _ });
+                quote! { Self::#variant_ident(#(#skipped,)* value, ..) }
+            };
+            let arm = quote_spanned! { field.span() =>
+                #pattern => Dynamic::from(value.clone())
+            };
+            match getters.iter_mut().find(|(name, _)| *name == prop_name) {
+                Some((_, arms)) => arms.push(arm),
+                None => getters.push((prop_name, vec![arm])),
+            }
+
+            arg_names.push(format!(
+                "{}: {}",
+                field_name,
+                crate::function::print_type(field_type)
+            ));
+            arg_types.push(quote_spanned! { field_type.span() => TypeId::of::<#field_type>() });
+            ctor_fields.push(quote_spanned! { field_type.span() =>
+                #member: mem::take(args[#arg_index]).cast::<#field_type>()
+            });
+            arg_index += 1;
+        }
+
+        ctors.push(match variant.fields {
+            syn::Fields::Unit => quote! {
+                module.set_var(#variant_name, Self::#variant_ident);
+            },
+            _ => quote! {
+                module.set_fn(#variant_name, FnNamespace::Internal, FnAccess::Public,
+                              Some(&[#(#arg_names),*]), &[#(#arg_types),*],
+                              CallableFunction::from_pure(Box::new(|_, args| {
+                                  Ok(Dynamic::from(Self::#variant_ident { #(#ctor_fields),* }))
+                              })));
+            },
+        });
+    }
+
+    let mut registrations = Vec::new();
+
+    registrations.push(quote! {
+        engine.register_get("variant", |obj: &mut Self| -> ImmutableString {
+            match obj {
+                #(#variant_arms,)*
+                #[allow(unreachable_patterns)]
+                _ => "",
+            }.into()
+        });
+    });
+
+    for (prop_name, arms) in getters {
+        registrations.push(quote! {
+            engine.register_get(#prop_name, |obj: &mut Self| -> Dynamic {
+                match obj {
+                    #(#arms,)*
+                    #[allow(unreachable_patterns)]
+                    _ => Dynamic::UNIT,
+                }
+            });
+        });
+    }
+
+    registrations.push(quote! {
+        let mut module = Module::new();
+        #(#ctors)*
+        engine.register_static_module(#pretty_name, module.into());
+    });
+
+    Ok(registrations)
+}
@@ -90,6 +90,28 @@ pub(crate) fn flatten_type_groups(ty: &syn::Type) -> &syn::Type {
     }
 }
 
+/// Get the inner type `T` of an `Option<T>`, which marks an optional parameter.
+pub(crate) fn optional_type(ty: &syn::Type) -> Option<&syn::Type> {
+    match flatten_type_groups(ty) {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            let segment = path.segments.last()?;
+            if segment.ident != "Option" {
+                return None;
+            }
+            match segment.arguments {
+                syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+                    match args.args.first()? {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 pub(crate) fn print_type(ty: &syn::Type) -> String {
     ty.to_token_stream()
         .to_string()
@@ -102,6 +124,51 @@ pub(crate) fn print_type(ty: &syn::Type) -> String {
         .replace(" > ", ">")
 }
 
+/// Extract the doc-comments in a list of attributes, in the same format as for script functions.
+pub(crate) fn doc_comments(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                ref path,
+                lit: syn::Lit::Str(ref s),
+                ..
+            })) if path.is_ident("doc") => Some(s.value()),
+            _ => None,
+        })
+        .map(|text| {
+            if text.contains('\n') {
+                format!("/**{}*/", text)
+            } else {
+                format!("///{}", text)
+            }
+        })
+        .collect()
+}
+
+/// Replace all occurrences of an identifier in a token stream with other tokens.
+pub(crate) fn replace_ident(
+    tokens: proc_macro2::TokenStream,
+    ident: &str,
+    replacement: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    use proc_macro2::{Group, TokenTree};
+
+    tokens
+        .into_iter()
+        .flat_map(|tt| match tt {
+            TokenTree::Ident(ref i) if i == ident => replacement.clone(),
+            TokenTree::Group(group) => {
+                let stream = replace_ident(group.stream(), ident, replacement);
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group).into_token_stream()
+            }
+            tt => tt.into_token_stream(),
+        })
+        .collect()
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct ExportedFnParams {
     pub name: Vec<String>,
@@ -272,6 +339,8 @@ pub(crate) struct ExportedFn {
     pass_context: bool,
     return_dynamic: bool,
     mut_receiver: bool,
+    optional_params: usize,
+    comments: Vec<String>,
     params: ExportedFnParams,
 }
 
@@ -296,6 +365,9 @@ impl Parse for ExportedFn {
         // Determine if the function is public.
         let is_public = matches!(fn_all.vis, syn::Visibility::Public(_));
 
+        // Extract doc-comments.
+        let comments = doc_comments(&fn_all.attrs);
+
         // Determine if the function requires a call context
         if let Some(first_arg) = fn_all.sig.inputs.first() {
             if let syn::FnArg::Typed(syn::PatType { ref ty, .. }) = first_arg {
@@ -377,6 +449,31 @@ impl Parse for ExportedFn {
             }
         }
 
+        // Count the trailing optional parameters, i.e. `Option<T>`, which may be omitted in calls.
+        let mut optional_params = 0;
+        for arg in fn_all
+            .sig
+            .inputs
+            .iter()
+            .skip(skip_slots + if mut_receiver { 1 } else { 0 })
+            .rev()
+        {
+            let inner = match arg {
+                syn::FnArg::Typed(syn::PatType { ref ty, .. }) => optional_type(ty),
+                _ => None,
+            };
+            match inner {
+                Some(syn::Type::Reference(_)) => {
+                    return Err(syn::Error::new(
+                        arg.span(),
+                        "optional parameters cannot be references",
+                    ))
+                }
+                Some(_) => optional_params += 1,
+                None => break,
+            }
+        }
+
         // Check return type.
         if let syn::ReturnType::Type(_, ref rtype) = fn_all.sig.output {
             match flatten_type_groups(rtype.as_ref()) {
@@ -407,6 +504,8 @@ impl Parse for ExportedFn {
             pass_context,
             return_dynamic,
             mut_receiver,
+            optional_params,
+            comments,
             params: Default::default(),
         })
     }
@@ -447,6 +546,10 @@ impl ExportedFn {
         self.is_public
     }
 
+    pub(crate) fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
     pub(crate) fn span(&self) -> &proc_macro2::Span {
         &self.entire_span
     }
@@ -494,6 +597,15 @@ impl ExportedFn {
         self.signature.inputs.len() - skip
     }
 
+    pub(crate) fn optional_params(&self) -> usize {
+        self.optional_params
+    }
+
+    /// Is the argument at a particular position (not counting the call context) optional?
+    pub(crate) fn is_optional_arg(&self, index: usize) -> bool {
+        index >= self.arg_count() - self.optional_params
+    }
+
     pub(crate) fn return_type(&self) -> Option<&syn::Type> {
         if let syn::ReturnType::Type(_, ref rtype) = self.signature.output {
             Some(flatten_type_groups(rtype))
@@ -515,6 +627,14 @@ impl ExportedFn {
             ));
         }
 
+        // 2. Property getters, setters and indexers cannot have optional parameters.
+        if self.optional_params > 0 && params.special != FnSpecialAccess::None {
+            return Err(syn::Error::new(
+                self.signature.span(),
+                "property getters, setters and indexers cannot have optional parameters",
+            ));
+        }
+
         match params.special {
             // 2a. Property getters must take only the subject as an argument.
             FnSpecialAccess::Property(Property::Get(_)) if self.arg_count() != 1 => {
@@ -587,6 +707,7 @@ impl ExportedFn {
         let input_names_block = self.generate_input_names("Token");
         let input_types_block = self.generate_input_types("Token");
         let return_type_block = self.generate_return_type("Token");
+        let comments_block = self.generate_comments("Token");
         let dyn_result_fn_block = self.generate_dynamic_fn();
         quote! {
             #[allow(unused)]
@@ -598,6 +719,7 @@ impl ExportedFn {
                 #input_names_block
                 #input_types_block
                 #return_type_block
+                #comments_block
                 #dyn_result_fn_block
             }
         }
@@ -706,6 +828,19 @@ impl ExportedFn {
         }
     }
 
+    pub fn generate_comments(&self, on_type_name: &str) -> proc_macro2::TokenStream {
+        let comments_fn_name: syn::Ident = syn::Ident::new(
+            format!("{}_comments", on_type_name.to_lowercase()).as_str(),
+            self.name().span(),
+        );
+        let comments = &self.comments;
+        quote! {
+            pub fn #comments_fn_name() -> Box<[&'static str]> {
+                new_vec![#(#comments),*].into_boxed_slice()
+            }
+        }
+    }
+
     pub fn generate_impl(&self, on_type_name: &str) -> proc_macro2::TokenStream {
         let sig_name = self.name().clone();
         let name = self
@@ -739,6 +874,7 @@ impl ExportedFn {
             skip_first_arg = true;
             let first_arg = self.arg_list().next().unwrap();
             let var = syn::Ident::new("arg0", proc_macro2::Span::call_site());
+            let args_ident = syn::Ident::new("args", proc_macro2::Span::call_site());
             match first_arg {
                 syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => {
                     let arg_name = format!("{}: {}", pat.to_token_stream(), print_type(ty));
@@ -747,7 +883,7 @@ impl ExportedFn {
                         p => p,
                     };
                     let downcast_span = quote_spanned!(
-                        arg_type.span()=> &mut args[0usize].write_lock::<#arg_type>().unwrap());
+                        arg_type.span()=> &mut #args_ident[0usize].write_lock::<#arg_type>().unwrap());
                     unpack_stmts.push(
                         syn::parse2::<syn::Stmt>(quote! {
                             let #var = #downcast_span;
@@ -777,12 +913,19 @@ impl ExportedFn {
         let string_type_path = syn::parse2::<syn::Path>(quote! { String }).unwrap();
         for (i, arg) in self.arg_list().enumerate().skip(skip_first_arg as usize) {
             let var = syn::Ident::new(&format!("arg{}", i), proc_macro2::Span::call_site());
+            let args_ident = syn::Ident::new("args", proc_macro2::Span::call_site());
             let is_string;
             let is_ref;
+            let is_optional = self.is_optional_arg(i);
             match arg {
                 syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => {
-                    let arg_name = format!("{}: {}", pat.to_token_stream(), print_type(ty));
-                    let arg_type = ty.as_ref();
+                    // Optional parameters are unpacked as their inner type `T` of `Option<T>`.
+                    let arg_type = if is_optional {
+                        optional_type(ty).unwrap()
+                    } else {
+                        ty.as_ref()
+                    };
+                    let arg_name = format!("{}: {}", pat.to_token_stream(), print_type(arg_type));
                     let downcast_span = match flatten_type_groups(arg_type) {
                         syn::Type::Reference(syn::TypeReference {
                             mutability: None,
@@ -793,7 +936,7 @@ impl ExportedFn {
                                 is_string = true;
                                 is_ref = true;
                                 quote_spanned!(arg_type.span()=>
-                                               mem::take(args[#i]).take_immutable_string().unwrap())
+                                               mem::take(#args_ident[#i]).take_immutable_string().unwrap())
                             }
                             _ => panic!("internal error: why wasn't this found earlier!?"),
                         },
@@ -801,14 +944,21 @@ impl ExportedFn {
                             is_string = true;
                             is_ref = false;
                             quote_spanned!(arg_type.span()=>
-                                           mem::take(args[#i]).take_string().unwrap())
+                                           mem::take(#args_ident[#i]).take_string().unwrap())
                         }
                         _ => {
                             is_string = false;
                             is_ref = false;
                             quote_spanned!(arg_type.span()=>
-                                           mem::take(args[#i]).cast::<#arg_type>())
+                                           mem::take(#args_ident[#i]).cast::<#arg_type>())
+                        }
+                    };
+                    let downcast_span = if is_optional {
+                        quote! {
+                            if args.len() > #i { Some(#downcast_span) } else { None }
                         }
+                    } else {
+                        downcast_span
                     };
 
                     unpack_stmts.push(
@@ -874,19 +1024,43 @@ impl ExportedFn {
             }
         };
 
+        // Functions with optional parameters accept a range of argument counts.
+        let optional_params = self.optional_params();
+        let (check_arg_count, num_optional_params) = if optional_params > 0 {
+            let min_arg_count = arg_count - optional_params;
+            (
+                quote! {
+                    debug_assert!(args.len() >= #min_arg_count && args.len() <= #arg_count,
+                                  "wrong arg count: {} not in {}..={}",
+                                  args.len(), #min_arg_count, #arg_count);
+                },
+                quote! {
+                    fn num_optional_params(&self) -> usize { #optional_params }
+                },
+            )
+        } else {
+            (
+                quote! {
+                    debug_assert_eq!(args.len(), #arg_count,
+                                     "wrong arg count: {} != {}",
+                                     args.len(), #arg_count);
+                },
+                quote! {},
+            )
+        };
+
         let type_name = syn::Ident::new(on_type_name, proc_macro2::Span::call_site());
         quote! {
             impl PluginFunction for #type_name {
                 fn call(&self, context: NativeCallContext, args: &mut [&mut Dynamic]) -> Result<Dynamic, Box<EvalAltResult>> {
-                    debug_assert_eq!(args.len(), #arg_count,
-                                     "wrong arg count: {} != {}",
-                                     args.len(), #arg_count);
+                    #check_arg_count
                     #(#unpack_stmts)*
                     #return_expr
                 }
 
                 fn is_method_call(&self) -> bool { #is_method_call }
                 fn is_variadic(&self) -> bool { false }
+                #num_optional_params
                 fn clone_boxed(&self) -> Box<dyn PluginFunction> { Box::new(#type_name()) }
                 fn input_names(&self) -> Box<[&'static str]> {
                     new_vec![#(#input_type_names),*].into_boxed_slice()
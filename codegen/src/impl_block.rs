@@ -0,0 +1,186 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse::Parser, spanned::Spanned};
+
+use crate::attrs::{AttrItem, ExportInfo, ExportedParams};
+use crate::function::replace_ident;
+use crate::module::Module;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub(crate) struct ExportedImplParams {}
+
+impl ExportedParams for ExportedImplParams {
+    fn parse_stream(args: syn::parse::ParseStream) -> syn::Result<Self> {
+        Self::from_info(crate::attrs::parse_attr_items(args)?)
+    }
+
+    fn no_attrs() -> Self {
+        Default::default()
+    }
+
+    fn from_info(info: ExportInfo) -> syn::Result<Self> {
+        let ExportInfo { items: attrs, .. } = info;
+        match attrs.into_iter().next() {
+            Some(AttrItem { key, .. }) => Err(syn::Error::new(
+                key.span(),
+                format!("unknown attribute '{}'", key),
+            )),
+            None => Ok(ExportedImplParams {}),
+        }
+    }
+}
+
+/// Make sure that a method is registered into the global namespace unless specified otherwise.
+fn make_global(attr: Option<syn::Attribute>) -> syn::Result<syn::Attribute> {
+    let mut args = match attr {
+        Some(ref attr) => attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+        )?,
+        None => Default::default(),
+    };
+    let has_namespace = args.iter().any(|arg| match arg {
+        syn::Expr::Path(syn::ExprPath { path, .. }) => path
+            .get_ident()
+            .map(|i| *i == "global" || *i == "internal")
+            .unwrap_or(false),
+        _ => false,
+    });
+    if !has_namespace {
+        args.push(syn::parse2(quote! { global }).unwrap());
+    }
+    let tokens = quote! { #[rhai_fn(#args)] };
+    Ok(syn::Attribute::parse_outer.parse2(tokens)?.remove(0))
+}
+
+pub(crate) fn export_impl(
+    _params: ExportedImplParams,
+    mut item_impl: syn::ItemImpl,
+) -> syn::Result<TokenStream> {
+    if let Some((_, ref path, _)) = item_impl.trait_ {
+        return Err(syn::Error::new(
+            path.span(),
+            "trait implementations cannot be exported",
+        ));
+    }
+    if !item_impl.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_impl.generics.span(),
+            "generic implementations cannot be exported",
+        ));
+    }
+
+    let self_ty = item_impl.self_ty.as_ref().clone();
+    let self_tokens = self_ty.to_token_stream();
+    let type_ident = match self_ty {
+        syn::Type::Path(syn::TypePath { ref path, .. }) => {
+            path.segments.last().unwrap().ident.clone()
+        }
+        ref ty => return Err(syn::Error::new(ty.span(), "expecting a type name")),
+    };
+    let mod_name = syn::Ident::new(&format!("rhai_impl_{}", type_ident), type_ident.span());
+
+    let mut wrappers = Vec::new();
+
+    for item in item_impl.items.iter_mut() {
+        let method = match item {
+            syn::ImplItem::Method(method) => method,
+            _ => continue,
+        };
+
+        // Remove the #[rhai_fn] attribute, which is used for the generated wrapper function.
+        let rhai_fn_attr = method
+            .attrs
+            .iter()
+            .position(|a| a.path.get_ident().map(|i| *i == "rhai_fn").unwrap_or(false))
+            .map(|index| method.attrs.remove(index));
+
+        if !matches!(method.vis, syn::Visibility::Public(_)) {
+            continue;
+        }
+
+        let sig = &method.sig;
+        let fn_name = &sig.ident;
+
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                sig.generics.span(),
+                "generic methods cannot be exported",
+            ));
+        }
+
+        let mut inputs = Vec::new();
+        let mut call_args = Vec::new();
+        let mut receiver = None;
+
+        for (index, arg) in sig.inputs.iter().enumerate() {
+            match arg {
+                syn::FnArg::Receiver(syn::Receiver { reference, .. }) => {
+                    inputs.push(quote! { this: &mut #self_ty });
+                    receiver = Some(if reference.is_some() {
+                        quote! { this }
+                    } else {
+                        quote! { this.clone() }
+                    });
+                }
+                syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => {
+                    let arg_name = match pat.as_ref() {
+                        syn::Pat::Ident(syn::PatIdent { ident, .. }) => ident.clone(),
+                        _ => syn::Ident::new(&format!("arg{}", index), pat.span()),
+                    };
+                    let ty = replace_ident(ty.to_token_stream(), "Self", &self_tokens);
+                    inputs.push(quote! { #arg_name: #ty });
+                    call_args.push(arg_name);
+                }
+            }
+        }
+
+        let output = replace_ident(sig.output.to_token_stream(), "Self", &self_tokens);
+
+        // Methods are always exposed globally; associated functions are namespaced.
+        let rhai_fn_attr = match receiver {
+            Some(_) => Some(make_global(rhai_fn_attr)?),
+            None => rhai_fn_attr,
+        };
+        let doc_attrs = method
+            .attrs
+            .iter()
+            .filter(|a| a.path.get_ident().map(|i| *i == "doc").unwrap_or(false));
+
+        let call = match receiver {
+            Some(this) => quote! { #this.#fn_name(#(#call_args),*) },
+            None => quote! { <#self_ty>::#fn_name(#(#call_args),*) },
+        };
+
+        wrappers.push(quote! {
+            #(#doc_attrs)*
+            #rhai_fn_attr
+            pub fn #fn_name(#(#inputs),*) #output {
+                #call
+            }
+        });
+    }
+
+    let module = syn::parse2::<Module>(quote! {
+        #[allow(non_snake_case)]
+        pub mod #mod_name {
+            use super::*;
+
+            #(#wrappers)*
+        }
+    })?;
+    let module_tokens = module.generate();
+
+    Ok(quote! {
+        #item_impl
+
+        impl #self_ty {
+            /// Create a Rhai [`Module`] containing the exported API of this type.
+            #[allow(dead_code)]
+            pub fn rhai_module_generate() -> Module {
+                #mod_name::rhai_module_generate()
+            }
+        }
+
+        #module_tokens
+    })
+}
@@ -91,7 +91,9 @@ use quote::quote;
 use syn::parse_macro_input;
 
 mod attrs;
+mod custom_type;
 mod function;
+mod impl_block;
 mod module;
 mod register;
 mod rhai_module;
@@ -184,6 +186,155 @@ pub fn export_module(
     proc_macro::TokenStream::from(tokens)
 }
 
+/// Derive macro that implements `CustomType` for a Rust struct or enum, so that it can be
+/// registered, together with its API, into an `Engine` via `Engine::build_type`.
+///
+/// For a struct, getters and setters are registered for all public fields.
+///
+/// For an enum, a static module named after the type is registered, containing one constructor
+/// per variant: a constant for a unit variant (e.g. `Status::Active`) and a function taking all
+/// fields in order for other variants (e.g. `Status::Failed(42)`).
+/// The `variant` property returns the name of the variant, which can be used in `switch`.
+/// Getters (named `field0`, `field1` etc. for tuple variants) are registered for all fields,
+/// returning `()` when the variant does not have that field.
+///
+/// # Attributes
+///
+/// On the struct:
+///
+/// * `#[rhai(name = "...")]` - set the pretty-print name of the type (defaults to the struct name)
+/// * `#[rhai(constructor)]` or `#[rhai(constructor = "...")]` - (structs only) register a
+///   constructor function (named after the type by default) taking all exposed fields as
+///   parameters in order; all other fields are set to their default values
+/// * `#[rhai(display)]` - register `to_string` and `print` via `Display`
+/// * `#[rhai(debug)]` - register `to_debug` and `debug` via `Debug`
+/// * `#[rhai(eq)]` - register `==` and `!=` via `PartialEq`
+///
+/// On a field:
+///
+/// * `#[rhai(skip)]` - do not expose the field (it is set to its default value in constructors)
+/// * `#[rhai(readonly)]` - only register a getter for the field
+/// * `#[rhai(name = "...")]` - expose the field under a different property name
+///
+/// On an enum variant:
+///
+/// * `#[rhai(skip)]` - do not register a constructor for the variant
+/// * `#[rhai(name = "...")]` - expose the variant under a different name
+///
+/// # Usage
+///
+/// ```
+/// # use rhai::{Engine, EvalAltResult};
+/// use rhai::plugin::*;
+///
+/// #[derive(Debug, Clone, PartialEq, CustomType)]
+/// #[rhai(name = "Point", constructor, eq)]
+/// pub struct Point {
+///     pub x: i64,
+///     #[rhai(readonly, name = "y_pos")]
+///     pub y: i64,
+///     #[rhai(skip)]
+///     pub tag: String,
+/// }
+///
+/// # fn main() -> Result<(), Box<EvalAltResult>> {
+/// let mut engine = Engine::new();
+///
+/// engine.build_type::<Point>();
+///
+/// assert_eq!(engine.eval::<i64>("let p = Point(40, 2); p.x + p.y_pos")?, 42);
+/// assert!(engine.eval::<bool>("Point(1, 2) == Point(1, 2)")?);
+///
+/// #[derive(Clone, CustomType)]
+/// pub enum Shape {
+///     Empty,
+///     Circle(i64),
+///     Rect { width: i64, height: i64 },
+/// }
+///
+/// engine.build_type::<Shape>();
+///
+/// assert_eq!(engine.eval::<i64>("Shape::Circle(42).field0")?, 42);
+/// assert_eq!(engine.eval::<i64>("Shape::Rect(6, 7).height")?, 7);
+/// assert_eq!(engine.eval::<String>("Shape::Empty.variant")?, "Empty");
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro_derive(CustomType, attributes(rhai))]
+pub fn derive_custom_type(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+
+    match custom_type::derive_custom_type(input) {
+        Ok(tokens) => proc_macro::TokenStream::from(tokens),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute, when put on an `impl` block of a custom type, exports the public methods and
+/// associated functions in it to a Rhai `Module`.
+///
+/// The module is created by calling `exported_module!` on the type.
+///
+/// Methods taking `self`, `&self` or `&mut self` are exposed globally, so they can be called
+/// as methods on the type.  Associated functions without `self` (e.g. constructors) are kept
+/// in the module's namespace, so they should be called with the module name as prefix
+/// (e.g. `MyType::create()`) after registering the module as a static module.
+///
+/// All `#[rhai_fn]` options, such as `name`, `get`, `set`, `return_raw` and `global`, are
+/// supported on the methods.
+///
+/// # Usage
+///
+/// ```
+/// # use rhai::{Engine, EvalAltResult};
+/// use rhai::plugin::*;
+///
+/// #[derive(Clone)]
+/// pub struct Counter {
+///     count: i64,
+/// }
+///
+/// #[export_impl]
+/// impl Counter {
+///     #[rhai_fn(name = "create")]
+///     pub fn new(count: i64) -> Self {
+///         Self { count }
+///     }
+///     pub fn increment(&mut self) {
+///         self.count += 1;
+///     }
+///     #[rhai_fn(get = "count")]
+///     pub fn get_count(&self) -> i64 {
+///         self.count
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<EvalAltResult>> {
+/// let mut engine = Engine::new();
+///
+/// engine.register_static_module("Counter", exported_module!(Counter).into());
+///
+/// assert_eq!(engine.eval::<i64>("let c = Counter::create(41); c.increment(); c.count")?, 42);
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn export_impl(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let parsed_params = match crate::attrs::outer_item_attributes(args.into(), "export_impl") {
+        Ok(args) => args,
+        Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
+    };
+    let item_impl = parse_macro_input!(input as syn::ItemImpl);
+
+    match impl_block::export_impl(parsed_params, item_impl) {
+        Ok(tokens) => proc_macro::TokenStream::from(tokens),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 /// Macro to generate a Rhai `Module` from a _plugin module_ defined via `#[export_module]`.
 ///
 /// # Usage
@@ -339,9 +490,10 @@ pub fn set_exported_fn(args: proc_macro::TokenStream) -> proc_macro::TokenStream
     };
     let gen_mod_path = crate::register::generated_module_path(&rust_modpath);
     let tokens = quote! {
-        #module_expr.set_fn(#export_name, FnNamespace::Internal, FnAccess::Public,
+        #module_expr.set_fn_with_comments(#export_name, FnNamespace::Internal, FnAccess::Public,
                             Some(#gen_mod_path::token_input_names().as_ref()),
                             #gen_mod_path::token_input_types().as_ref(),
+                            #gen_mod_path::token_comments().as_ref(),
                             #gen_mod_path::token_callable());
     };
     proc_macro::TokenStream::from(tokens)
@@ -381,9 +533,10 @@ pub fn set_exported_global_fn(args: proc_macro::TokenStream) -> proc_macro::Toke
     };
     let gen_mod_path = crate::register::generated_module_path(&rust_modpath);
     let tokens = quote! {
-        #module_expr.set_fn(#export_name, FnNamespace::Global, FnAccess::Public,
+        #module_expr.set_fn_with_comments(#export_name, FnNamespace::Global, FnAccess::Public,
                             Some(#gen_mod_path::token_input_names().as_ref()),
                             #gen_mod_path::token_input_types().as_ref(),
+                            #gen_mod_path::token_comments().as_ref(),
                             #gen_mod_path::token_callable());
     };
     proc_macro::TokenStream::from(tokens)
@@ -1,5 +1,5 @@
 use quote::{quote, ToTokens};
-use syn::{parse::Parse, parse::ParseStream};
+use syn::{parse::Parse, parse::ParseStream, parse::Parser, spanned::Spanned};
 
 use crate::function::ExportedFn;
 use crate::rhai_module::ExportedConst;
@@ -119,6 +119,9 @@ impl Parse for Module {
         let mut consts: Vec<_> = new_vec![];
         let mut submodules: Vec<_> = Vec::new();
         if let Some((_, ref mut content)) = mod_all.content {
+            // Instantiate generic functions.
+            instantiate_generic_fns(content)?;
+
             // Gather and parse functions.
             fns = content
                 .iter_mut()
@@ -203,6 +206,178 @@ impl Parse for Module {
     }
 }
 
+/// Expand each generic function marked with `#[rhai_fn(instantiate(T = type1, type2, ...))]`
+/// into one non-generic function per type, all registered under the original name.
+///
+/// The original generic function is kept as-is but not exported.
+fn instantiate_generic_fns(content: &mut Vec<syn::Item>) -> syn::Result<()> {
+    let mut instances = Vec::new();
+
+    for item in content.iter_mut() {
+        let itemfn = match item {
+            syn::Item::Fn(f) if f.sig.generics.type_params().next().is_some() => f,
+            _ => continue,
+        };
+
+        let attr_index = itemfn
+            .attrs
+            .iter()
+            .position(|a| a.path.get_ident().map(|i| *i == "rhai_fn").unwrap_or(false));
+        let mut args = match attr_index {
+            Some(index) => itemfn.attrs[index].parse_args_with(
+                syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+            )?,
+            None => Default::default(),
+        };
+
+        // Extract the instantiate(...) attribute
+        let position = args.iter().position(|arg| match arg {
+            syn::Expr::Call(syn::ExprCall { func, .. }) => match func.as_ref() {
+                syn::Expr::Path(syn::ExprPath { path, .. }) => path.is_ident("instantiate"),
+                _ => false,
+            },
+            _ => false,
+        });
+        let instantiate = match position {
+            Some(index) => {
+                let mut list: Vec<_> = args.into_iter().collect();
+                let call = list.remove(index);
+                args = list.into_iter().collect();
+                call
+            }
+            None if matches!(itemfn.vis, syn::Visibility::Public(_)) => {
+                return Err(syn::Error::new(
+                    itemfn.sig.generics.span(),
+                    "generic functions require 'instantiate(...)'",
+                ))
+            }
+            None => continue,
+        };
+        let (param, types) = parse_instantiate(&instantiate)?;
+
+        let type_params: Vec<_> = itemfn.sig.generics.type_params().collect();
+        if type_params.len() != 1 || type_params[0].ident != param {
+            return Err(syn::Error::new(
+                itemfn.sig.generics.span(),
+                "only functions with a single generic type parameter can be instantiated",
+            ));
+        }
+
+        let fn_name = &itemfn.sig.ident;
+        let has_name = args.iter().any(|arg| match arg {
+            syn::Expr::Assign(syn::ExprAssign { left, .. }) => match left.as_ref() {
+                syn::Expr::Path(syn::ExprPath { path, .. }) => {
+                    path.is_ident("name") || path.is_ident("get") || path.is_ident("set")
+                }
+                _ => false,
+            },
+            syn::Expr::Path(syn::ExprPath { path, .. }) => {
+                path.is_ident("index_get") || path.is_ident("index_set")
+            }
+            _ => false,
+        });
+        if !has_name {
+            let name = fn_name.to_string();
+            args.push(syn::parse2(quote! { name = #name }).unwrap());
+        }
+
+        let mut arg_names = Vec::new();
+        let mut inputs = Vec::new();
+        for (index, arg) in itemfn.sig.inputs.iter().enumerate() {
+            match arg {
+                syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => {
+                    let arg_name = match pat.as_ref() {
+                        syn::Pat::Ident(syn::PatIdent { ident, .. }) => ident.clone(),
+                        _ => syn::Ident::new(&format!("arg{}", index), pat.span()),
+                    };
+                    inputs.push((arg_name.clone(), ty.to_token_stream()));
+                    arg_names.push(arg_name);
+                }
+                syn::FnArg::Receiver(r) => {
+                    return Err(syn::Error::new(r.span(), "unexpected receiver"))
+                }
+            }
+        }
+
+        let doc_attrs: Vec<_> = itemfn
+            .attrs
+            .iter()
+            .filter(|a| a.path.get_ident().map(|i| *i == "doc").unwrap_or(false))
+            .cloned()
+            .collect();
+        let vis = &itemfn.vis;
+        let param_name = param.to_string();
+
+        for ty in types {
+            let ty = ty.to_token_stream();
+            let suffix: String = ty
+                .to_string()
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect();
+            let instance_name = syn::Ident::new(
+                &format!("{}_{}", fn_name, suffix.trim_matches('_')),
+                fn_name.span(),
+            );
+            let params = inputs.iter().map(|(name, arg_ty)| {
+                let arg_ty = crate::function::replace_ident(arg_ty.clone(), &param_name, &ty);
+                quote! { #name: #arg_ty }
+            });
+            let output = crate::function::replace_ident(
+                itemfn.sig.output.to_token_stream(),
+                &param_name,
+                &ty,
+            );
+
+            instances.push(syn::parse2::<syn::Item>(quote! {
+                #(#doc_attrs)*
+                #[rhai_fn(#args)]
+                #vis fn #instance_name(#(#params),*) #output {
+                    #fn_name::<#ty>(#(#arg_names),*)
+                }
+            })?);
+        }
+
+        // Do not export the generic function itself
+        let skip_attr = syn::Attribute::parse_outer
+            .parse2(quote! { #[rhai_fn(skip)] })?
+            .remove(0);
+        match attr_index {
+            Some(index) => itemfn.attrs[index] = skip_attr,
+            None => itemfn.attrs.push(skip_attr),
+        }
+    }
+
+    content.extend(instances);
+    Ok(())
+}
+
+/// Parse `instantiate(T = type1, type2, ...)` into the type parameter and the list of types.
+fn parse_instantiate(expr: &syn::Expr) -> syn::Result<(syn::Ident, Vec<syn::Type>)> {
+    let args = match expr {
+        syn::Expr::Call(syn::ExprCall { args, .. }) if !args.is_empty() => args,
+        _ => return Err(syn::Error::new(expr.span(), "expecting list of types")),
+    };
+
+    let mut iter = args.iter();
+    let (param, first) = match iter.next().unwrap() {
+        syn::Expr::Assign(syn::ExprAssign { left, right, .. }) => match left.as_ref() {
+            syn::Expr::Path(syn::ExprPath { path, .. }) if path.get_ident().is_some() => {
+                (path.get_ident().unwrap().clone(), right.as_ref())
+            }
+            x => return Err(syn::Error::new(x.span(), "expecting type parameter name")),
+        },
+        x => return Err(syn::Error::new(x.span(), "expecting 'T = type'")),
+    };
+
+    let types = std::iter::once(first)
+        .chain(iter)
+        .map(|ty| syn::parse2::<syn::Type>(ty.to_token_stream()))
+        .collect::<syn::Result<_>>()?;
+
+    Ok((param, types))
+}
+
 #[allow(dead_code)]
 impl Module {
     pub fn attrs(&self) -> &Vec<syn::Attribute> {
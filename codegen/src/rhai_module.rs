@@ -4,8 +4,8 @@ use quote::{quote, ToTokens};
 
 use crate::attrs::ExportScope;
 use crate::function::{
-    flatten_type_groups, print_type, ExportedFn, FnNamespaceAccess, FnSpecialAccess, FN_GET,
-    FN_IDX_GET, FN_IDX_SET, FN_SET,
+    flatten_type_groups, optional_type, print_type, ExportedFn, FnNamespaceAccess, FnSpecialAccess,
+    FN_GET, FN_IDX_GET, FN_IDX_SET, FN_SET,
 };
 use crate::module::Module;
 
@@ -83,9 +83,16 @@ pub(crate) fn generate_body(
 
         let fn_input_names: Vec<String> = function
             .arg_list()
-            .map(|fnarg| match fnarg {
+            .enumerate()
+            .map(|(i, fnarg)| match fnarg {
                 syn::FnArg::Receiver(_) => panic!("internal error: receiver fn outside impl!?"),
                 syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => {
+                    // Optional parameters are registered with the inner type `T` of `Option<T>`.
+                    let ty = if function.is_optional_arg(i) {
+                        optional_type(ty).unwrap()
+                    } else {
+                        ty
+                    };
                     format!("{}: {}", pat.to_token_stream(), print_type(ty))
                 }
             })
@@ -93,10 +100,16 @@ pub(crate) fn generate_body(
 
         let fn_input_types: Vec<syn::Expr> = function
             .arg_list()
-            .map(|fnarg| match fnarg {
+            .enumerate()
+            .map(|(i, fnarg)| match fnarg {
                 syn::FnArg::Receiver(_) => panic!("internal error: receiver fn outside impl!?"),
                 syn::FnArg::Typed(syn::PatType { ref ty, .. }) => {
-                    let arg_type = match flatten_type_groups(ty.as_ref()) {
+                    let ty = if function.is_optional_arg(i) {
+                        optional_type(ty).unwrap()
+                    } else {
+                        ty
+                    };
+                    let arg_type = match flatten_type_groups(ty) {
                         syn::Type::Reference(syn::TypeReference {
                             mutability: None,
                             ref elem,
@@ -169,14 +182,22 @@ pub(crate) fn generate_body(
                 },
                 fn_literal.span(),
             );
-            set_fn_stmts.push(
+            let comments = function.comments();
+            set_fn_stmts.push(if comments.is_empty() {
                 syn::parse2::<syn::Stmt>(quote! {
                     m.set_fn(#fn_literal, FnNamespace::#ns_str, FnAccess::Public,
                                 Some(&[#(#fn_input_names,)* #return_type]), &[#(#fn_input_types),*],
                                 #fn_token_name().into());
                 })
-                .unwrap(),
-            );
+                .unwrap()
+            } else {
+                syn::parse2::<syn::Stmt>(quote! {
+                    m.set_fn_with_comments(#fn_literal, FnNamespace::#ns_str, FnAccess::Public,
+                                Some(&[#(#fn_input_names,)* #return_type]), &[#(#fn_input_types),*],
+                                &[#(#comments),*], #fn_token_name().into());
+                })
+                .unwrap()
+            });
         }
 
         gen_fn_tokens.push(quote! {
@@ -308,6 +308,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn() -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(Dynamic::from(do_nothing()))
                 }
@@ -362,6 +365,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn(x: usize) -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(Dynamic::from(do_something(x)))
                 }
@@ -416,6 +422,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn(context: NativeCallContext, x: usize) -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(Dynamic::from(do_something(context, x)))
                 }
@@ -472,6 +481,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn() -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(return_dynamic())
                 }
@@ -562,6 +574,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn(x: usize, y: usize) -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(Dynamic::from(add_together(x, y)))
                 }
@@ -618,6 +633,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn(x: &mut usize, y: usize) -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(Dynamic::from(increment(x, y)))
                 }
@@ -673,6 +691,9 @@ mod generate_tests {
                 pub fn token_return_type() -> &'static str {
                     Token().return_type()
                 }
+                pub fn token_comments() -> Box<[&'static str]> {
+                    new_vec![].into_boxed_slice()
+                }
                 pub fn dynamic_result_fn(message: &str) -> Result<Dynamic, Box<EvalAltResult> > {
                     Ok(Dynamic::from(special_print(message)))
                 }
@@ -0,0 +1,201 @@
+use rhai::plugin::*;
+use rhai::{Engine, EvalAltResult, Scope, INT};
+
+mod point {
+    use rhai::plugin::*;
+    use rhai::INT;
+
+    #[derive(Debug, Clone, PartialEq, CustomType)]
+    #[rhai(name = "Point", constructor = "new_point", display, debug, eq)]
+    pub struct Point {
+        pub x: INT,
+        #[rhai(name = "y_pos")]
+        pub y: INT,
+        #[rhai(readonly)]
+        pub label: String,
+        #[rhai(skip)]
+        pub cache: Vec<INT>,
+        pub(crate) hidden: bool,
+    }
+
+    impl std::fmt::Display for Point {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}({}, {})", self.label, self.x, self.y)
+        }
+    }
+}
+
+#[test]
+fn custom_type_test() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.build_type::<point::Point>();
+
+    let p = engine.eval::<point::Point>(
+        r#"
+            let p = new_point(1, 2, "P");
+            p.x = 40;
+            p.x + p.y_pos;
+            p.y_pos = p.x + p.y_pos;
+            p
+        "#,
+    )?;
+    assert_eq!(p.x, 40);
+    assert_eq!(p.y, 42);
+    assert_eq!(p.label, "P");
+    assert!(p.cache.is_empty());
+    assert!(!p.hidden);
+
+    assert_eq!(
+        engine.eval::<String>(r#"type_of(new_point(1, 2, "P"))"#)?,
+        "Point"
+    );
+    assert_eq!(
+        engine.eval::<String>(r#"new_point(1, 2, "P").to_string()"#)?,
+        "P(1, 2)"
+    );
+    assert!(engine
+        .eval::<String>(r#"to_debug(new_point(1, 2, "P"))"#)?
+        .starts_with("Point { x: 1, y: 2"));
+    assert!(engine.eval::<bool>(r#"new_point(1, 2, "P") == new_point(1, 2, "P")"#)?);
+    assert!(engine.eval::<bool>(r#"new_point(1, 2, "P") != new_point(1, 3, "P")"#)?);
+
+    // Read-only, skipped and private fields have no setters or getters
+    assert!(engine
+        .eval::<()>(r#"let p = new_point(1, 2, "P"); p.label = "Q";"#)
+        .is_err());
+    assert!(engine
+        .eval::<INT>(r#"let p = new_point(1, 2, "P"); p.y"#)
+        .is_err());
+    assert!(engine
+        .eval::<INT>(r#"let p = new_point(1, 2, "P"); p.cache"#)
+        .is_err());
+    assert!(engine
+        .eval::<bool>(r#"let p = new_point(1, 2, "P"); p.hidden"#)
+        .is_err());
+
+    Ok(())
+}
+
+#[derive(Clone, CustomType)]
+#[rhai(constructor)]
+pub struct Wrapper<T: Clone + Send + Sync + 'static> {
+    pub value: T,
+}
+
+#[test]
+fn custom_type_generic_test() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.build_type::<Wrapper<INT>>();
+
+    assert_eq!(
+        engine.eval::<INT>("let w = Wrapper(21); w.value *= 2; w.value")?,
+        42
+    );
+    assert_eq!(engine.eval::<String>("type_of(Wrapper(0))")?, "Wrapper");
+
+    Ok(())
+}
+
+mod status {
+    use rhai::plugin::*;
+    use rhai::INT;
+
+    #[derive(Debug, Clone, PartialEq, CustomType)]
+    #[rhai(debug, eq)]
+    pub enum Status {
+        Active,
+        #[rhai(name = "Stopped")]
+        Inactive,
+        Failed(INT, #[rhai(name = "reason")] String),
+        Moved {
+            x: INT,
+            y: INT,
+        },
+        Resized {
+            x: INT,
+            #[rhai(skip)]
+            scale: INT,
+        },
+        #[rhai(skip)]
+        Internal,
+        #[rhai(skip)]
+        Hidden {
+            secret: INT,
+        },
+    }
+}
+
+#[test]
+fn custom_type_enum_test() -> Result<(), Box<EvalAltResult>> {
+    use status::Status;
+
+    let mut engine = Engine::new();
+    engine.build_type::<Status>();
+
+    assert_eq!(engine.eval::<Status>("Status::Active")?, Status::Active);
+    assert_eq!(engine.eval::<Status>("Status::Stopped")?, Status::Inactive);
+    assert_eq!(
+        engine.eval::<Status>(r#"Status::Failed(42, "oops")"#)?,
+        Status::Failed(42, "oops".into())
+    );
+    assert_eq!(
+        engine.eval::<Status>("Status::Moved(1, 2)")?,
+        Status::Moved { x: 1, y: 2 }
+    );
+    assert_eq!(
+        engine.eval::<Status>("Status::Resized(1)")?,
+        Status::Resized { x: 1, scale: 0 }
+    );
+    assert!(engine.eval::<Status>("Status::Internal").is_err());
+
+    // Skipped variants expose neither their name nor their fields
+    let mut scope = Scope::new();
+    scope.push("h", Status::Hidden { secret: 42 });
+    assert_eq!(
+        engine.eval_with_scope::<String>(&mut scope, "h.variant")?,
+        ""
+    );
+    assert!(engine
+        .eval_with_scope::<INT>(&mut scope, "h.secret")
+        .is_err());
+
+    assert_eq!(engine.eval::<String>("type_of(Status::Active)")?, "Status");
+    assert_eq!(
+        engine.eval::<String>(r#"Status::Failed(42, "oops").variant"#)?,
+        "Failed"
+    );
+    assert_eq!(
+        engine.eval::<String>(r#"Status::Failed(42, "oops").reason"#)?,
+        "oops"
+    );
+    assert_eq!(
+        engine.eval::<INT>(r#"Status::Failed(42, "oops").field0"#)?,
+        42
+    );
+    assert_eq!(
+        engine.eval::<INT>("let s = Status::Moved(1, 2); s.x + s.y")?,
+        3
+    );
+    assert_eq!(engine.eval::<INT>("Status::Resized(1).x")?, 1);
+    assert_eq!(engine.eval::<()>("Status::Active.x")?, ());
+
+    assert!(engine.eval::<bool>("Status::Moved(1, 2) == Status::Moved(1, 2)")?);
+    assert!(engine.eval::<bool>("Status::Active != Status::Stopped")?);
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let s = Status::Moved(1, 2);
+
+                switch s.variant {
+                    "Active" => 0,
+                    "Moved" => s.x * 10 + s.y,
+                    _ => 99
+                }
+            "#
+        )?,
+        12
+    );
+
+    Ok(())
+}
@@ -175,3 +175,79 @@ fn raw_returning_fn_test() -> Result<(), Box<EvalAltResult>> {
     );
     Ok(())
 }
+
+pub mod optional_params_fn {
+    use rhai::plugin::*;
+    use rhai::INT;
+
+    #[export_fn]
+    pub fn add_all(x: INT, y: Option<INT>, z: Option<INT>) -> INT {
+        x + y.unwrap_or(1) + z.unwrap_or(10)
+    }
+}
+
+#[test]
+fn optional_params_fn_test() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    let mut m = Module::new();
+    rhai::set_exported_fn!(m, "add_all", optional_params_fn::add_all);
+    engine.register_static_module("Math", m.into());
+
+    assert_eq!(engine.eval::<rhai::INT>("Math::add_all(100)")?, 111);
+    assert_eq!(engine.eval::<rhai::INT>("Math::add_all(100, 2)")?, 112);
+    assert_eq!(engine.eval::<rhai::INT>("Math::add_all(100, 2, 3)")?, 105);
+    assert!(engine.eval::<rhai::INT>("Math::add_all()").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn optional_params_fn_capabilities_test() -> Result<(), Box<EvalAltResult>> {
+    use optional_params_fn::rhai_fn_add_all;
+    use rhai::{Capabilities, EvalOptions, Scope, INT};
+
+    let mut engine = Engine::new();
+    let mut m = Module::new();
+    let hash = m.set_fn(
+        "add_all",
+        FnNamespace::Internal,
+        FnAccess::Public,
+        None,
+        rhai_fn_add_all::token_input_types().as_ref(),
+        rhai_fn_add_all::token_callable(),
+    );
+    m.update_fn_capabilities(hash, &["math"]);
+    engine.register_static_module("Math", m.into());
+
+    // The versions taking fewer arguments require the same capabilities
+    let denied = Capabilities::new();
+
+    for script in &[
+        "Math::add_all(100)",
+        "Math::add_all(100, 2)",
+        "Math::add_all(100, 2, 3)",
+    ] {
+        let ast = engine.compile(script)?;
+        let err = engine
+            .eval_ast_with_scope_and_options::<INT>(
+                &mut Scope::new(),
+                &ast,
+                EvalOptions::new().with_capabilities(denied.clone()),
+            )
+            .expect_err("should error");
+        assert!(matches!(*err, EvalAltResult::ErrorPermissionDenied(..)));
+    }
+
+    let granted = Capabilities::new().grant("math");
+    let ast = engine.compile("Math::add_all(100)")?;
+    assert_eq!(
+        engine.eval_ast_with_scope_and_options::<INT>(
+            &mut Scope::new(),
+            &ast,
+            EvalOptions::new().with_capabilities(granted)
+        )?,
+        111
+    );
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use rhai::plugin::*;
+use rhai::{Engine, EvalAltResult, INT};
+
+pub mod counter {
+    use rhai::plugin::*;
+    use rhai::INT;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Counter {
+        count: INT,
+        step: INT,
+    }
+
+    #[export_impl]
+    impl Counter {
+        /// Create a new counter.
+        #[rhai_fn(name = "create")]
+        pub fn new(count: INT) -> Self {
+            Self { count, step: 1 }
+        }
+        #[rhai_fn(name = "with_step")]
+        pub fn new_with_step(count: INT, step: INT) -> Self {
+            Self { count, step }
+        }
+        #[rhai_fn(global)]
+        pub fn make_counter() -> Counter {
+            Self::new(0)
+        }
+        pub fn increment(&mut self) {
+            self.count += self.step;
+        }
+        pub fn plus(&self, value: INT) -> INT {
+            self.count + value
+        }
+        pub fn doubled(self) -> Self {
+            Self {
+                count: self.count * 2,
+                ..self
+            }
+        }
+        #[rhai_fn(get = "count")]
+        pub fn get_count(&self) -> INT {
+            self.count
+        }
+        #[rhai_fn(set = "count")]
+        pub fn set_count(&mut self, count: INT) {
+            self.count = count;
+        }
+        #[rhai_fn(return_raw)]
+        pub fn checked_step(&mut self, step: INT) -> Result<Dynamic, Box<EvalAltResult>> {
+            if step <= 0 {
+                Err("step must be positive".into())
+            } else {
+                self.step = step;
+                Ok(Dynamic::UNIT)
+            }
+        }
+        #[rhai_fn(internal)]
+        pub fn reset(this: &mut Self) {
+            this.count = 0;
+        }
+        #[rhai_fn(skip)]
+        pub fn hidden(&self) -> INT {
+            self.private()
+        }
+        fn private(&self) -> INT {
+            self.step
+        }
+    }
+}
+
+#[test]
+fn export_impl_test() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.register_static_module("Counter", exported_module!(counter::Counter).into());
+
+    assert_eq!(
+        engine.eval::<INT>("let c = Counter::create(40); c.increment(); c.increment(); c.count")?,
+        42
+    );
+    assert_eq!(
+        engine.eval::<INT>("let c = Counter::with_step(0, 21); c.increment(); c.plus(21)")?,
+        42
+    );
+    assert_eq!(
+        engine.eval::<counter::Counter>("let c = make_counter(); c.count = 21; c.doubled()")?,
+        counter::Counter::new(42)
+    );
+    assert_eq!(
+        engine.eval::<INT>(
+            "let c = Counter::create(0); c.checked_step(42); c.increment(); c.count"
+        )?,
+        42
+    );
+    assert!(matches!(
+        *engine
+            .eval::<()>("let c = Counter::create(0); c.checked_step(0);")
+            .expect_err("should error"),
+        EvalAltResult::ErrorRuntime(_, _)
+    ));
+
+    // Associated functions are namespaced
+    assert!(engine.eval::<counter::Counter>("create(1)").is_err());
+
+    // Methods marked internal are namespaced
+    assert!(engine
+        .eval::<()>("let c = Counter::create(1); c.reset();")
+        .is_err());
+    assert_eq!(
+        engine.eval::<INT>("let c = Counter::create(1); Counter::reset(c); c.count")?,
+        0
+    );
+
+    // Skipped and private methods are not exported
+    assert!(engine
+        .eval::<INT>("let c = Counter::create(1); c.hidden()")
+        .is_err());
+    assert!(engine
+        .eval::<INT>("let c = Counter::create(1); c.private()")
+        .is_err());
+
+    Ok(())
+}
@@ -417,3 +417,130 @@ fn export_all_test() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+pub mod generic_fns {
+    use rhai::plugin::*;
+
+    #[export_module]
+    pub mod generic_math {
+        use std::ops::Add;
+
+        /// Add two numbers.
+        #[rhai_fn(instantiate(T = i64, f64, u8))]
+        pub fn add<T: Add<Output = T> + Copy>(x: T, y: T) -> T {
+            x + y
+        }
+
+        #[rhai_fn(name = "push_twice", instantiate(T = i64, bool))]
+        pub fn push2<T: Clone + Send + Sync + 'static>(list: &mut rhai::Array, item: T) {
+            list.push(Dynamic::from(item.clone()));
+            list.push(Dynamic::from(item));
+        }
+
+        fn helper<T>(x: T) -> T {
+            x
+        }
+
+        pub fn identity(x: i64) -> i64 {
+            helper(x)
+        }
+    }
+}
+
+#[test]
+fn generic_fn_test() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    let m = rhai::exported_module!(crate::generic_fns::generic_math);
+    engine.register_static_module("Math", m.into());
+
+    assert_eq!(engine.eval::<INT>("Math::add(40, 2)")?, 42);
+    assert_eq!(engine.eval::<FLOAT>("Math::add(40.0, 2.5)")?, 42.5);
+    assert!(engine.eval::<INT>(r#"Math::add("a", "b")"#).is_err());
+    assert_eq!(engine.eval::<INT>("Math::identity(42)")?, 42);
+
+    let output_array = engine.eval::<Array>(
+        r#"
+        let a = [];
+        Math::push_twice(a, 1);
+        Math::push_twice(a, true);
+        a
+        "#,
+    )?;
+    assert_eq!(output_array.len(), 4);
+    assert_eq!(output_array[1].as_int().unwrap(), 1);
+    assert_eq!(output_array[3].as_bool().unwrap(), true);
+
+    let signatures = engine.gen_fn_signatures(false);
+    assert!(signatures
+        .iter()
+        .any(|s| s == "Math::add(x: u8, y: u8) -> u8"));
+    assert!(signatures
+        .iter()
+        .any(|s| s == "Math::add(x: f64, y: f64) -> f64"));
+    assert!(!signatures.iter().any(|s| s.starts_with("Math::push2")));
+
+    Ok(())
+}
+
+pub mod optional_params {
+    use rhai::plugin::*;
+
+    #[export_module]
+    pub mod text {
+        use rhai::{ImmutableString, INT};
+
+        pub fn pad(s: &mut ImmutableString, len: INT, ch: Option<char>) -> ImmutableString {
+            let ch = ch.unwrap_or(' ');
+            let mut result = s.to_string();
+            while (result.chars().count() as INT) < len {
+                result.push(ch);
+            }
+            result.into()
+        }
+
+        pub fn find(s: &str, sub: String, start: Option<INT>, end: Option<INT>) -> INT {
+            let start = start.unwrap_or(0) as usize;
+            let end = end.map(|e| e as usize).unwrap_or(s.len());
+            s[start..end]
+                .find(&sub)
+                .map(|p| (p + start) as INT)
+                .unwrap_or(-1)
+        }
+    }
+}
+
+#[test]
+fn optional_params_test() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    let m = rhai::exported_module!(crate::optional_params::text);
+    engine.register_static_module("Text", m.into());
+
+    assert_eq!(
+        engine.eval::<String>(r#"let s = "ab"; Text::pad(s, 4) + "|""#)?,
+        "ab  |"
+    );
+    assert_eq!(
+        engine.eval::<String>(r#"let s = "ab"; Text::pad(s, 4, '*')"#)?,
+        "ab**"
+    );
+    assert_eq!(engine.eval::<INT>(r#"Text::find("abcabc", "c")"#)?, 2);
+    assert_eq!(engine.eval::<INT>(r#"Text::find("abcabc", "c", 3)"#)?, 5);
+    assert_eq!(
+        engine.eval::<INT>(r#"Text::find("abcabc", "c", 3, 4)"#)?,
+        -1
+    );
+    assert!(engine.eval::<INT>(r#"Text::find("abcabc")"#).is_err());
+
+    let signatures = engine.gen_fn_signatures(false);
+    assert!(signatures
+        .iter()
+        .any(|s| s == "Text::find(s: &str, sub: String) -> INT"));
+    assert!(signatures
+        .iter()
+        .any(|s| s == "Text::find(s: &str, sub: String, start: INT, end: INT) -> INT"));
+    assert!(signatures
+        .iter()
+        .any(|s| s == "Text::pad(s: &mut ImmutableString, len: INT, ch: char) -> ImmutableString"));
+
+    Ok(())
+}
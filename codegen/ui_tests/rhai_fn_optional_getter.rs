@@ -0,0 +1,29 @@
+use rhai::plugin::*;
+
+#[derive(Clone)]
+pub struct Point {
+    x: f32,
+    y: f32,
+}
+
+#[export_module]
+pub mod test_module {
+    pub use super::Point;
+
+    #[rhai_fn(get = "x")]
+    pub fn get_x(p: &mut Point, scale: Option<f32>) -> f32 {
+        p.x * scale.unwrap_or(1.0)
+    }
+}
+
+fn main() {
+    let n = Point {
+        x: 0.0,
+        y: 10.0,
+    };
+    if test_module::get_x(&mut n, None) == 0.0 {
+        println!("yes");
+    } else {
+        println!("no");
+    }
+}
@@ -0,0 +1,665 @@
+//! Module implementing the debug adapter.
+
+use crate::transport::{read_message, write_message};
+use rhai::{
+    Array, BreakPoint, Debugger, DebuggerCommand, DebuggerEvent, Dynamic, Engine, EvalAltResult,
+    EvalContext, Map, Position, Scope,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// ID of the only thread.
+const THREAD_ID: i64 = 1;
+/// Filter of the exception break-point that stops on errors.
+const ERROR_FILTER: &str = "error";
+/// Variable references at or above this value refer to expanded arrays and object maps.
+/// Variable references below it refer to the scope of a stack frame (frame ID + 1).
+const VALUE_REF_BASE: i64 = 1_000_000;
+
+/// A stream of messages to and from the client.
+struct Connection {
+    /// Input stream.
+    input: Box<dyn BufRead + Send>,
+    /// Output stream.
+    output: Box<dyn Write + Send>,
+    /// Sequence number of the last message sent.
+    seq: i64,
+}
+
+impl Connection {
+    /// Send a message, filling in its sequence number.
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.output, &message)
+    }
+    /// Send the response to a request.
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) -> io::Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+
+        match result {
+            Ok(Value::Null) => (),
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+
+        self.send(response)
+    }
+    /// Send an event.
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// State of the debugging session, shared with the debugger callbacks.
+#[derive(Default)]
+struct Session {
+    /// Connection to the client.
+    connection: Option<Connection>,
+    /// Path of the script to debug.
+    program: Option<PathBuf>,
+    /// Has the client finished configuring the session?
+    configured: bool,
+    /// Stop at the first statement?
+    stop_on_entry: bool,
+    /// Is execution about to stop at the first statement?
+    entry_pending: bool,
+    /// Stop when an error is raised?
+    break_on_error: bool,
+    /// Lines of break-points, keyed by source path.
+    line_break_points: BTreeMap<String, Vec<usize>>,
+    /// Names of functions to break at.
+    function_break_points: Vec<String>,
+    /// Arrays and object maps shown in the variables view, indexed by variable reference.
+    values: Vec<Dynamic>,
+    /// Has the client disconnected?
+    disconnected: bool,
+}
+
+impl Session {
+    /// Get the connection to the client.
+    fn connection(&mut self) -> io::Result<&mut Connection> {
+        self.connection
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "not connected"))
+    }
+    /// Read the next request.
+    fn read(&mut self) -> io::Result<Option<Value>> {
+        read_message(&mut self.connection()?.input)
+    }
+    /// Set up the break-points of a [`Debugger`].
+    fn apply_break_points(&self, debugger: &mut Debugger) {
+        let break_points = debugger.break_points_mut();
+        break_points.clear();
+
+        self.line_break_points.iter().for_each(|(source, lines)| {
+            break_points.extend(lines.iter().map(|&line| BreakPoint::AtPosition {
+                source: Some(source.as_str().into()),
+                line,
+                enabled: true,
+            }))
+        });
+
+        break_points.extend(
+            self.function_break_points
+                .iter()
+                .map(|name| BreakPoint::AtFunctionName {
+                    name: name.as_str().into(),
+                    enabled: true,
+                }),
+        );
+
+        debugger.set_break_on_error(self.break_on_error);
+    }
+    /// Handle a request that is valid whether or not the script is paused.
+    ///
+    /// Returns [`None`] if the request is not handled.
+    fn handle_common(&mut self, request: &Value) -> Option<Result<Value, String>> {
+        let args = &request["arguments"];
+
+        Some(Ok(match request["command"].as_str().unwrap_or_default() {
+            "initialize" => json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsFunctionBreakpoints": true,
+                "supportsEvaluateForHovers": true,
+                "exceptionBreakpointFilters": [
+                    { "filter": ERROR_FILTER, "label": "Errors", "default": false }
+                ]
+            }),
+            "setBreakpoints" => {
+                let path = match args["source"]["path"].as_str() {
+                    Some(path) => normalize_path(path),
+                    None => return Some(Err("missing source path".to_string())),
+                };
+                let lines: Vec<_> = args["breakpoints"]
+                    .as_array()
+                    .map(|bps| bps.iter().filter_map(|bp| bp["line"].as_u64()).collect())
+                    .unwrap_or_default();
+
+                let break_points: Vec<_> = lines
+                    .iter()
+                    .map(|line| json!({ "verified": true, "line": line }))
+                    .collect();
+
+                self.line_break_points
+                    .insert(path, lines.into_iter().map(|line| line as usize).collect());
+
+                json!({ "breakpoints": break_points })
+            }
+            "setFunctionBreakpoints" => {
+                self.function_break_points = args["breakpoints"]
+                    .as_array()
+                    .map(|bps| {
+                        bps.iter()
+                            .filter_map(|bp| bp["name"].as_str().map(|s| s.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let break_points: Vec<_> = self
+                    .function_break_points
+                    .iter()
+                    .map(|_| json!({ "verified": true }))
+                    .collect();
+
+                json!({ "breakpoints": break_points })
+            }
+            "setExceptionBreakpoints" => {
+                self.break_on_error = args["filters"]
+                    .as_array()
+                    .map_or(false, |filters| filters.iter().any(|f| f == ERROR_FILTER));
+                Value::Null
+            }
+            "threads" => json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+            _ => return None,
+        }))
+    }
+}
+
+/// Lock the shared session state.
+fn lock(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    session.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// A debug adapter for Rhai scripts.
+///
+/// The adapter is backed by an [`Engine`] which is used to run the script being debugged.
+/// Hosts that register their own API should pass their own pre-configured [`Engine`] to
+/// [`Adapter::new`].
+///
+/// Requests from the client are only processed while the script is not running or is paused.
+pub struct Adapter {
+    /// The scripting [`Engine`].
+    engine: Engine,
+    /// State of the debugging session.
+    session: Arc<Mutex<Session>>,
+}
+
+impl Adapter {
+    /// Create a new [`Adapter`] backed by an [`Engine`].
+    ///
+    /// The debugger and the `print` and `debug` callbacks of the [`Engine`] are replaced.
+    pub fn new(mut engine: Engine) -> Self {
+        let session: Arc<Mutex<Session>> = Default::default();
+
+        let init_session = session.clone();
+        let pause_session = session.clone();
+        let print_session = session.clone();
+        let debug_session = session.clone();
+
+        engine
+            .register_debugger(
+                move |debugger| {
+                    let session = lock(&init_session);
+                    session.apply_break_points(debugger);
+                    if session.stop_on_entry {
+                        debugger.break_at_next_statement();
+                    }
+                },
+                move |context, event, pos| pause(&pause_session, context, event, pos),
+            )
+            .on_print(move |s| output(&print_session, "stdout", s))
+            .on_debug(move |s, source, pos| {
+                let text = match source {
+                    Some(source) => format!("{} @ {:?} > {}", source, pos, s),
+                    None => format!("{:?} > {}", pos, s),
+                };
+                output(&debug_session, "console", &text)
+            });
+
+        Self { engine, session }
+    }
+    /// The [`Engine`] backing this [`Adapter`].
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+    /// Run the adapter over stdin/stdout until the client disconnects.
+    pub fn run_stdio(&mut self) -> io::Result<()> {
+        self.run(BufReader::new(io::stdin()), io::stdout())
+    }
+    /// Run the adapter over a pair of streams until the client disconnects
+    /// or the input stream is closed.
+    pub fn run(
+        &mut self,
+        input: impl BufRead + Send + 'static,
+        output: impl Write + Send + 'static,
+    ) -> io::Result<()> {
+        lock(&self.session).connection = Some(Connection {
+            input: Box::new(input),
+            output: Box::new(output),
+            seq: 0,
+        });
+
+        loop {
+            let mut session = lock(&self.session);
+
+            let request = match session.read()? {
+                Some(request) => request,
+                None => break,
+            };
+
+            let args = &request["arguments"];
+
+            let result = match session.handle_common(&request) {
+                Some(result) => result,
+                None => match request["command"].as_str().unwrap_or_default() {
+                    "launch" => match args["program"].as_str() {
+                        Some(program) => {
+                            session.program = Some(normalize_path(program).into());
+                            session.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+                            Ok(Value::Null)
+                        }
+                        None => Err("missing program".to_string()),
+                    },
+                    "configurationDone" => {
+                        session.configured = true;
+                        Ok(Value::Null)
+                    }
+                    "disconnect" => {
+                        session.disconnected = true;
+                        Ok(Value::Null)
+                    }
+                    command => Err(format!("unsupported request: {}", command)),
+                },
+            };
+
+            let conn = session.connection()?;
+            conn.respond(&request, result)?;
+
+            match request["command"].as_str() {
+                Some("initialize") => conn.event("initialized", json!({}))?,
+                Some("disconnect") => break,
+                _ => (),
+            }
+
+            if session.configured && session.program.is_some() {
+                session.configured = false;
+                let program = session.program.take().unwrap();
+                drop(session);
+
+                self.run_program(&program)?;
+
+                if lock(&self.session).disconnected {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+    /// Run a script under the debugger.
+    fn run_program(&self, program: &Path) -> io::Result<()> {
+        {
+            let mut session = lock(&self.session);
+            session.entry_pending = session.stop_on_entry;
+        }
+
+        let result = self.engine.compile_file(program.into()).and_then(|mut ast| {
+            ast.set_source(Some(program.to_string_lossy().as_ref()));
+            self.engine.consume_ast(&ast)
+        });
+
+        let mut session = lock(&self.session);
+
+        if session.disconnected {
+            return Ok(());
+        }
+
+        let conn = session.connection()?;
+
+        if let Err(ref err) = result {
+            conn.event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{}\n", err) }),
+            )?;
+        }
+
+        conn.event(
+            "exited",
+            json!({ "exitCode": if result.is_ok() { 0 } else { 1 } }),
+        )?;
+        conn.event("terminated", json!({}))
+    }
+}
+
+/// Send an `output` event.
+fn output(session: &Mutex<Session>, category: &str, text: &str) {
+    if let Ok(conn) = lock(session).connection() {
+        // Output is best-effort - errors surface on the next request
+        let _ = conn.event(
+            "output",
+            json!({ "category": category, "output": format!("{}\n", text) }),
+        );
+    }
+}
+
+/// Make an error that terminates the script.
+fn terminate(reason: impl ToString, pos: Position) -> Box<EvalAltResult> {
+    EvalAltResult::ErrorTerminated(reason.to_string().into(), pos).into()
+}
+
+/// Pause execution and process requests until the client resumes execution.
+fn pause(
+    session: &Mutex<Session>,
+    context: &mut EvalContext,
+    event: DebuggerEvent,
+    pos: Position,
+) -> Result<DebuggerCommand, Box<EvalAltResult>> {
+    let mut body = json!({ "threadId": THREAD_ID, "allThreadsStopped": true });
+
+    if let DebuggerEvent::Error(err) = event {
+        body["text"] = json!(err.to_string());
+    }
+
+    {
+        let mut session = lock(session);
+
+        body["reason"] = json!(match event {
+            _ if session.entry_pending => "entry",
+            DebuggerEvent::Step => "step",
+            DebuggerEvent::BreakPoint(index) => match context
+                .debugger()
+                .and_then(|d| d.break_points().get(index))
+            {
+                Some(BreakPoint::AtFunctionName { .. }) => "function breakpoint",
+                _ => "breakpoint",
+            },
+            DebuggerEvent::Error(_) => "exception",
+        });
+
+        session.entry_pending = false;
+        session.values.clear();
+        session
+            .connection()
+            .and_then(|conn| conn.event("stopped", body))
+            .map_err(|err| terminate(err, pos))?;
+    }
+
+    loop {
+        // Do not hold the lock while handling a request because evaluating expressions
+        // may call `print` or `debug`
+        let request = lock(session).read().map_err(|err| terminate(err, pos))?;
+
+        let request = match request {
+            Some(request) => request,
+            None => {
+                lock(session).disconnected = true;
+                return Err(terminate("disconnected", pos));
+            }
+        };
+
+        let args = &request["arguments"];
+        let mut command = None;
+
+        let common = lock(session).handle_common(&request);
+
+        let result = match common {
+            Some(result) => {
+                if let Some(debugger) = context.debugger_mut() {
+                    lock(session).apply_break_points(debugger);
+                }
+                result
+            }
+            None => match request["command"].as_str().unwrap_or_default() {
+                "stackTrace" => Ok(stack_trace(context, pos)),
+                "scopes" => {
+                    let frame_id = args["frameId"].as_i64().unwrap_or(0);
+                    Ok(json!({
+                        "scopes": [{
+                            "name": "Locals",
+                            "variablesReference": frame_id + 1,
+                            "expensive": false
+                        }]
+                    }))
+                }
+                "variables" => {
+                    let reference = args["variablesReference"].as_i64().unwrap_or(0);
+                    let variables = frame_variables(context, reference - 1)
+                        .or_else(|| expand_value(&lock(session), reference))
+                        .unwrap_or_default();
+
+                    let mut session = lock(session);
+                    let variables: Vec<_> = variables
+                        .into_iter()
+                        .map(|(name, value)| variable(&mut session, name, value))
+                        .collect();
+
+                    Ok(json!({ "variables": variables }))
+                }
+                "evaluate" => {
+                    let frame_id = args["frameId"].as_i64().unwrap_or(0);
+                    let expr = args["expression"].as_str().unwrap_or_default();
+
+                    match evaluate(context, frame_id, expr) {
+                        Ok(value) => {
+                            let mut result = variable(&mut lock(session), String::new(), value);
+                            result["result"] = result["value"].take();
+                            Ok(result)
+                        }
+                        Err(err) => Err(err),
+                    }
+                }
+                "continue" => {
+                    command = Some(DebuggerCommand::Continue);
+                    Ok(json!({ "allThreadsContinued": true }))
+                }
+                "next" => {
+                    command = Some(DebuggerCommand::StepOver);
+                    Ok(Value::Null)
+                }
+                "stepIn" => {
+                    command = Some(DebuggerCommand::StepInto);
+                    Ok(Value::Null)
+                }
+                "stepOut" => {
+                    command = Some(DebuggerCommand::StepOut);
+                    Ok(Value::Null)
+                }
+                "disconnect" => {
+                    lock(session).disconnected = true;
+                    Ok(Value::Null)
+                }
+                cmd => Err(format!("unsupported request: {}", cmd)),
+            },
+        };
+
+        let mut session = lock(session);
+
+        session
+            .connection()
+            .and_then(|conn| conn.respond(&request, result))
+            .map_err(|err| terminate(err, pos))?;
+
+        if session.disconnected {
+            return Err(terminate("disconnected", pos));
+        }
+
+        if let Some(command) = command {
+            session.values.clear();
+            return Ok(command);
+        }
+    }
+}
+
+/// Name and source of the function running in a stack frame.
+///
+/// Frame 0 is the innermost frame.
+fn frame_info(context: &EvalContext, frame_id: usize) -> (String, Option<String>) {
+    let call_stack = context.debugger().map_or(&[][..], |d| d.call_stack());
+
+    match call_stack.len().checked_sub(frame_id + 1) {
+        Some(index) => {
+            let frame = &call_stack[index];
+            (
+                frame.fn_name.to_string(),
+                frame.source.as_ref().map(|s| s.to_string()),
+            )
+        }
+        None => ("<main>".to_string(), context.source().map(|s| s.to_string())),
+    }
+}
+
+/// Handle a `stackTrace` request.
+fn stack_trace(context: &EvalContext, pos: Position) -> Value {
+    let call_stack = context.debugger().map_or(&[][..], |d| d.call_stack());
+
+    // The position of each frame is the position of the call made by it
+    let positions = Some(pos)
+        .into_iter()
+        .chain(call_stack.iter().rev().map(|frame| frame.pos));
+
+    let frames: Vec<_> = positions
+        .enumerate()
+        .map(|(id, pos)| {
+            let (name, source) = frame_info(context, id);
+            let mut frame = json!({
+                "id": id,
+                "name": name,
+                "line": pos.line().unwrap_or(0),
+                "column": pos.position().unwrap_or(0),
+            });
+            if let Some(path) = source {
+                let name = Path::new(&path)
+                    .file_name()
+                    .map_or(path.clone(), |s| s.to_string_lossy().into_owned());
+                frame["source"] = json!({ "name": name, "path": path });
+            }
+            frame
+        })
+        .collect();
+
+    json!({ "stackFrames": frames, "totalFrames": frames.len() })
+}
+
+/// Get the copy of the [`Scope`] and `this` of an outer stack frame (i.e. not the innermost one),
+/// as of the last time execution stopped in that frame.
+fn outer_frame(context: &EvalContext, frame_id: usize) -> Option<(Scope<'static>, Option<Dynamic>)> {
+    let debugger = context.debugger()?;
+    let call_stack = debugger.call_stack();
+
+    if frame_id > call_stack.len() {
+        None
+    } else if frame_id == call_stack.len() {
+        Some((debugger.global_scope().clone(), None))
+    } else {
+        let frame = &call_stack[call_stack.len() - frame_id - 1];
+        Some((frame.scope.clone(), frame.this_ptr.clone()))
+    }
+}
+
+/// Get the variables in a stack frame.
+fn frame_variables(context: &EvalContext, frame_id: i64) -> Option<Vec<(String, Dynamic)>> {
+    if frame_id < 0 || frame_id >= VALUE_REF_BASE - 1 {
+        return None;
+    }
+
+    let collect = |scope: &Scope, this_ptr: Option<Dynamic>| {
+        this_ptr
+            .map(|value| ("this".to_string(), value))
+            .into_iter()
+            .chain(scope.iter().map(|(name, _, value)| (name.to_string(), value)))
+            .collect()
+    };
+
+    if frame_id == 0 {
+        Some(collect(context.scope(), context.this_ptr().cloned()))
+    } else {
+        let (scope, this_ptr) = outer_frame(context, frame_id as usize)?;
+        Some(collect(&scope, this_ptr))
+    }
+}
+
+/// Get the items of an array or object map shown in the variables view.
+fn expand_value(session: &Session, reference: i64) -> Option<Vec<(String, Dynamic)>> {
+    let value = session.values.get((reference - VALUE_REF_BASE) as usize)?;
+
+    if value.is::<Array>() {
+        Some(
+            value
+                .clone()
+                .cast::<Array>()
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (format!("[{}]", i), v))
+                .collect(),
+        )
+    } else if value.is::<Map>() {
+        let map: BTreeMap<_, _> = value
+            .clone()
+            .cast::<Map>()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        Some(map.into_iter().collect())
+    } else {
+        None
+    }
+}
+
+/// Make a `Variable` for the variables view.
+fn variable(session: &mut Session, name: String, value: Dynamic) -> Value {
+    let reference = if value.is::<Array>() || value.is::<Map>() {
+        session.values.push(value.clone());
+        VALUE_REF_BASE + session.values.len() as i64 - 1
+    } else {
+        0
+    };
+
+    json!({
+        "name": name,
+        "value": format!("{:?}", value),
+        "type": value.type_name(),
+        "variablesReference": reference,
+    })
+}
+
+/// Evaluate an expression in a stack frame.
+fn evaluate(context: &mut EvalContext, frame_id: i64, expr: &str) -> Result<Dynamic, String> {
+    let result = if frame_id <= 0 {
+        context.eval_expression(expr)
+    } else {
+        match outer_frame(context, frame_id as usize) {
+            Some((mut scope, _)) => context
+                .engine()
+                .eval_expression_with_scope(&mut scope, expr),
+            None => return Err(format!("invalid stack frame: {}", frame_id)),
+        }
+    };
+
+    result.map_err(|err| err.to_string())
+}
+
+/// Normalize a file path so that break-points can be matched with sources.
+fn normalize_path(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
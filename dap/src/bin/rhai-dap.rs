@@ -0,0 +1,11 @@
+use rhai::Engine;
+use rhai_dap::Adapter;
+
+use std::process::exit;
+
+fn main() {
+    if let Err(err) = Adapter::new(Engine::new()).run_stdio() {
+        eprintln!("rhai-dap: {}", err);
+        exit(1);
+    }
+}
@@ -0,0 +1,25 @@
+//! # Debug Adapter Protocol server for Rhai
+//!
+//! This crate implements a [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/)
+//! server for [Rhai](https://schungx.github.io/rhai) scripts, communicating over stdio.
+//!
+//! It is a reference implementation of a debugger built on top of
+//! [`Engine::register_debugger`][rhai::Engine::register_debugger].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rhai::{Engine, RegisterFn};
+//! use rhai_dap::Adapter;
+//!
+//! let mut engine = Engine::new();
+//! engine.register_fn("compute", |x: i64| x * 2);
+//!
+//! Adapter::new(engine).run_stdio().unwrap();
+//! ```
+
+mod adapter;
+mod transport;
+
+pub use adapter::Adapter;
+pub use transport::{read_message, write_message};
@@ -0,0 +1,177 @@
+use rhai::Engine;
+use rhai_dap::{read_message, Adapter};
+use serde_json::{json, Value};
+use std::io::{BufReader, Cursor, Write};
+use std::sync::{Arc, Mutex};
+
+/// An output stream that can be read after the adapter has finished.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run the adapter with a list of requests, returning all the messages sent back.
+fn run(requests: Vec<Value>) -> Vec<Value> {
+    let input: String = requests
+        .into_iter()
+        .enumerate()
+        .map(|(seq, mut request)| {
+            request["seq"] = json!(seq + 1);
+            request["type"] = json!("request");
+            let content = request.to_string();
+            format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+        })
+        .collect();
+
+    let output = SharedBuffer::default();
+
+    Adapter::new(Engine::new())
+        .run(BufReader::new(Cursor::new(input)), output.clone())
+        .unwrap();
+
+    let bytes = output.0.lock().unwrap().clone();
+    let mut output = BufReader::new(Cursor::new(bytes));
+    let mut messages = Vec::new();
+
+    while let Some(message) = read_message(&mut output).unwrap() {
+        messages.push(message);
+    }
+
+    messages
+}
+
+fn request(command: &str, arguments: Value) -> Value {
+    json!({ "command": command, "arguments": arguments })
+}
+
+fn response<'a>(messages: &'a [Value], command: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|m| m["type"] == "response" && m["command"] == command)
+        .collect()
+}
+
+fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+    messages
+        .iter()
+        .filter(|m| m["type"] == "event" && m["event"] == event)
+        .collect()
+}
+
+#[test]
+fn test_dap_session() {
+    let dir = std::env::temp_dir().join("rhai_dap_test_session");
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("test.rhai");
+    std::fs::write(
+        &program,
+        "fn double(x) {\n    let y = x * 2;\n    y\n}\nlet a = [1, 2];\nlet b = double(21);\nprint(b);\n",
+    )
+    .unwrap();
+    let program = program.to_string_lossy().into_owned();
+
+    let messages = run(vec![
+        request("initialize", json!({ "adapterID": "rhai" })),
+        request("launch", json!({ "program": program })),
+        request(
+            "setBreakpoints",
+            json!({ "source": { "path": program }, "breakpoints": [{ "line": 3 }] }),
+        ),
+        request("configurationDone", json!({})),
+        // Paused at the break-point
+        request("stackTrace", json!({ "threadId": 1 })),
+        request("variables", json!({ "variablesReference": 1 })),
+        request("variables", json!({ "variablesReference": 2 })),
+        request("variables", json!({ "variablesReference": 1_000_000 })),
+        request("evaluate", json!({ "expression": "y + 1", "frameId": 0 })),
+        request("evaluate", json!({ "expression": "a.len()", "frameId": 1 })),
+        request("next", json!({ "threadId": 1 })),
+        // Paused after stepping over the end of `double`
+        request("continue", json!({ "threadId": 1 })),
+        request("disconnect", json!({})),
+    ]);
+
+    assert!(response(&messages, "initialize")[0]["body"]["supportsFunctionBreakpoints"]
+        .as_bool()
+        .unwrap());
+    assert_eq!(events(&messages, "initialized").len(), 1);
+
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 2);
+    assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+    assert_eq!(stopped[1]["body"]["reason"], "step");
+
+    let frames = &response(&messages, "stackTrace")[0]["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "double");
+    assert_eq!(frames[0]["line"], 3);
+    assert_eq!(frames[1]["name"], "<main>");
+    assert_eq!(frames[1]["line"], 6);
+    assert_eq!(frames[1]["source"]["name"], "test.rhai");
+
+    let variables = response(&messages, "variables");
+    assert_eq!(
+        variables[0]["body"]["variables"],
+        json!([
+            { "name": "x", "value": "21", "type": "i64", "variablesReference": 0 },
+            { "name": "y", "value": "42", "type": "i64", "variablesReference": 0 },
+        ])
+    );
+    assert_eq!(variables[1]["body"]["variables"][0]["name"], "a");
+    assert_eq!(
+        variables[1]["body"]["variables"][0]["variablesReference"],
+        1_000_000
+    );
+    assert_eq!(variables[2]["body"]["variables"][1]["name"], "[1]");
+    assert_eq!(variables[2]["body"]["variables"][1]["value"], "2");
+
+    let results = response(&messages, "evaluate");
+    assert_eq!(results[0]["body"]["result"], "43");
+    assert_eq!(results[1]["body"]["result"], "2");
+
+    let output = events(&messages, "output");
+    assert_eq!(output[0]["body"]["output"], "42\n");
+
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+    assert_eq!(events(&messages, "terminated").len(), 1);
+    assert_eq!(response(&messages, "disconnect").len(), 1);
+}
+
+#[test]
+fn test_dap_errors_and_disconnect() {
+    let dir = std::env::temp_dir().join("rhai_dap_test_errors");
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("test.rhai");
+    std::fs::write(&program, "let x = 1;\nx.foo();\nprint(x);\n").unwrap();
+    let program = program.to_string_lossy().into_owned();
+
+    let messages = run(vec![
+        request("initialize", json!({})),
+        request("launch", json!({ "program": program, "stopOnEntry": true })),
+        request("setExceptionBreakpoints", json!({ "filters": ["error"] })),
+        request("configurationDone", json!({})),
+        // Paused on entry
+        request("continue", json!({ "threadId": 1 })),
+        // Paused on error
+        request("disconnect", json!({})),
+    ]);
+
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 2);
+    assert_eq!(stopped[0]["body"]["reason"], "entry");
+    assert_eq!(stopped[1]["body"]["reason"], "exception");
+    assert!(stopped[1]["body"]["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("Function not found: foo"));
+
+    assert_eq!(response(&messages, "disconnect").len(), 1);
+    assert!(events(&messages, "output").is_empty());
+    assert!(events(&messages, "terminated").is_empty());
+}
@@ -0,0 +1,89 @@
+use rhai::Engine;
+use rhai_doc::{render, Format, Library, Options};
+
+use std::{env, fs, path::PathBuf, process::exit};
+
+const USAGE: &str = "\
+Usage: rhai-doc [OPTIONS] <DIR>
+
+Generate documentation for all .rhai scripts under DIR.
+
+Options:
+    -o, --output <DIR>       Output directory (default: doc)
+    -f, --format <FORMAT>    Output format: html (default) or markdown
+    -m, --metadata <FILE>    Engine metadata JSON, as generated by Engine::gen_fn_metadata_to_json
+        --no-private         Do not document private functions
+    -h, --help               Print this help";
+
+fn fail(message: &str) -> ! {
+    eprintln!("rhai-doc: {}", message);
+    exit(1);
+}
+
+fn main() {
+    let mut input = None;
+    let mut output = PathBuf::from("doc");
+    let mut metadata = None;
+    let mut options = Options::new(Format::Html);
+
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .unwrap_or_else(|| fail(&format!("missing value for {}", name)))
+        };
+
+        match arg.as_str() {
+            "-o" | "--output" => output = value(&arg).into(),
+            "-f" | "--format" => {
+                options.format = match value(&arg).as_str() {
+                    "html" => Format::Html,
+                    "markdown" | "md" => Format::Markdown,
+                    format => fail(&format!("unknown format: {}", format)),
+                }
+            }
+            "-m" | "--metadata" => metadata = Some(value(&arg)),
+            "--no-private" => options.include_private = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => fail(&format!("unknown option: {}\n\n{}", arg, USAGE)),
+            _ if input.is_some() => fail(&format!("unexpected argument: {}", arg)),
+            _ => input = Some(arg),
+        }
+    }
+
+    let input = input.unwrap_or_else(|| fail(USAGE));
+
+    let mut engine = Engine::new();
+    engine.set_doc_comments(true);
+
+    let mut library = Library::new();
+
+    if let Err(err) = library.load_dir(&engine, &input) {
+        fail(&err.to_string());
+    }
+
+    if let Some(path) = metadata {
+        let json =
+            fs::read_to_string(&path).unwrap_or_else(|err| fail(&format!("{}: {}", path, err)));
+
+        if let Err(err) = library.load_metadata(&json) {
+            fail(&err.to_string());
+        }
+    }
+
+    if let Err(err) = fs::create_dir_all(&output) {
+        fail(&format!("{}: {}", output.display(), err));
+    }
+
+    for page in render(&library, &options) {
+        let path = output.join(&page.file_name);
+
+        if let Err(err) = fs::write(&path, page.content) {
+            fail(&format!("{}: {}", path.display(), err));
+        }
+    }
+}
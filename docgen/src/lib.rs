@@ -0,0 +1,43 @@
+//! # Documentation generator for Rhai script libraries
+//!
+//! This crate generates browsable documentation (in HTML or Markdown) for a directory of
+//! [Rhai](https://schungx.github.io/rhai) script modules, optionally together with the API of the
+//! [`Engine`][rhai::Engine] running them (read from the JSON generated by
+//! [`Engine::gen_fn_metadata_to_json`][rhai::Engine::gen_fn_metadata_to_json]).
+//!
+//! Each module gets its own page listing its exported variables and its functions, with their
+//! signatures, doc-comments (rendered as Markdown) and cross-links to the functions they call in
+//! other modules or in the engine API.
+//!
+//! # Example
+//!
+//! ```
+//! use rhai::Engine;
+//! use rhai_doc::{render, Format, Library, Options};
+//!
+//! let mut engine = Engine::new();
+//! engine.set_doc_comments(true);
+//!
+//! let mut library = Library::new();
+//! library.add_script(&engine, "greetings", r#"
+//!     /// Say _hello_.
+//!     fn hello(name) { "Hello, " + name }
+//! "#).unwrap();
+//!
+//! let pages = render(&library, &Options::new(Format::Html));
+//!
+//! assert_eq!(pages[0].file_name, "index.html");
+//! assert!(pages[1].content.contains("<em>hello</em>"));
+//! ```
+
+// Function bodies are inspected via the AST, which is only exposed under the `internals` feature.
+#![allow(deprecated)]
+
+mod library;
+mod render;
+
+pub use library::{
+    engine_anchor, fn_anchor, CallRef, EngineFn, Error, FnDoc, Library, LinkTarget, ScriptModule,
+    VarDoc,
+};
+pub use render::{doc_comments_to_text, module_file_name, render, Format, Options, Page};
@@ -0,0 +1,507 @@
+//! Module containing the collection of script modules and engine functions to document.
+
+use rhai::{Engine, Expr, FnAccess, ParseError, Stmt};
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the names of anonymous functions generated for closures.
+const FN_ANONYMOUS: &str = "anon$";
+
+/// File extension of Rhai scripts.
+const SCRIPT_EXTENSION: &str = "rhai";
+
+/// Error when loading a [`Library`].
+#[derive(Debug)]
+pub enum Error {
+    /// Error reading a file or directory.
+    Io(PathBuf, io::Error),
+    /// Error compiling a script.
+    Parse(PathBuf, ParseError),
+    /// Error reading engine metadata JSON.
+    Metadata(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            Self::Metadata(err) => write!(f, "invalid metadata: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A function call made by a script-defined function.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct CallRef {
+    /// Namespace of the call (e.g. the alias of an imported module), if any.
+    pub namespace: Option<String>,
+    /// Name of the function called.
+    pub name: String,
+    /// Number of arguments, not counting the object of a method call.
+    pub num_args: usize,
+}
+
+impl fmt::Display for CallRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref namespace) = self.namespace {
+            write!(f, "{}::", namespace)?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+/// A script-defined function.
+#[derive(Debug, Clone)]
+pub struct FnDoc {
+    /// Function name.
+    pub name: String,
+    /// Names of the parameters.
+    pub params: Vec<String>,
+    /// Access mode of the function.
+    pub access: FnAccess,
+    /// Doc-comments, each starting with `///` or `/**`.
+    pub comments: Vec<String>,
+    /// All function calls made by the function, without duplicates.
+    pub calls: Vec<CallRef>,
+}
+
+impl FnDoc {
+    /// Anchor of the function within its page.
+    pub fn anchor(&self) -> String {
+        fn_anchor(&self.name, self.params.len())
+    }
+    /// Signature of the function, e.g. `fn foo(x, y)`.
+    pub fn signature(&self) -> String {
+        format!(
+            "{}fn {}({})",
+            if self.access.is_private() {
+                "private "
+            } else {
+                ""
+            },
+            self.name,
+            self.params.join(", ")
+        )
+    }
+}
+
+/// A variable or constant exported by a script module.
+#[derive(Debug, Clone)]
+pub struct VarDoc {
+    /// Exported name of the variable.
+    pub name: String,
+    /// Is the variable a constant?
+    pub constant: bool,
+    /// Initial value, if it is a literal.
+    pub value: Option<String>,
+}
+
+/// A script module, i.e. a `.rhai` file.
+#[derive(Debug, Clone)]
+pub struct ScriptModule {
+    /// Name of the module, which is the path used to `import` it (without extension).
+    pub name: String,
+    /// Script-defined functions, sorted by name and number of parameters.
+    pub functions: Vec<FnDoc>,
+    /// Exported variables and constants.
+    pub variables: Vec<VarDoc>,
+    /// Imported modules, mapping aliases to module names.
+    pub imports: BTreeMap<String, String>,
+}
+
+impl ScriptModule {
+    /// Find a function by name and number of parameters.
+    pub fn get_fn(&self, name: &str, num_params: usize) -> Option<&FnDoc> {
+        self.functions
+            .iter()
+            .find(|f| f.name == name && f.params.len() == num_params)
+    }
+}
+
+/// A native function registered into an [`Engine`], read from its metadata JSON.
+#[derive(Debug, Clone)]
+pub struct EngineFn {
+    /// Function name.
+    pub name: String,
+    /// Function signature.
+    pub signature: String,
+    /// Doc-comments, each starting with `///` or `/**`.
+    pub comments: Vec<String>,
+}
+
+/// Target of a cross-link.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum LinkTarget {
+    /// A function in a script module.
+    Module(String, String),
+    /// A native function in the engine API.
+    Engine(String),
+}
+
+/// A collection of script modules, plus (optionally) the API of the [`Engine`] running them.
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+    /// Script modules, keyed by name.
+    modules: BTreeMap<String, ScriptModule>,
+    /// Native functions in the engine API, keyed by namespace (empty for global functions).
+    engine_fns: BTreeMap<String, Vec<EngineFn>>,
+}
+
+impl Library {
+    /// Create a new, empty [`Library`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Get an iterator over all script modules, in order of name.
+    pub fn modules(&self) -> impl Iterator<Item = &ScriptModule> {
+        self.modules.values()
+    }
+    /// Get a script module by name.
+    pub fn get_module(&self, name: &str) -> Option<&ScriptModule> {
+        self.modules.get(name)
+    }
+    /// Get an iterator over all native functions in the engine API, grouped by namespace
+    /// (empty for global functions).
+    pub fn engine_fns(&self) -> impl Iterator<Item = (&str, &[EngineFn])> {
+        self.engine_fns
+            .iter()
+            .map(|(namespace, fns)| (namespace.as_str(), fns.as_slice()))
+    }
+    /// Is the engine API available?
+    pub fn has_engine_fns(&self) -> bool {
+        !self.engine_fns.is_empty()
+    }
+
+    /// Compile a script and add it as a module.
+    ///
+    /// The [`Engine`] should have doc-comments enabled via
+    /// [`set_doc_comments`][Engine::set_doc_comments].
+    pub fn add_script(
+        &mut self,
+        engine: &Engine,
+        name: &str,
+        script: &str,
+    ) -> Result<&ScriptModule, ParseError> {
+        let ast = engine.compile(script)?;
+
+        let mut functions: Vec<_> = ast
+            .lib()
+            .iter_script_fn_info()
+            .filter(|(_, _, name, _, _)| !name.starts_with(FN_ANONYMOUS))
+            .map(|(_, access, name, _, fn_def)| {
+                let mut calls = Vec::new();
+                collect_calls_stmt(&fn_def.body, &mut calls);
+                calls.sort();
+                calls.dedup();
+
+                FnDoc {
+                    name: name.to_string(),
+                    params: fn_def.params.iter().map(|p| p.to_string()).collect(),
+                    access,
+                    comments: fn_def.comments.clone(),
+                    calls,
+                }
+            })
+            .collect();
+
+        functions.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| a.params.len().cmp(&b.params.len()))
+        });
+
+        let mut variables = Vec::new();
+        let mut imports = BTreeMap::new();
+
+        for stmt in ast.statements() {
+            match stmt {
+                Stmt::Let(var, expr, true, _) | Stmt::Const(var, expr, true, _) => {
+                    variables.push(VarDoc {
+                        name: var.name.to_string(),
+                        constant: matches!(stmt, Stmt::Const(_, _, _, _)),
+                        value: expr.as_ref().and_then(literal_value),
+                    })
+                }
+                Stmt::Export(list, _) => {
+                    for (var, alias) in list {
+                        let decl = ast.statements().iter().find_map(|stmt| match stmt {
+                            Stmt::Let(v, expr, _, _) if v.name == var.name => Some((false, expr)),
+                            Stmt::Const(v, expr, _, _) if v.name == var.name => Some((true, expr)),
+                            _ => None,
+                        });
+
+                        variables.push(VarDoc {
+                            name: alias.as_ref().unwrap_or(var).name.to_string(),
+                            constant: decl.map_or(false, |(constant, _)| constant),
+                            value: decl
+                                .and_then(|(_, expr)| expr.as_ref())
+                                .and_then(literal_value),
+                        });
+                    }
+                }
+                Stmt::Import(Expr::StringConstant(path, _), Some(alias), _) => {
+                    imports.insert(alias.name.to_string(), module_name(path));
+                }
+                _ => (),
+            }
+        }
+
+        let module = ScriptModule {
+            name: name.to_string(),
+            functions,
+            variables,
+            imports,
+        };
+
+        self.modules.insert(name.to_string(), module);
+        Ok(&self.modules[name])
+    }
+    /// Load all `.rhai` scripts under a directory (recursively) as modules.
+    ///
+    /// Each module is named after its path relative to the directory, without extension,
+    /// which is the same path used to `import` it with the default file module resolver.
+    pub fn load_dir(&mut self, engine: &Engine, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        find_scripts(dir, &mut files)?;
+        files.sort();
+
+        for path in files {
+            let script = fs::read_to_string(&path).map_err(|err| Error::Io(path.clone(), err))?;
+
+            let name: Vec<_> = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .with_extension("")
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+
+            self.add_script(engine, &name.join("/"), &script)
+                .map_err(|err| Error::Parse(path.clone(), err))?;
+        }
+
+        Ok(())
+    }
+    /// Load the engine API from metadata JSON, as generated by
+    /// [`Engine::gen_fn_metadata_to_json`][rhai::Engine::gen_fn_metadata_to_json].
+    pub fn load_metadata(&mut self, json: &str) -> Result<(), Error> {
+        let metadata: Value = serde_json::from_str(json).map_err(Error::Metadata)?;
+        self.add_engine_fns("", &metadata);
+        Ok(())
+    }
+    /// Add the native functions in a module of the engine metadata.
+    fn add_engine_fns(&mut self, namespace: &str, metadata: &Value) {
+        if let Some(functions) = metadata["functions"].as_array() {
+            let fns = self.engine_fns.entry(namespace.to_string()).or_default();
+
+            for f in functions {
+                if f["access"] == "private" {
+                    continue;
+                }
+                let name = match f["name"].as_str() {
+                    Some(name) => name,
+                    None => continue,
+                };
+
+                fns.push(EngineFn {
+                    name: name.to_string(),
+                    signature: f["signature"].as_str().unwrap_or(name).to_string(),
+                    comments: f["docComments"]
+                        .as_array()
+                        .map(|c| {
+                            c.iter()
+                                .filter_map(|s| s.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        if let Some(modules) = metadata["modules"].as_object() {
+            for (name, module) in modules {
+                let namespace = if namespace.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}::{}", namespace, name)
+                };
+                self.add_engine_fns(&namespace, module);
+            }
+        }
+    }
+
+    /// Resolve a function call made within a script module into a cross-link target, if possible.
+    ///
+    /// Namespaced calls are resolved via the imports of the module, falling back to static
+    /// modules in the engine API.  Other calls are resolved to functions in the same module,
+    /// falling back to global functions in the engine API.
+    pub fn resolve_call(&self, module: &ScriptModule, call: &CallRef) -> Option<LinkTarget> {
+        let engine_fn = |namespace: &str| {
+            self.engine_fns
+                .get(namespace)
+                .filter(|fns| fns.iter().any(|f| f.name == call.name))
+                .map(|_| LinkTarget::Engine(engine_anchor(namespace, &call.name)))
+        };
+
+        match call.namespace {
+            Some(ref namespace) => match module.imports.get(namespace) {
+                Some(name) => self
+                    .modules
+                    .get(name)
+                    .and_then(|m| m.get_fn(&call.name, call.num_args))
+                    .filter(|f| !f.access.is_private())
+                    .map(|f| LinkTarget::Module(name.clone(), f.anchor())),
+                None => engine_fn(namespace),
+            },
+            None => match module.get_fn(&call.name, call.num_args) {
+                Some(f) => Some(LinkTarget::Module(module.name.clone(), f.anchor())),
+                None => engine_fn(""),
+            },
+        }
+    }
+}
+
+/// Anchor of a script-defined function within its page.
+pub fn fn_anchor(name: &str, num_params: usize) -> String {
+    format!("fn.{}.{}", name, num_params)
+}
+
+/// Anchor of a native function within the engine API page.
+pub fn engine_anchor(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        format!("fn.{}", name)
+    } else {
+        format!("fn.{}.{}", namespace.replace("::", "."), name)
+    }
+}
+
+/// Normalize an `import` path into a module name.
+fn module_name(path: &str) -> String {
+    let path = path.trim_start_matches("./");
+    path.strip_suffix(".rhai").unwrap_or(path).to_string()
+}
+
+/// Find all script files under a directory, recursively.
+fn find_scripts(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let entries = fs::read_dir(dir).map_err(|err| Error::Io(dir.into(), err))?;
+
+    for entry in entries {
+        let path = entry.map_err(|err| Error::Io(dir.into(), err))?.path();
+
+        if path.is_dir() {
+            find_scripts(&path, files)?;
+        } else if path
+            .extension()
+            .map_or(false, |ext| ext == SCRIPT_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the value of a literal expression as text.
+fn literal_value(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::StringConstant(s, _) => Some(format!("{:?}", s.as_str())),
+        Expr::CharConstant(c, _) => Some(format!("{:?}", c)),
+        expr if expr.is_constant() => expr.get_constant_value().map(|v| v.to_string()),
+        _ => None,
+    }
+}
+
+/// Is a function name an identifier (i.e. not an operator)?
+fn is_ident(name: &str) -> bool {
+    name.chars().all(|ch| ch == '_' || ch.is_alphanumeric())
+}
+
+/// Collect all function calls within a statement.
+fn collect_calls_stmt(stmt: &Stmt, calls: &mut Vec<CallRef>) {
+    match stmt {
+        Stmt::If(cond, x, _) => {
+            collect_calls_expr(cond, calls);
+            collect_calls_stmt(&x.0, calls);
+            if let Some(ref stmt) = x.1 {
+                collect_calls_stmt(stmt, calls);
+            }
+        }
+        Stmt::Switch(expr, x, _) => {
+            collect_calls_expr(expr, calls);
+            x.0.values()
+                .for_each(|stmt| collect_calls_stmt(stmt, calls));
+            if let Some(ref stmt) = x.1 {
+                collect_calls_stmt(stmt, calls);
+            }
+        }
+        Stmt::While(expr, body, _) | Stmt::Do(body, expr, _, _) => {
+            collect_calls_expr(expr, calls);
+            collect_calls_stmt(body, calls);
+        }
+        Stmt::For(expr, x, _) => {
+            collect_calls_expr(expr, calls);
+            collect_calls_stmt(&x.1, calls);
+        }
+        Stmt::Let(_, Some(expr), _, _)
+        | Stmt::Const(_, Some(expr), _, _)
+        | Stmt::Expr(expr)
+        | Stmt::Return(_, Some(expr), _)
+        | Stmt::Import(expr, _, _) => collect_calls_expr(expr, calls),
+        Stmt::Assignment(x, _) => {
+            collect_calls_expr(&x.0, calls);
+            collect_calls_expr(&x.2, calls);
+        }
+        Stmt::Block(statements, _) => statements
+            .iter()
+            .for_each(|stmt| collect_calls_stmt(stmt, calls)),
+        Stmt::TryCatch(x, _, _) => {
+            collect_calls_stmt(&x.0, calls);
+            collect_calls_stmt(&x.2, calls);
+        }
+        _ => (),
+    }
+}
+
+/// Collect all function calls within an expression.
+fn collect_calls_expr(expr: &Expr, calls: &mut Vec<CallRef>) {
+    match expr {
+        Expr::Array(x, _) => x.iter().for_each(|expr| collect_calls_expr(expr, calls)),
+        Expr::Map(x, _) => x
+            .iter()
+            .for_each(|(_, expr)| collect_calls_expr(expr, calls)),
+        Expr::Stmt(x, _) => x.iter().for_each(|stmt| collect_calls_stmt(stmt, calls)),
+        Expr::FnCall(x, _) => {
+            if is_ident(&x.name) {
+                calls.push(CallRef {
+                    namespace: x.namespace.as_ref().map(|ns| {
+                        let path: Vec<_> = ns.iter().map(|m| m.name.as_str()).collect();
+                        path.join("::")
+                    }),
+                    name: x.name.to_string(),
+                    num_args: x.args.len(),
+                });
+            }
+            x.args
+                .iter()
+                .for_each(|expr| collect_calls_expr(expr, calls));
+        }
+        Expr::Dot(x, _) | Expr::Index(x, _) | Expr::In(x, _) | Expr::And(x, _) | Expr::Or(x, _) => {
+            collect_calls_expr(&x.lhs, calls);
+            collect_calls_expr(&x.rhs, calls);
+        }
+        Expr::Custom(x, _) => x
+            .keywords
+            .iter()
+            .for_each(|expr| collect_calls_expr(expr, calls)),
+        _ => (),
+    }
+}
@@ -0,0 +1,439 @@
+//! Module containing the HTML and Markdown renderers.
+
+use crate::library::{engine_anchor, EngineFn, FnDoc, Library, LinkTarget, ScriptModule};
+
+use std::fmt::Write;
+
+/// Name of the index page, without extension.
+const INDEX_PAGE: &str = "index";
+
+/// Name of the engine API page, without extension.
+const ENGINE_PAGE: &str = "engine";
+
+/// Output format of the documentation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Format {
+    /// HTML pages, with doc-comments rendered from Markdown.
+    Html,
+    /// Markdown pages, with doc-comments included verbatim.
+    Markdown,
+}
+
+impl Format {
+    /// File extension of pages in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// Options for rendering documentation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Options {
+    /// Output format.
+    pub format: Format,
+    /// Include private functions?
+    pub include_private: bool,
+}
+
+impl Options {
+    /// Create [`Options`] for a particular [format][Format], including private functions.
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            include_private: true,
+        }
+    }
+}
+
+/// A rendered page.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Page {
+    /// File name of the page, relative to the output directory.
+    pub file_name: String,
+    /// Title of the page.
+    pub title: String,
+    /// Contents of the page.
+    pub content: String,
+}
+
+/// Render the documentation of a [`Library`] into pages.
+///
+/// The pages consist of an index, one page per script module and, if engine metadata is loaded,
+/// a page for the engine API.
+pub fn render(library: &Library, options: &Options) -> Vec<Page> {
+    let renderer = Renderer { library, options };
+
+    let mut pages = vec![renderer.index_page()];
+    pages.extend(library.modules().map(|m| renderer.module_page(m)));
+
+    if library.has_engine_fns() {
+        pages.push(renderer.engine_page());
+    }
+
+    pages
+}
+
+/// Get the file name of the page of a script module.
+pub fn module_file_name(name: &str, format: Format) -> String {
+    format!("{}.{}", name.replace('/', "."), format.extension())
+}
+
+/// Renderer of a [`Library`].
+struct Renderer<'a> {
+    library: &'a Library,
+    options: &'a Options,
+}
+
+impl Renderer<'_> {
+    /// Is the output HTML?
+    fn is_html(&self) -> bool {
+        self.options.format == Format::Html
+    }
+    /// Get the file name of a page.
+    fn file_name(&self, page: &str) -> String {
+        format!("{}.{}", page, self.options.format.extension())
+    }
+    /// Wrap the body of a page into a [`Page`].
+    fn page(&self, file_name: String, title: String, body: String) -> Page {
+        let content = if self.is_html() {
+            format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                escape_html(&title),
+                STYLE,
+                body
+            )
+        } else {
+            body
+        };
+
+        Page {
+            file_name,
+            title,
+            content,
+        }
+    }
+    /// Write a heading, optionally with an anchor.
+    fn heading(&self, out: &mut String, level: usize, text: &str, anchor: Option<&str>) {
+        if self.is_html() {
+            let id = anchor.map_or(String::new(), |a| format!(" id=\"{}\"", a));
+            let _ = writeln!(out, "<h{0}{1}>{2}</h{0}>", level, id, escape_html(text));
+        } else {
+            if let Some(anchor) = anchor {
+                let _ = writeln!(out, "<a name=\"{}\"></a>\n", anchor);
+            }
+            let _ = writeln!(out, "{} {}\n", "#".repeat(level), text);
+        }
+    }
+    /// Write a code block.
+    fn code(&self, out: &mut String, code: &str) {
+        if self.is_html() {
+            let _ = writeln!(out, "<pre><code>{}</code></pre>", escape_html(code));
+        } else {
+            let _ = writeln!(out, "```rust\n{}\n```\n", code);
+        }
+    }
+    /// Write doc-comments.
+    fn comments(&self, out: &mut String, comments: &[String]) {
+        if comments.is_empty() {
+            return;
+        }
+
+        let text = doc_comments_to_text(comments);
+
+        if self.is_html() {
+            let parser = pulldown_cmark::Parser::new(&text);
+            out.push_str("<div class=\"docs\">\n");
+            pulldown_cmark::html::push_html(out, parser);
+            out.push_str("</div>\n");
+        } else {
+            let _ = writeln!(out, "{}\n", text);
+        }
+    }
+    /// Format a link.
+    fn link(&self, text: &str, href: &str) -> String {
+        if self.is_html() {
+            format!(
+                "<a href=\"{}\"><code>{}</code></a>",
+                escape_html(href),
+                escape_html(text)
+            )
+        } else {
+            format!("[`{}`]({})", text, href)
+        }
+    }
+    /// Write a list of items.
+    fn list(&self, out: &mut String, items: &[String]) {
+        if self.is_html() {
+            out.push_str("<ul>\n");
+            items.iter().for_each(|item| {
+                let _ = writeln!(out, "<li>{}</li>", item);
+            });
+            out.push_str("</ul>\n");
+        } else {
+            items.iter().for_each(|item| {
+                let _ = writeln!(out, "* {}", item);
+            });
+            out.push('\n');
+        }
+    }
+    /// Write a paragraph of text.
+    fn paragraph(&self, out: &mut String, text: &str) {
+        if self.is_html() {
+            let _ = writeln!(out, "<p>{}</p>", text);
+        } else {
+            let _ = writeln!(out, "{}\n", text);
+        }
+    }
+    /// Get the functions of a module that are to be documented.
+    fn functions<'m>(&self, module: &'m ScriptModule) -> impl Iterator<Item = &'m FnDoc> {
+        let include_private = self.options.include_private;
+        module
+            .functions
+            .iter()
+            .filter(move |f| include_private || !f.access.is_private())
+    }
+
+    /// Render the index page.
+    fn index_page(&self) -> Page {
+        let mut out = String::new();
+        let title = "Rhai Script Library".to_string();
+
+        self.heading(&mut out, 1, &title, None);
+        self.heading(&mut out, 2, "Modules", None);
+
+        let modules: Vec<_> = self
+            .library
+            .modules()
+            .map(|m| {
+                let link = self.link(&m.name, &module_file_name(&m.name, self.options.format));
+                let count = self.functions(m).count();
+                format!(
+                    "{} ({} function{})",
+                    link,
+                    count,
+                    if count == 1 { "" } else { "s" }
+                )
+            })
+            .collect();
+        self.list(&mut out, &modules);
+
+        if self.library.has_engine_fns() {
+            self.heading(&mut out, 2, "Engine API", None);
+            let link = self.link("Engine API", &self.file_name(ENGINE_PAGE));
+            self.paragraph(&mut out, &link);
+        }
+
+        self.page(self.file_name(INDEX_PAGE), title, out)
+    }
+    /// Render the page of a script module.
+    fn module_page(&self, module: &ScriptModule) -> Page {
+        let mut out = String::new();
+        let title = format!("Module {}", module.name);
+
+        let index = self.link("Index", &self.file_name(INDEX_PAGE));
+        self.paragraph(&mut out, &index);
+        self.heading(&mut out, 1, &title, None);
+
+        if !module.imports.is_empty() {
+            self.heading(&mut out, 2, "Imports", None);
+            let imports: Vec<_> = module
+                .imports
+                .iter()
+                .map(|(alias, name)| {
+                    let text = format!("{} as {}", name, alias);
+                    if self.library.get_module(name).is_some() {
+                        self.link(&text, &module_file_name(name, self.options.format))
+                    } else {
+                        self.inline_code(&text)
+                    }
+                })
+                .collect();
+            self.list(&mut out, &imports);
+        }
+
+        if !module.variables.is_empty() {
+            self.heading(&mut out, 2, "Variables", None);
+
+            for var in module.variables.iter() {
+                let decl = match var.value {
+                    Some(ref value) => format!(
+                        "{} {} = {};",
+                        if var.constant { "const" } else { "let" },
+                        var.name,
+                        value
+                    ),
+                    None => format!(
+                        "{} {};",
+                        if var.constant { "const" } else { "let" },
+                        var.name
+                    ),
+                };
+                self.code(&mut out, &decl);
+            }
+        }
+
+        let functions: Vec<_> = self.functions(module).collect();
+
+        if !functions.is_empty() {
+            self.heading(&mut out, 2, "Functions", None);
+
+            for f in functions {
+                let anchor = f.anchor();
+                self.heading(&mut out, 3, &f.name, Some(&anchor));
+                self.code(&mut out, &f.signature());
+
+                if f.access.is_private() {
+                    let badge = if self.is_html() {
+                        "<span class=\"private\">private</span>"
+                    } else {
+                        "*private*"
+                    };
+                    self.paragraph(&mut out, badge);
+                }
+
+                self.comments(&mut out, &f.comments);
+
+                let calls: Vec<_> = f
+                    .calls
+                    .iter()
+                    .filter(|call| {
+                        // Private functions are not linked to when they are not documented
+                        self.options.include_private
+                            || call.namespace.is_some()
+                            || module
+                                .get_fn(&call.name, call.num_args)
+                                .map_or(true, |f| !f.access.is_private())
+                    })
+                    .filter_map(|call| {
+                        self.library
+                            .resolve_call(module, call)
+                            .map(|target| self.link(&call.to_string(), &self.href(module, &target)))
+                    })
+                    .collect();
+
+                if !calls.is_empty() {
+                    self.paragraph(&mut out, "Calls:");
+                    self.list(&mut out, &calls);
+                }
+            }
+        }
+
+        self.page(
+            module_file_name(&module.name, self.options.format),
+            title,
+            out,
+        )
+    }
+    /// Render the engine API page.
+    fn engine_page(&self) -> Page {
+        let mut out = String::new();
+        let title = "Engine API".to_string();
+
+        let index = self.link("Index", &self.file_name(INDEX_PAGE));
+        self.paragraph(&mut out, &index);
+        self.heading(&mut out, 1, &title, None);
+
+        for (namespace, fns) in self.library.engine_fns() {
+            if fns.is_empty() {
+                continue;
+            }
+            if namespace.is_empty() {
+                self.heading(&mut out, 2, "Global Functions", None);
+            } else {
+                self.heading(&mut out, 2, &format!("Module {}", namespace), None);
+            }
+
+            let mut names: Vec<_> = fns.iter().map(|f| f.name.as_str()).collect();
+            names.sort();
+            names.dedup();
+
+            for name in names {
+                let overloads: Vec<&EngineFn> = fns.iter().filter(|f| f.name == name).collect();
+                let anchor = engine_anchor(namespace, name);
+                self.heading(&mut out, 3, name, Some(&anchor));
+
+                let signatures: Vec<_> = overloads.iter().map(|f| f.signature.as_str()).collect();
+                self.code(&mut out, &signatures.join("\n"));
+
+                for f in overloads {
+                    self.comments(&mut out, &f.comments);
+                }
+            }
+        }
+
+        self.page(self.file_name(ENGINE_PAGE), title, out)
+    }
+    /// Get the link to a cross-link target from the page of a script module.
+    fn href(&self, module: &ScriptModule, target: &LinkTarget) -> String {
+        match target {
+            LinkTarget::Module(name, anchor) if *name == module.name => format!("#{}", anchor),
+            LinkTarget::Module(name, anchor) => {
+                format!("{}#{}", module_file_name(name, self.options.format), anchor)
+            }
+            LinkTarget::Engine(anchor) => format!("{}#{}", self.file_name(ENGINE_PAGE), anchor),
+        }
+    }
+    /// Format inline code.
+    fn inline_code(&self, text: &str) -> String {
+        if self.is_html() {
+            format!("<code>{}</code>", escape_html(text))
+        } else {
+            format!("`{}`", text)
+        }
+    }
+}
+
+/// Style sheet of HTML pages.
+const STYLE: &str = "body{font-family:sans-serif;max-width:60em;margin:auto;padding:1em}\
+pre{background:#f4f4f4;padding:.5em}\
+.private{color:#fff;background:#999;border-radius:.3em;padding:0 .4em;font-size:.8em}\
+.docs{margin-left:1em}";
+
+/// Escape text for HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+/// Convert doc-comments into Markdown text by removing the comment markers.
+pub fn doc_comments_to_text(comments: &[String]) -> String {
+    comments
+        .iter()
+        .map(|comment| {
+            if let Some(line) = comment.strip_prefix("///") {
+                line.strip_prefix(' ').unwrap_or(line).to_string()
+            } else if let Some(block) = comment.strip_prefix("/**") {
+                block
+                    .strip_suffix("*/")
+                    .unwrap_or(block)
+                    .lines()
+                    .map(|line| {
+                        let line = line.trim();
+                        let line = line.strip_prefix('*').unwrap_or(line);
+                        line.strip_prefix(' ').unwrap_or(line)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .trim()
+                    .to_string()
+            } else {
+                comment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
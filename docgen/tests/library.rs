@@ -0,0 +1,195 @@
+use rhai::Engine;
+use rhai_doc::{render, CallRef, Format, Library, LinkTarget, Options};
+use serde_json::json;
+
+use std::fs;
+
+const MATH: &str = r#"
+    /// Add two numbers.
+    ///
+    /// Returns the **sum**.
+    fn add(x, y) { x + y }
+
+    /** Double a number
+        via `add`. */
+    fn double(x) { add(x, x) }
+
+    private fn helper() { 42 }
+
+    export const LIMIT = 100;
+    let internal = 1;
+    let scale = 2;
+    export scale as SCALE;
+"#;
+
+const APP: &str = r#"
+    import "utils/math" as math;
+
+    /// Run the app.
+    fn run() {
+        print(math::double(21));
+        math::helper();
+        compute(1);
+    }
+"#;
+
+fn make_library() -> Library {
+    let mut engine = Engine::new();
+    engine.set_doc_comments(true);
+
+    let mut library = Library::new();
+    library.add_script(&engine, "utils/math", MATH).unwrap();
+    library.add_script(&engine, "app", APP).unwrap();
+    library
+        .load_metadata(
+            &json!({
+                "functions": [
+                    {
+                        "name": "compute",
+                        "access": "public",
+                        "signature": "compute(x: i64) -> i64",
+                        "docComments": ["/// Compute something."]
+                    },
+                    { "name": "secret", "access": "private", "signature": "secret()" }
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+    library
+}
+
+#[test]
+fn test_doc_library() {
+    let library = make_library();
+
+    let math = library.get_module("utils/math").unwrap();
+    let names: Vec<_> = math.functions.iter().map(|f| f.signature()).collect();
+    assert_eq!(
+        names,
+        ["fn add(x, y)", "fn double(x)", "private fn helper()"]
+    );
+    assert_eq!(
+        math.functions[0].comments,
+        ["/// Add two numbers.", "///", "/// Returns the **sum**."]
+    );
+
+    let vars: Vec<_> = math
+        .variables
+        .iter()
+        .map(|v| (v.name.as_str(), v.constant, v.value.as_deref()))
+        .collect();
+    assert_eq!(
+        vars,
+        [("LIMIT", true, Some("100")), ("SCALE", false, Some("2"))]
+    );
+
+    let app = library.get_module("app").unwrap();
+    assert_eq!(app.imports["math"], "utils/math");
+
+    let run = app.get_fn("run", 0).unwrap();
+    let call = |namespace: Option<&str>, name: &str, num_args| CallRef {
+        namespace: namespace.map(str::to_string),
+        name: name.to_string(),
+        num_args,
+    };
+    assert!(run.calls.contains(&call(Some("math"), "double", 1)));
+
+    assert_eq!(
+        library.resolve_call(app, &call(Some("math"), "double", 1)),
+        Some(LinkTarget::Module(
+            "utils/math".into(),
+            "fn.double.1".into()
+        ))
+    );
+    assert_eq!(
+        library.resolve_call(app, &call(Some("math"), "helper", 0)),
+        None
+    );
+    assert_eq!(
+        library.resolve_call(app, &call(None, "compute", 1)),
+        Some(LinkTarget::Engine("fn.compute".into()))
+    );
+    assert_eq!(
+        library.resolve_call(math, &call(None, "add", 2)),
+        Some(LinkTarget::Module("utils/math".into(), "fn.add.2".into()))
+    );
+}
+
+#[test]
+fn test_doc_render_html() {
+    let library = make_library();
+    let pages = render(&library, &Options::new(Format::Html));
+
+    let files: Vec<_> = pages.iter().map(|p| p.file_name.as_str()).collect();
+    assert_eq!(
+        files,
+        ["index.html", "app.html", "utils.math.html", "engine.html"]
+    );
+
+    let math = &pages[2].content;
+    assert!(math.contains("<h3 id=\"fn.add.2\">add</h3>"));
+    assert!(math.contains("<strong>sum</strong>"));
+    assert!(math.contains("<p>Double a number\nvia <code>add</code>.</p>"));
+    assert!(math.contains("<span class=\"private\">private</span>"));
+    assert!(math.contains("<a href=\"#fn.add.2\"><code>add</code></a>"));
+    assert!(math.contains("const LIMIT = 100;"));
+
+    let app = &pages[1].content;
+    assert!(app.contains("<a href=\"utils.math.html#fn.double.1\"><code>math::double</code></a>"));
+    assert!(app.contains("<a href=\"engine.html#fn.compute\"><code>compute</code></a>"));
+    assert!(!app.contains("math::helper</code></a>"));
+
+    let engine = &pages[3].content;
+    assert!(engine.contains("compute(x: i64) -&gt; i64"));
+    assert!(engine.contains("Compute something."));
+    assert!(!engine.contains("secret"));
+}
+
+#[test]
+fn test_doc_render_markdown() {
+    let library = make_library();
+    let options = Options {
+        format: Format::Markdown,
+        include_private: false,
+    };
+    let pages = render(&library, &options);
+
+    assert_eq!(pages[0].file_name, "index.md");
+    assert!(pages[0]
+        .content
+        .contains("* [`utils/math`](utils.math.md) (2 functions)"));
+
+    let math = &pages[2].content;
+    assert!(math.contains("<a name=\"fn.add.2\"></a>\n\n### add\n"));
+    assert!(math.contains("```rust\nfn add(x, y)\n```"));
+    assert!(math.contains("Add two numbers.\n\nReturns the **sum**."));
+    assert!(!math.contains("helper"));
+}
+
+#[test]
+fn test_doc_load_dir() {
+    let dir = std::env::temp_dir().join(format!("rhai-doc-test-{}", std::process::id()));
+    fs::create_dir_all(dir.join("utils")).unwrap();
+    fs::write(dir.join("utils").join("math.rhai"), MATH).unwrap();
+    fs::write(dir.join("app.rhai"), APP).unwrap();
+    fs::write(dir.join("notes.txt"), "not a script").unwrap();
+
+    let mut engine = Engine::new();
+    engine.set_doc_comments(true);
+
+    let mut library = Library::new();
+    let result = library.load_dir(&engine, &dir);
+
+    fs::write(dir.join("broken.rhai"), "fn (").unwrap();
+    let err = Library::new().load_dir(&engine, &dir).unwrap_err();
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    result.unwrap();
+    let names: Vec<_> = library.modules().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["app", "utils/math"]);
+
+    assert!(err.to_string().contains("broken.rhai"));
+}
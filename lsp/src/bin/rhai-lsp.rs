@@ -0,0 +1,16 @@
+use rhai::Engine;
+use rhai_lsp::Server;
+
+use std::process::exit;
+
+fn main() {
+    let mut engine = Engine::new();
+
+    // Keep doc-comments for hover information
+    engine.set_doc_comments(true);
+
+    if let Err(err) = Server::new(engine).run_stdio() {
+        eprintln!("rhai-lsp: {}", err);
+        exit(1);
+    }
+}
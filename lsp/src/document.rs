@@ -0,0 +1,290 @@
+//! Module containing the analysis of a single script document.
+
+use rhai::{Engine, Expr, ParseError, Position, Stmt, AST};
+use std::iter;
+
+/// Prefix of the names of anonymous functions generated for closures.
+const FN_ANONYMOUS: &str = "anon$";
+
+/// Kind of a symbol defined in a script.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SymbolKind {
+    /// Script-defined function.
+    Function,
+    /// Variable defined via `let`.
+    Variable,
+    /// Constant defined via `const`.
+    Constant,
+    /// Module imported via `import`.
+    Module,
+}
+
+impl SymbolKind {
+    /// The LSP `SymbolKind` number.
+    pub fn lsp_symbol_kind(self) -> u64 {
+        match self {
+            Self::Function => 12,
+            Self::Variable => 13,
+            Self::Constant => 14,
+            Self::Module => 2,
+        }
+    }
+    /// The LSP `CompletionItemKind` number.
+    pub fn lsp_completion_kind(self) -> u64 {
+        match self {
+            Self::Function => 3,
+            Self::Variable => 6,
+            Self::Constant => 21,
+            Self::Module => 9,
+        }
+    }
+}
+
+/// A symbol defined at the top level of a script.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Symbol {
+    /// Name of the symbol.
+    pub name: String,
+    /// Kind of the symbol.
+    pub kind: SymbolKind,
+    /// Position of the symbol's name in the script.
+    pub pos: Position,
+    /// Short description, e.g. the signature of a function.
+    pub detail: Option<String>,
+    /// Doc-comments of the symbol, with comment markers removed.
+    pub doc: Option<String>,
+    /// Path of an imported module.
+    pub path: Option<String>,
+}
+
+/// An identifier found at a particular location in a script.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct WordAt {
+    /// Namespace qualifier in front of the identifier, if any, e.g. `foo` in `foo::bar`.
+    pub namespace: Option<String>,
+    /// The identifier.
+    pub word: String,
+    /// Is the identifier a property or method, i.e. preceded by `.`?
+    pub is_property: bool,
+}
+
+/// A script document opened in the editor.
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Text of the script.
+    text: String,
+    /// Last successfully compiled [`AST`], kept so that completion keeps working while typing.
+    ast: Option<AST>,
+    /// Error from the last compilation, if any.
+    error: Option<ParseError>,
+    /// Symbols defined in the last successfully compiled [`AST`].
+    symbols: Vec<Symbol>,
+}
+
+impl Document {
+    /// Create a new [`Document`] and compile it.
+    pub fn new(engine: &Engine, text: impl Into<String>) -> Self {
+        let mut doc = Self {
+            text: Default::default(),
+            ast: None,
+            error: None,
+            symbols: Default::default(),
+        };
+        doc.update(engine, text);
+        doc
+    }
+    /// Replace the text of the [`Document`] and recompile it.
+    pub fn update(&mut self, engine: &Engine, text: impl Into<String>) {
+        self.text = text.into();
+
+        match engine.compile(&self.text) {
+            Ok(ast) => {
+                self.symbols = collect_symbols(&ast);
+                self.ast = Some(ast);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+    /// Text of the script.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// The last successfully compiled [`AST`], if any.
+    pub fn ast(&self) -> Option<&AST> {
+        self.ast.as_ref()
+    }
+    /// Error from the last compilation, if any.
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
+    /// Symbols defined at the top level of the script.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+    /// Find a symbol by name and kind.
+    pub fn find_symbol(&self, name: &str, kinds: &[SymbolKind]) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .find(|s| s.name == name && kinds.contains(&s.kind))
+    }
+    /// Find the identifier at a location (0-based line, and character offset in UTF-16 code
+    /// units as in LSP).
+    ///
+    /// If `prefix_only` is `true`, only characters before the location are considered,
+    /// which is what completion wants.
+    pub fn word_at(&self, line: usize, character: usize, prefix_only: bool) -> Option<WordAt> {
+        let line = self.text.lines().nth(line)?;
+        let text: Vec<char> = line.chars().collect();
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+
+        let cursor = utf16_to_chars(line, character);
+
+        let mut start = cursor;
+        while start > 0 && is_ident(text[start - 1]) {
+            start -= 1;
+        }
+
+        let mut end = cursor;
+        if !prefix_only {
+            while end < text.len() && is_ident(text[end]) {
+                end += 1;
+            }
+        }
+
+        let word: String = text[start..end].iter().collect();
+
+        let mut namespace = None;
+        let mut is_property = false;
+
+        if start >= 2 && text[start - 1] == ':' && text[start - 2] == ':' {
+            let ns_end = start - 2;
+            let mut ns_start = ns_end;
+            while ns_start > 0 && is_ident(text[ns_start - 1]) {
+                ns_start -= 1;
+            }
+            if ns_start < ns_end {
+                namespace = Some(text[ns_start..ns_end].iter().collect());
+            }
+        } else if start >= 1 && text[start - 1] == '.' {
+            is_property = true;
+        }
+
+        if word.is_empty() && !prefix_only {
+            None
+        } else {
+            Some(WordAt {
+                namespace,
+                word,
+                is_property,
+            })
+        }
+    }
+}
+
+/// Number of UTF-16 code units taken by the first `chars` characters of a line.
+///
+/// Characters beyond the end of the line count as one code unit each.
+pub fn chars_to_utf16(line: &str, chars: usize) -> usize {
+    line.chars()
+        .map(char::len_utf16)
+        .chain(iter::repeat(1))
+        .take(chars)
+        .sum()
+}
+
+/// Number of characters taking up the first `units` UTF-16 code units of a line.
+///
+/// An offset in the middle of a character counts as after it.
+pub fn utf16_to_chars(line: &str, units: usize) -> usize {
+    let mut total = 0;
+
+    line.chars()
+        .take_while(|c| {
+            let before = total;
+            total += c.len_utf16();
+            before < units
+        })
+        .count()
+}
+
+/// Collect all symbols defined at the top level of an [`AST`].
+pub fn collect_symbols(ast: &AST) -> Vec<Symbol> {
+    let mut symbols: Vec<_> = ast
+        .iter_functions()
+        .filter(|f| !f.name.starts_with(FN_ANONYMOUS))
+        .map(|f| Symbol {
+            name: f.name.to_string(),
+            kind: SymbolKind::Function,
+            pos: f.pos,
+            detail: Some(format!("fn {}", f)),
+            doc: if f.comments.is_empty() {
+                None
+            } else {
+                Some(doc_comments_to_text(&f.comments))
+            },
+            path: None,
+        })
+        .collect();
+
+    // Top-level statements are only exposed via a volatile API
+    #[allow(deprecated)]
+    let statements = ast.statements();
+
+    statements.iter().for_each(|stmt| match stmt {
+        Stmt::Let(var, _, _, _) | Stmt::Const(var, _, _, _) => symbols.push(Symbol {
+            name: var.name.to_string(),
+            kind: match stmt {
+                Stmt::Const(_, _, _, _) => SymbolKind::Constant,
+                _ => SymbolKind::Variable,
+            },
+            pos: var.pos,
+            detail: None,
+            doc: None,
+            path: None,
+        }),
+        Stmt::Import(expr, Some(alias), _) => symbols.push(Symbol {
+            name: alias.name.to_string(),
+            kind: SymbolKind::Module,
+            pos: alias.pos,
+            detail: None,
+            doc: None,
+            path: match expr {
+                Expr::StringConstant(path, _) => Some(path.to_string()),
+                _ => None,
+            },
+        }),
+        _ => (),
+    });
+
+    symbols
+}
+
+/// Convert doc-comments into plain text by removing the comment markers.
+pub fn doc_comments_to_text(comments: &[&str]) -> String {
+    comments
+        .iter()
+        .map(|comment| {
+            if let Some(line) = comment.strip_prefix("///") {
+                line.strip_prefix(' ').unwrap_or(line).to_string()
+            } else if let Some(block) = comment.strip_prefix("/**") {
+                block
+                    .strip_suffix("*/")
+                    .unwrap_or(block)
+                    .lines()
+                    .map(|line| {
+                        let line = line.trim();
+                        let line = line.strip_prefix('*').unwrap_or(line);
+                        line.strip_prefix(' ').unwrap_or(line)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .trim()
+                    .to_string()
+            } else {
+                comment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
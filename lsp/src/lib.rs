@@ -0,0 +1,28 @@
+//! # Language Server Protocol server for Rhai
+//!
+//! This crate implements a [Language Server Protocol](https://microsoft.github.io/language-server-protocol/)
+//! server for [Rhai](https://schungx.github.io/rhai) scripts, communicating over stdio.
+//!
+//! The server is backed by an [`Engine`][rhai::Engine], so hosts that register their own
+//! functions, types and modules can embed the server with their own pre-configured
+//! [`Engine`][rhai::Engine] to get accurate completion and hover information.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rhai::{Engine, RegisterFn};
+//! use rhai_lsp::Server;
+//!
+//! let mut engine = Engine::new();
+//! engine.register_fn("compute", |x: i64| x * 2);
+//!
+//! Server::new(engine).run_stdio().unwrap();
+//! ```
+
+mod document;
+mod server;
+mod transport;
+
+pub use document::{Document, Symbol, SymbolKind, WordAt};
+pub use server::Server;
+pub use transport::{read_message, write_message};
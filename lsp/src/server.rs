@@ -0,0 +1,625 @@
+//! Module implementing the language server.
+
+use crate::document::{chars_to_utf16, collect_symbols, Document, Symbol, SymbolKind, WordAt};
+use crate::transport::{read_message, write_message};
+use rhai::{Engine, Position, AST};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Keywords offered for completion.
+const KEYWORDS: &[&str] = &[
+    "let", "const", "if", "else", "switch", "do", "while", "until", "loop", "for", "in", "continue",
+    "break", "return", "throw", "try", "catch", "fn", "private", "import", "export", "as", "true",
+    "false", "this",
+];
+
+/// LSP error code for a request that is not valid in the current state of the server.
+const INVALID_REQUEST: i64 = -32600;
+/// LSP error code for an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// LSP error code for invalid parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+/// LSP `CompletionItemKind` of a method.
+const COMPLETION_KIND_METHOD: u64 = 2;
+/// LSP `CompletionItemKind` of a property.
+const COMPLETION_KIND_PROPERTY: u64 = 10;
+/// LSP `CompletionItemKind` of a keyword.
+const COMPLETION_KIND_KEYWORD: u64 = 14;
+
+/// A native function registered into an [`Engine`], parsed from its signature.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct NativeFn {
+    /// Name of the static module containing the function, if any.
+    namespace: Option<String>,
+    /// Function name.
+    name: String,
+    /// Full signature of the function.
+    signature: String,
+}
+
+impl NativeFn {
+    /// Name of the property, if this function is a property getter.
+    fn property(&self) -> Option<&str> {
+        self.name.strip_prefix("get$")
+    }
+    /// Can this function be called by name, i.e. it is not an operator or a property accessor?
+    fn is_callable(&self) -> bool {
+        self.name
+            .chars()
+            .next()
+            .map_or(false, |c| c.is_alphabetic() || c == '_')
+            && !self.name.contains('$')
+    }
+}
+
+/// A language server for Rhai scripts.
+///
+/// The server is backed by an [`Engine`] which is used to compile scripts, to list the
+/// registered functions and to resolve imported modules. Hosts that register their own API
+/// should pass their own pre-configured [`Engine`] to [`Server::new`].
+#[derive(Debug)]
+pub struct Server {
+    /// The scripting [`Engine`].
+    engine: Engine,
+    /// Open documents, keyed by URI.
+    documents: HashMap<String, Document>,
+    /// Has a `shutdown` request been received?
+    shutdown: bool,
+    /// Has an `exit` notification been received?
+    exited: bool,
+}
+
+impl Server {
+    /// Create a new [`Server`] backed by an [`Engine`].
+    pub fn new(engine: Engine) -> Self {
+        Self {
+            engine,
+            documents: Default::default(),
+            shutdown: false,
+            exited: false,
+        }
+    }
+    /// The [`Engine`] backing this [`Server`].
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+    /// Get an open [`Document`] by URI.
+    pub fn document(&self, uri: &str) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+    /// Has the client asked the server to exit?
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+    /// Run the server over stdin/stdout until the client asks it to exit.
+    pub fn run_stdio(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        self.run(stdin.lock(), stdout.lock())
+    }
+    /// Run the server over a pair of streams until the client asks it to exit
+    /// or the input stream is closed.
+    pub fn run(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            for reply in self.handle(&message) {
+                write_message(&mut output, &reply)?;
+            }
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+    /// Handle one incoming message.
+    ///
+    /// Returns the messages (responses and notifications) to send back to the client.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            // After `shutdown`, only `exit` is accepted; notifications are ignored
+            "exit" => {
+                self.exited = true;
+                return Vec::new();
+            }
+            _ if self.shutdown => {
+                Err((INVALID_REQUEST, format!("server is shut down: {}", method)))
+            }
+            "initialize" => Ok(self.initialize()),
+            "initialized" => return Vec::new(),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents
+                    .insert(uri.to_string(), Document::new(&self.engine, text));
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                // Only full-text synchronization is supported, so the last change is the whole text
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+
+                match (self.documents.get_mut(uri), text) {
+                    (Some(doc), Some(text)) => doc.update(&self.engine, text),
+                    (None, Some(text)) => {
+                        self.documents
+                            .insert(uri.to_string(), Document::new(&self.engine, text));
+                    }
+                    (_, None) => return Vec::new(),
+                }
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                return vec![notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )];
+            }
+            "textDocument/completion" => self.with_position(params, Self::completion),
+            "textDocument/hover" => self.with_position(params, Self::hover),
+            "textDocument/definition" => self.with_position(params, Self::definition),
+            "textDocument/documentSymbol" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                match self.documents.get(uri) {
+                    Some(doc) => Ok(document_symbols(doc)),
+                    None => Err((INVALID_PARAMS, format!("unknown document: {}", uri))),
+                }
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method: {}", method))),
+        };
+
+        // Notifications never receive a response
+        let id = match id {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+
+        vec![match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message }
+            }),
+        }]
+    }
+
+    /// Reply to the `initialize` request with the server's capabilities.
+    fn initialize(&self) -> Value {
+        json!({
+            "capabilities": {
+                "textDocumentSync": 1,
+                "completionProvider": { "triggerCharacters": [".", ":"] },
+                "hoverProvider": true,
+                "definitionProvider": true,
+                "documentSymbolProvider": true
+            },
+            "serverInfo": {
+                "name": "rhai-lsp",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })
+    }
+
+    /// Run a request that refers to a location in a document.
+    fn with_position(
+        &self,
+        params: &Value,
+        f: impl FnOnce(&Self, &str, &Document, usize, usize) -> Value,
+    ) -> Result<Value, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let line = params["position"]["line"].as_u64();
+        let character = params["position"]["character"].as_u64();
+
+        match (self.documents.get(uri), line, character) {
+            (Some(doc), Some(line), Some(character)) => {
+                Ok(f(self, uri, doc, line as usize, character as usize))
+            }
+            (None, _, _) => Err((INVALID_PARAMS, format!("unknown document: {}", uri))),
+            _ => Err((INVALID_PARAMS, "invalid position".to_string())),
+        }
+    }
+
+    /// Make a `textDocument/publishDiagnostics` notification for a document.
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let mut diagnostics = Vec::new();
+
+        if let Some(doc) = self.documents.get(uri) {
+            if let Some(err) = doc.error() {
+                diagnostics.push(json!({
+                    "range": range(doc.text(), err.1, 1),
+                    "severity": 1,
+                    "source": "rhai",
+                    "message": err.0.to_string()
+                }));
+            } else {
+                // Check that all imported modules can be resolved
+                doc.symbols()
+                    .iter()
+                    .filter(|s| s.kind == SymbolKind::Module)
+                    .for_each(|s| {
+                        if let Some(Err(err)) = s.path.as_ref().and_then(|path| {
+                            self.engine
+                                .module_resolver()
+                                .resolve_ast(&self.engine, path, s.pos)
+                        }) {
+                            diagnostics.push(json!({
+                                "range": range(doc.text(), s.pos, s.name.chars().count()),
+                                "severity": 2,
+                                "source": "rhai",
+                                "message": err.to_string()
+                            }));
+                        }
+                    });
+            }
+        }
+
+        notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    /// All native functions registered into the [`Engine`].
+    fn native_functions(&self) -> Vec<NativeFn> {
+        self.engine
+            .gen_fn_signatures(true)
+            .into_iter()
+            .filter_map(|signature| {
+                let full_name = &signature[..signature.find('(')?];
+                let (namespace, name) = match full_name.rfind("::") {
+                    Some(index) => (
+                        Some(full_name[..index].to_string()),
+                        full_name[index + 2..].to_string(),
+                    ),
+                    None => (None, full_name.to_string()),
+                };
+                Some(NativeFn {
+                    namespace,
+                    name,
+                    signature,
+                })
+            })
+            .collect()
+    }
+
+    /// Resolve the [`AST`] of a module imported by a document under an alias.
+    fn resolve_import(&self, doc: &Document, alias: &str) -> Option<AST> {
+        let symbol = doc.find_symbol(alias, &[SymbolKind::Module])?;
+        let path = symbol.path.as_ref()?;
+
+        self.engine
+            .module_resolver()
+            .resolve_ast(&self.engine, path, symbol.pos)?
+            .ok()
+    }
+
+    /// Handle a `textDocument/completion` request.
+    fn completion(&self, _uri: &str, doc: &Document, line: usize, character: usize) -> Value {
+        let word = match doc.word_at(line, character, true) {
+            Some(word) => word,
+            None => return json!([]),
+        };
+
+        let natives = self.native_functions();
+        // Keep items ordered and unique by label
+        let mut items = BTreeMap::new();
+
+        let mut add = |label: &str, kind: u64, detail: Option<&str>, doc: Option<&str>| {
+            if label.starts_with(&word.word) && !items.contains_key(label) {
+                let mut item = json!({ "label": label, "kind": kind });
+                if let Some(detail) = detail {
+                    item["detail"] = json!(detail);
+                }
+                if let Some(doc) = doc {
+                    item["documentation"] = json!({ "kind": "markdown", "value": doc });
+                }
+                items.insert(label.to_string(), item);
+            }
+        };
+
+        if let Some(ref namespace) = word.namespace {
+            // Functions in an imported script module
+            if let Some(ast) = self.resolve_import(doc, namespace) {
+                collect_symbols(&ast)
+                    .iter()
+                    .filter(|s| s.kind == SymbolKind::Function)
+                    .for_each(|s| {
+                        add(
+                            &s.name,
+                            s.kind.lsp_completion_kind(),
+                            s.detail.as_deref(),
+                            s.doc.as_deref(),
+                        )
+                    });
+            }
+            // Functions in a static module
+            natives
+                .iter()
+                .filter(|f| f.namespace.as_ref() == Some(namespace) && f.is_callable())
+                .for_each(|f| {
+                    add(
+                        &f.name,
+                        SymbolKind::Function.lsp_completion_kind(),
+                        Some(&f.signature),
+                        None,
+                    )
+                });
+        } else if word.is_property {
+            natives
+                .iter()
+                .filter(|f| f.namespace.is_none())
+                .for_each(|f| {
+                    if let Some(prop) = f.property() {
+                        add(prop, COMPLETION_KIND_PROPERTY, Some(&f.signature), None);
+                    } else if f.is_callable() {
+                        add(&f.name, COMPLETION_KIND_METHOD, Some(&f.signature), None);
+                    }
+                });
+            doc.symbols()
+                .iter()
+                .filter(|s| s.kind == SymbolKind::Function)
+                .for_each(|s| {
+                    add(
+                        &s.name,
+                        COMPLETION_KIND_METHOD,
+                        s.detail.as_deref(),
+                        s.doc.as_deref(),
+                    )
+                });
+        } else {
+            doc.symbols().iter().for_each(|s| {
+                add(
+                    &s.name,
+                    s.kind.lsp_completion_kind(),
+                    s.detail.as_deref(),
+                    s.doc.as_deref(),
+                )
+            });
+            natives.iter().for_each(|f| match f.namespace {
+                Some(ref namespace) => add(
+                    namespace,
+                    SymbolKind::Module.lsp_completion_kind(),
+                    None,
+                    None,
+                ),
+                None if f.is_callable() => add(
+                    &f.name,
+                    SymbolKind::Function.lsp_completion_kind(),
+                    Some(&f.signature),
+                    None,
+                ),
+                None => (),
+            });
+            KEYWORDS
+                .iter()
+                .for_each(|keyword| add(keyword, COMPLETION_KIND_KEYWORD, None, None));
+        }
+
+        Value::Array(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// Handle a `textDocument/hover` request.
+    fn hover(&self, _uri: &str, doc: &Document, line: usize, character: usize) -> Value {
+        let WordAt {
+            namespace,
+            word,
+            is_property,
+        } = match doc.word_at(line, character, false) {
+            Some(word) => word,
+            None => return Value::Null,
+        };
+
+        let natives = self.native_functions();
+
+        let text = if let Some(namespace) = namespace {
+            let script_fn = self.resolve_import(doc, &namespace).and_then(|ast| {
+                collect_symbols(&ast)
+                    .into_iter()
+                    .find(|s| s.kind == SymbolKind::Function && s.name == word)
+            });
+
+            match script_fn {
+                Some(symbol) => symbol_hover(&symbol),
+                None => signatures_hover(
+                    natives
+                        .iter()
+                        .filter(|f| f.namespace.as_ref() == Some(&namespace) && f.name == word),
+                ),
+            }
+        } else if is_property {
+            signatures_hover(natives.iter().filter(|f| {
+                f.namespace.is_none() && (f.property() == Some(&word) || f.name == word)
+            }))
+        } else {
+            match doc.find_symbol(
+                &word,
+                &[
+                    SymbolKind::Function,
+                    SymbolKind::Variable,
+                    SymbolKind::Constant,
+                    SymbolKind::Module,
+                ],
+            ) {
+                Some(symbol) => symbol_hover(symbol),
+                None => signatures_hover(
+                    natives
+                        .iter()
+                        .filter(|f| f.namespace.is_none() && f.name == word),
+                ),
+            }
+        };
+
+        match text {
+            Some(text) => json!({ "contents": { "kind": "markdown", "value": text } }),
+            None => Value::Null,
+        }
+    }
+
+    /// Handle a `textDocument/definition` request.
+    fn definition(&self, uri: &str, doc: &Document, line: usize, character: usize) -> Value {
+        let word = match doc.word_at(line, character, false) {
+            Some(word) if !word.is_property => word,
+            _ => return Value::Null,
+        };
+
+        if let Some(ref namespace) = word.namespace {
+            // Function in an imported script module
+            return self
+                .resolve_import(doc, namespace)
+                .and_then(|ast| {
+                    let path = ast.source()?;
+                    let text = fs::read_to_string(path).unwrap_or_default();
+                    collect_symbols(&ast)
+                        .into_iter()
+                        .find(|s| s.kind == SymbolKind::Function && s.name == word.word)
+                        .map(|s| location(&path_to_uri(path), &text, s.pos, s.name.chars().count()))
+                })
+                .unwrap_or(Value::Null);
+        }
+
+        match doc.find_symbol(
+            &word.word,
+            &[
+                SymbolKind::Function,
+                SymbolKind::Variable,
+                SymbolKind::Constant,
+                SymbolKind::Module,
+            ],
+        ) {
+            // Imported module - go to the module's script file
+            Some(Symbol {
+                kind: SymbolKind::Module,
+                name,
+                ..
+            }) => self
+                .resolve_import(doc, name)
+                .and_then(|ast| ast.source().map(path_to_uri))
+                .map(|file| location(&file, "", Position::new(1, 1), 0))
+                .unwrap_or(Value::Null),
+            Some(symbol) => location(uri, doc.text(), symbol.pos, symbol.name.chars().count()),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Handle a `textDocument/documentSymbol` request.
+fn document_symbols(doc: &Document) -> Value {
+    Value::Array(
+        doc.symbols()
+            .iter()
+            .map(|s| {
+                let range = range(doc.text(), s.pos, s.name.chars().count());
+                let mut symbol = json!({
+                    "name": s.name,
+                    "kind": s.kind.lsp_symbol_kind(),
+                    "range": range,
+                    "selectionRange": range
+                });
+                if let Some(ref detail) = s.detail {
+                    symbol["detail"] = json!(detail);
+                }
+                symbol
+            })
+            .collect(),
+    )
+}
+
+/// Hover text for a symbol defined in a script.
+fn symbol_hover(symbol: &Symbol) -> Option<String> {
+    let code = match symbol.kind {
+        SymbolKind::Function => symbol.detail.clone().unwrap_or_default(),
+        SymbolKind::Variable => format!("let {}", symbol.name),
+        SymbolKind::Constant => format!("const {}", symbol.name),
+        SymbolKind::Module => match symbol.path {
+            Some(ref path) => format!("import {:?} as {}", path, symbol.name),
+            None => format!("import ... as {}", symbol.name),
+        },
+    };
+
+    let mut text = format!("```rhai\n{}\n```", code);
+
+    if let Some(ref doc) = symbol.doc {
+        text.push_str("\n\n");
+        text.push_str(doc);
+    }
+
+    Some(text)
+}
+
+/// Hover text for a list of native functions.
+fn signatures_hover<'a>(functions: impl Iterator<Item = &'a NativeFn>) -> Option<String> {
+    let mut signatures: Vec<_> = functions.map(|f| f.signature.as_str()).collect();
+
+    if signatures.is_empty() {
+        return None;
+    }
+
+    signatures.sort();
+    signatures.dedup();
+
+    Some(format!("```rust\n{}\n```", signatures.join("\n")))
+}
+
+/// Make a notification message.
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// Convert a [`Position`] in a script, plus an offset in characters, into an LSP position.
+///
+/// LSP counts characters in UTF-16 code units, so the line of the script is needed.
+fn position(text: &str, pos: Position, offset: usize) -> Value {
+    let line = pos.line().map_or(0, |line| line - 1);
+    let character = pos.position().map_or(0, |character| character - 1) + offset;
+
+    json!({
+        "line": line,
+        "character": chars_to_utf16(text.lines().nth(line).unwrap_or_default(), character)
+    })
+}
+
+/// Make an LSP range in a script, starting at a [`Position`] and spanning `len` characters.
+fn range(text: &str, pos: Position, len: usize) -> Value {
+    json!({ "start": position(text, pos, 0), "end": position(text, pos, len) })
+}
+
+/// Make an LSP location in a script.
+fn location(uri: &str, text: &str, pos: Position, len: usize) -> Value {
+    json!({ "uri": uri, "range": range(text, pos, len) })
+}
+
+/// Convert a file path into a `file://` URI.
+fn path_to_uri(path: &str) -> String {
+    let path = Path::new(path);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+
+    let path = path.to_string_lossy().replace('\\', "/").replace(' ', "%20");
+
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
@@ -0,0 +1,63 @@
+//! Module implementing the base protocol of LSP: JSON-RPC messages framed with HTTP-style headers.
+
+use serde_json::Value;
+use std::io::{BufRead, Error, ErrorKind, Result, Write};
+
+/// Read one message from the input stream.
+///
+/// Returns `Ok(None)` when the input stream is closed.
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    let mut line = String::new();
+
+    // Headers are terminated by an empty line
+    loop {
+        line.clear();
+
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        let mut parts = header.splitn(2, ':');
+
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("Content-Length") => {
+                let len = value.trim().parse::<usize>().map_err(|_| {
+                    Error::new(ErrorKind::InvalidData, format!("invalid header: {}", header))
+                })?;
+                content_length = Some(len);
+            }
+            // Other headers (e.g. Content-Type) are ignored
+            (Some(_), Some(_)) => (),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid header: {}", header),
+                ))
+            }
+        }
+    }
+
+    let len = content_length
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing Content-Length header"))?;
+
+    let mut content = vec![0_u8; len];
+    input.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+/// Write one message to the output stream.
+pub fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    output.flush()
+}
@@ -0,0 +1,245 @@
+use rhai::module_resolvers::FileModuleResolver;
+use rhai::{Engine, RegisterFn};
+use rhai_lsp::{read_message, Server};
+use serde_json::{json, Value};
+use std::io::{BufReader, Cursor};
+
+const URI: &str = "file:///test.rhai";
+
+fn open(server: &mut Server, text: &str) -> Vec<Value> {
+    server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": URI, "languageId": "rhai", "version": 1, "text": text } }
+    }))
+}
+
+fn request(server: &mut Server, method: &str, line: u64, character: u64) -> Value {
+    let mut replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": {
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character }
+        }
+    }));
+    assert_eq!(replies.len(), 1);
+    replies.remove(0)["result"].take()
+}
+
+fn labels(items: &Value) -> Vec<&str> {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_lsp_diagnostics() {
+    let mut server = Server::new(Engine::new());
+
+    let replies = open(&mut server, "let x = 42;\nlet y = ;");
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+
+    let diagnostics = replies[0]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{ "text": "let x = 42;\nlet y = x;" }]
+        }
+    }));
+    assert!(replies[0]["params"]["diagnostics"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_lsp_completion() {
+    let mut engine = Engine::new();
+    engine.register_fn("compute_value", |x: i64| x * 2);
+
+    let mut server = Server::new(engine);
+
+    open(&mut server, "fn compare(a, b) { a < b }\nlet counter = 0;");
+
+    // Incomplete script - symbols from the last successful compilation are still available
+    server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": URI, "version": 2 },
+            "contentChanges": [{
+                "text": "fn compare(a, b) { a < b }\nlet counter = 0;\ncom\nlet s = \"hello\"; s.le"
+            }]
+        }
+    }));
+
+    let items = request(&mut server, "textDocument/completion", 2, 3);
+    let items = labels(&items);
+    assert!(items.contains(&"compare"));
+    assert!(items.contains(&"compute_value"));
+    assert!(!items.contains(&"counter"));
+
+    let items = request(&mut server, "textDocument/completion", 3, 22);
+    let items = labels(&items);
+    assert!(items.contains(&"len"));
+}
+
+#[test]
+fn test_lsp_hover_and_definition() {
+    let mut server = Server::new(Engine::new());
+
+    open(
+        &mut server,
+        "/// Add two numbers.\nfn add(a, b) { a + b }\n\nlet x = add(1, 2);\nx.len()",
+    );
+
+    let hover = request(&mut server, "textDocument/hover", 3, 9);
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("fn add(a, b)"));
+    assert!(text.contains("Add two numbers."));
+
+    let hover = request(&mut server, "textDocument/hover", 4, 3);
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("len("));
+
+    let location = request(&mut server, "textDocument/definition", 3, 9);
+    assert_eq!(location["uri"], URI);
+    assert_eq!(
+        location["range"]["start"],
+        json!({ "line": 1, "character": 3 })
+    );
+
+    let symbols = request(&mut server, "textDocument/documentSymbol", 0, 0);
+    let names: Vec<_> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["add", "x"]);
+}
+
+#[test]
+fn test_lsp_utf16_positions() {
+    let mut server = Server::new(Engine::new());
+
+    open(&mut server, "let e = \"😀😀\"; let x = 0; x + 1");
+
+    // Each emoji takes two UTF-16 code units
+    let hover = request(&mut server, "textDocument/hover", 0, 27);
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("let x"));
+
+    let location = request(&mut server, "textDocument/definition", 0, 27);
+    assert_eq!(
+        location["range"],
+        json!({
+            "start": { "line": 0, "character": 20 },
+            "end": { "line": 0, "character": 21 }
+        })
+    );
+}
+
+#[test]
+fn test_lsp_imported_module() {
+    let dir = std::env::temp_dir().join("rhai_lsp_test_imported_module");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("helpers.rhai"),
+        "/// Double a number.\nfn double(x) { x * 2 }\n",
+    )
+    .unwrap();
+
+    let mut engine = Engine::new();
+    engine.set_module_resolver(FileModuleResolver::new_with_path(&dir));
+
+    let mut server = Server::new(engine);
+
+    let replies = open(
+        &mut server,
+        "import \"helpers\" as h;\nimport \"missing\" as m;\nh::double(21)",
+    );
+    let diagnostics = replies[0]["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+    let items = request(&mut server, "textDocument/completion", 2, 5);
+    assert_eq!(labels(&items), vec!["double"]);
+
+    let location = request(&mut server, "textDocument/definition", 2, 5);
+    assert!(location["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("rhai_lsp_test_imported_module/helpers.rhai"));
+    assert_eq!(
+        location["range"]["start"],
+        json!({ "line": 1, "character": 3 })
+    );
+
+    let hover = request(&mut server, "textDocument/hover", 2, 5);
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("Double a number."));
+}
+
+#[test]
+fn test_lsp_stdio_protocol() {
+    let messages = [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "unknown/method", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/hover", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ];
+
+    let input: String = messages
+        .iter()
+        .map(|m| {
+            let content = m.to_string();
+            format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+        })
+        .collect();
+
+    let mut output = Vec::new();
+    let mut server = Server::new(Engine::new());
+    server.run(Cursor::new(input), &mut output).unwrap();
+    assert!(server.has_exited());
+
+    let mut output = BufReader::new(Cursor::new(output));
+
+    let reply = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(reply["id"], 1);
+    assert_eq!(reply["result"]["capabilities"]["hoverProvider"], true);
+
+    let reply = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(reply["id"], 2);
+    assert_eq!(reply["error"]["code"], -32601);
+
+    let reply = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(reply["id"], 3);
+    assert_eq!(reply["result"], Value::Null);
+
+    // Requests after shutdown are rejected
+    let reply = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(reply["id"], 4);
+    assert_eq!(reply["error"]["code"], -32600);
+
+    assert!(read_message(&mut output).unwrap().is_none());
+}
@@ -85,6 +85,8 @@ pub struct ScriptFnDef {
     pub externals: Vec<ImmutableString>,
     /// Function doc-comments (if any).
     pub comments: Vec<String>,
+    /// Position of the function name in the definition.
+    pub pos: Position,
 }
 
 impl fmt::Display for ScriptFnDef {
@@ -128,6 +130,8 @@ pub struct ScriptFnMetadata<'a> {
     pub name: &'a str,
     /// Function parameters (if any).
     pub params: Vec<&'a str>,
+    /// Position of the function name in the definition.
+    pub pos: Position,
 }
 
 impl fmt::Display for ScriptFnMetadata<'_> {
@@ -155,6 +159,7 @@ impl<'a> Into<ScriptFnMetadata<'a>> for &'a ScriptFnDef {
             access: self.access,
             name: &self.name,
             params: self.params.iter().map(|s| s.as_str()).collect(),
+            pos: self.pos,
         }
     }
 }
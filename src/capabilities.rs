@@ -0,0 +1,96 @@
+//! Module defining the set of capabilities granted to an evaluation.
+
+use crate::stdlib::{collections::HashSet, fmt, iter::FromIterator};
+use crate::ImmutableString;
+
+/// A set of capabilities granted to an evaluation.
+///
+/// Native Rust functions can be tagged with the names of the capabilities they require, e.g.
+/// `fs.read` or `net`, via [`Module::update_fn_capabilities`][crate::Module::update_fn_capabilities],
+/// [`Module::set_capabilities`][crate::Module::set_capabilities] or
+/// [`Engine::set_fn_capabilities`][crate::Engine::set_fn_capabilities].
+///
+/// When an evaluation is run with a granted set of capabilities, via
+/// [`EvalOptions::with_capabilities`][crate::EvalOptions::with_capabilities], calling a function that requires a capability not in the set fails with
+/// [`ErrorPermissionDenied`][crate::EvalAltResult::ErrorPermissionDenied].
+/// Evaluations run without a granted set of capabilities can call all functions.
+///
+/// Capability names are hierarchical, separated by `.`: granting `fs` also grants `fs.read` and
+/// `fs.write`.
+///
+/// # Example
+///
+/// ```
+/// use rhai::Capabilities;
+///
+/// let granted = Capabilities::new().grant("fs.read").grant("net");
+///
+/// assert!(granted.is_granted("fs.read"));
+/// assert!(!granted.is_granted("fs.write"));
+/// assert!(granted.is_granted("net.http"));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Capabilities(HashSet<ImmutableString>);
+
+impl Capabilities {
+    /// Create a new, empty, set of capabilities.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Add a capability to the set.
+    #[inline(always)]
+    pub fn grant(mut self, capability: impl Into<ImmutableString>) -> Self {
+        self.0.insert(capability.into());
+        self
+    }
+    /// Remove a capability from the set.
+    ///
+    /// Capabilities nested within it which are granted separately, e.g. `fs.read` under `fs`,
+    /// are not removed.
+    #[inline(always)]
+    pub fn revoke(mut self, capability: &str) -> Self {
+        self.0.remove(capability);
+        self
+    }
+    /// Is a capability granted, either by itself or via a parent, e.g. `fs.read` via `fs`?
+    pub fn is_granted(&self, capability: &str) -> bool {
+        if self.0.contains(capability) {
+            return true;
+        }
+
+        capability
+            .match_indices('.')
+            .any(|(index, _)| self.0.contains(&capability[..index]))
+    }
+    /// Are all the capabilities granted?
+    #[inline(always)]
+    pub fn is_all_granted<'a>(&self, capabilities: impl IntoIterator<Item = &'a str>) -> bool {
+        capabilities.into_iter().all(|c| self.is_granted(c))
+    }
+    /// Is the set empty?
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Get an iterator over the capabilities in the set, in no particular order.
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|s| s.as_str())
+    }
+}
+
+impl<T: Into<ImmutableString>> FromIterator<T> for Capabilities {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut list: crate::stdlib::vec::Vec<_> = self.iter().collect();
+        list.sort();
+        write!(f, "[{}]", list.join(", "))
+    }
+}
@@ -0,0 +1,481 @@
+//! Module defining statement and branch coverage recording.
+
+use crate::ast::{Expr, Stmt};
+use crate::engine::State;
+use crate::fn_native::{Locked, Shared};
+use crate::stdlib::{
+    collections::BTreeMap,
+    fmt::Write,
+    string::{String, ToString},
+    vec::Vec,
+};
+use crate::{Engine, ImmutableString, Module, Position};
+
+/// Name used in place of the source of scripts without one.
+const NO_SOURCE: &str = "<script>";
+
+/// Summary of coverage results.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct CoverageSummary {
+    /// Number of statements.
+    pub statements: usize,
+    /// Number of statements executed at least once.
+    pub statements_hit: usize,
+    /// Number of branches.
+    pub branches: usize,
+    /// Number of branches taken at least once.
+    pub branches_hit: usize,
+}
+
+impl CoverageSummary {
+    /// Add the numbers of another summary to this one.
+    #[inline(always)]
+    fn add(&mut self, other: &Self) {
+        self.statements += other.statements;
+        self.statements_hit += other.statements_hit;
+        self.branches += other.branches;
+        self.branches_hit += other.branches_hit;
+    }
+}
+
+/// Coverage results of a single source.
+///
+/// Statements are keyed by their [positions][Position].
+///
+/// Branching constructs (`if`, `switch`, `&&` and `||`) are keyed by the [position][Position] of
+/// the `if` or `switch` keyword or the operator, and each branch within them is keyed by the
+/// [position][Position] of the statement or expression it evaluates. A branch that evaluates
+/// nothing (i.e. an `if` without `else`, a `switch` without a default case, or a short-circuited
+/// `&&` or `||`) is keyed by [`Position::NONE`].
+#[derive(Debug, Clone, Default)]
+pub struct SourceCoverage {
+    /// Number of times each statement is executed.
+    statements: BTreeMap<Position, u64>,
+    /// Number of times each branch is taken, grouped by branching construct.
+    branches: BTreeMap<Position, BTreeMap<Position, u64>>,
+}
+
+impl SourceCoverage {
+    /// Get an iterator over all statements and the number of times each is executed,
+    /// in order of position.
+    #[inline(always)]
+    pub fn statements(&self) -> impl Iterator<Item = (Position, u64)> + '_ {
+        self.statements.iter().map(|(&pos, &hits)| (pos, hits))
+    }
+    /// Get the number of times the statement at a particular [position][Position] is executed,
+    /// or [`None`] if there is no statement at that position.
+    #[inline(always)]
+    pub fn statement_hits(&self, pos: Position) -> Option<u64> {
+        self.statements.get(&pos).copied()
+    }
+    /// Get an iterator over all branches and the number of times each is taken, in order of
+    /// position.
+    ///
+    /// Each item contains the position of the branching construct, the position of the branch,
+    /// and the number of times the branch is taken.
+    #[inline(always)]
+    pub fn branches(&self) -> impl Iterator<Item = (Position, Position, u64)> + '_ {
+        self.branches.iter().flat_map(|(&construct, branches)| {
+            branches
+                .iter()
+                .map(move |(&branch, &hits)| (construct, branch, hits))
+        })
+    }
+    /// Get the number of times a branch of the branching construct at a particular
+    /// [position][Position] is taken, or [`None`] if there is no such branch.
+    #[inline(always)]
+    pub fn branch_hits(&self, construct: Position, branch: Position) -> Option<u64> {
+        self.branches
+            .get(&construct)
+            .and_then(|branches| branches.get(&branch))
+            .copied()
+    }
+    /// Get a summary of the coverage results.
+    pub fn summary(&self) -> CoverageSummary {
+        CoverageSummary {
+            statements: self.statements.len(),
+            statements_hit: self.statements.values().filter(|&&n| n > 0).count(),
+            branches: self.branches.values().map(|b| b.len()).sum(),
+            branches_hit: self
+                .branches
+                .values()
+                .flat_map(|b| b.values())
+                .filter(|&&n| n > 0)
+                .count(),
+        }
+    }
+    /// Get the number of times each line is executed, in order of line number.
+    ///
+    /// The number for a line is the highest number of times any statement starting on it
+    /// is executed.
+    fn lines(&self) -> BTreeMap<usize, u64> {
+        let mut lines = BTreeMap::new();
+
+        self.statements.iter().for_each(|(pos, &hits)| {
+            if let Some(line) = pos.line() {
+                let n = lines.entry(line).or_insert(0);
+                if hits > *n {
+                    *n = hits;
+                }
+            }
+        });
+
+        lines
+    }
+    /// Register a statement, and all statements and branches within it.
+    fn register_stmt(&mut self, stmt: &Stmt) {
+        if is_covered(stmt) {
+            self.statements.entry(stmt.position()).or_insert(0);
+        }
+
+        match stmt {
+            Stmt::If(expr, x, pos) => {
+                self.register_expr(expr);
+                let (if_block, else_block) = x.as_ref();
+                self.register_branch(*pos, if_block.position());
+                self.register_branch(*pos, branch_position(else_block.as_ref()));
+                self.register_stmt(if_block);
+                else_block.iter().for_each(|stmt| self.register_stmt(stmt));
+            }
+            Stmt::Switch(expr, x, pos) => {
+                self.register_expr(expr);
+                let (table, def_stmt) = x.as_ref();
+                table.values().for_each(|stmt| {
+                    self.register_branch(*pos, stmt.position());
+                    self.register_stmt(stmt);
+                });
+                self.register_branch(*pos, branch_position(def_stmt.as_ref()));
+                def_stmt.iter().for_each(|stmt| self.register_stmt(stmt));
+            }
+            Stmt::While(expr, body, _) | Stmt::Do(body, expr, _, _) => {
+                self.register_expr(expr);
+                self.register_stmt(body);
+            }
+            Stmt::For(expr, x, _) => {
+                self.register_expr(expr);
+                self.register_stmt(&x.1);
+            }
+            Stmt::Let(_, expr, _, _) | Stmt::Const(_, expr, _, _) | Stmt::Return(_, expr, _) => {
+                expr.iter().for_each(|expr| self.register_expr(expr))
+            }
+            Stmt::Assignment(x, _) => {
+                self.register_expr(&x.0);
+                self.register_expr(&x.2);
+            }
+            Stmt::Block(statements, _) => {
+                statements.iter().for_each(|stmt| self.register_stmt(stmt))
+            }
+            Stmt::TryCatch(x, _, _) => {
+                self.register_stmt(&x.0);
+                self.register_stmt(&x.2);
+            }
+            Stmt::Expr(expr) => self.register_expr(expr),
+            #[cfg(not(feature = "no_module"))]
+            Stmt::Import(expr, _, _) => self.register_expr(expr),
+            _ => (),
+        }
+    }
+    /// Register all statements and branches within an expression.
+    fn register_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Stmt(statements, _) => {
+                statements.iter().for_each(|stmt| self.register_stmt(stmt))
+            }
+            Expr::And(x, pos) | Expr::Or(x, pos) => {
+                self.register_branch(*pos, x.rhs.position());
+                self.register_branch(*pos, Position::NONE);
+                self.register_expr(&x.lhs);
+                self.register_expr(&x.rhs);
+            }
+            Expr::Dot(x, _) | Expr::Index(x, _) | Expr::In(x, _) => {
+                self.register_expr(&x.lhs);
+                self.register_expr(&x.rhs);
+            }
+            Expr::FnCall(x, _) => x.args.iter().for_each(|expr| self.register_expr(expr)),
+            Expr::Array(x, _) => x.iter().for_each(|expr| self.register_expr(expr)),
+            Expr::Map(x, _) => x.iter().for_each(|(_, expr)| self.register_expr(expr)),
+            Expr::Custom(x, _) => x.keywords.iter().for_each(|expr| self.register_expr(expr)),
+            _ => (),
+        }
+    }
+    /// Register a branch.
+    #[inline(always)]
+    fn register_branch(&mut self, construct: Position, branch: Position) {
+        self.branches
+            .entry(construct)
+            .or_default()
+            .entry(branch)
+            .or_insert(0);
+    }
+}
+
+/// Report of statement and branch coverage.
+///
+/// Obtained via [`Engine::coverage_report`].
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    /// Coverage results, keyed by source.
+    sources: BTreeMap<Option<ImmutableString>, SourceCoverage>,
+}
+
+impl CoverageReport {
+    /// Is the report empty?
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+    /// Get an iterator over the coverage results of all sources, in order of source.
+    ///
+    /// Scripts without a source are listed under [`None`].
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = (Option<&str>, &SourceCoverage)> {
+        self.sources
+            .iter()
+            .map(|(source, coverage)| (source.as_ref().map(|s| s.as_str()), coverage))
+    }
+    /// Get the coverage results of a particular source.
+    ///
+    /// Pass [`None`] for scripts without a source.
+    #[inline(always)]
+    pub fn get(&self, source: Option<&str>) -> Option<&SourceCoverage> {
+        self.iter()
+            .find(|&(s, _)| s == source)
+            .map(|(_, coverage)| coverage)
+    }
+    /// Get a summary of the coverage results of all sources.
+    pub fn summary(&self) -> CoverageSummary {
+        let mut summary = CoverageSummary::default();
+        self.sources
+            .values()
+            .for_each(|coverage| summary.add(&coverage.summary()));
+        summary
+    }
+    /// Export the coverage results in the [`lcov`](https://github.com/linux-test-project/lcov)
+    /// tracefile format, understood by tools such as `genhtml` and most CI coverage services.
+    ///
+    /// Line hits are derived from statement hits. Scripts without a source are listed as
+    /// `<script>`.
+    pub fn to_lcov(&self) -> String {
+        let mut output = String::new();
+
+        for (source, coverage) in self.iter() {
+            output.push_str("TN:\n");
+            let _ = writeln!(output, "SF:{}", source.unwrap_or(NO_SOURCE));
+
+            for (block, (construct, branches)) in coverage.branches.iter().enumerate() {
+                let line = construct.line().unwrap_or(0);
+                let executed = branches.values().any(|&n| n > 0);
+
+                for (branch, &hits) in branches.values().enumerate() {
+                    if executed {
+                        let _ = writeln!(output, "BRDA:{},{},{},{}", line, block, branch, hits);
+                    } else {
+                        let _ = writeln!(output, "BRDA:{},{},{},-", line, block, branch);
+                    }
+                }
+            }
+
+            let summary = coverage.summary();
+            let _ = writeln!(output, "BRF:{}", summary.branches);
+            let _ = writeln!(output, "BRH:{}", summary.branches_hit);
+
+            let lines = coverage.lines();
+            lines.iter().for_each(|(line, hits)| {
+                let _ = writeln!(output, "DA:{},{}", line, hits);
+            });
+            let _ = writeln!(output, "LF:{}", lines.len());
+            let _ = writeln!(output, "LH:{}", lines.values().filter(|&&n| n > 0).count());
+
+            output.push_str("end_of_record\n");
+        }
+
+        output
+    }
+    /// Export the coverage results as a JSON summary.
+    ///
+    /// The JSON object contains the `statements`, `statements_hit`, `branches` and `branches_hit`
+    /// totals, plus a `sources` array with the same totals for each source, together with the
+    /// `lines` executed and not executed, and the `uncovered_branches` (as `[line, position]` of
+    /// the branching construct followed by `[line, position]` of the branch).
+    pub fn to_json(&self) -> String {
+        fn write_summary(output: &mut String, summary: &CoverageSummary) {
+            let _ = write!(
+                output,
+                r#""statements":{},"statements_hit":{},"branches":{},"branches_hit":{}"#,
+                summary.statements, summary.statements_hit, summary.branches, summary.branches_hit
+            );
+        }
+        fn write_position(output: &mut String, pos: Position) {
+            let _ = write!(
+                output,
+                "[{},{}]",
+                pos.line().unwrap_or(0),
+                pos.position().unwrap_or(0)
+            );
+        }
+
+        let mut output = String::from("{");
+        write_summary(&mut output, &self.summary());
+        output.push_str(r#","sources":["#);
+
+        for (index, (source, coverage)) in self.iter().enumerate() {
+            if index > 0 {
+                output.push(',');
+            }
+            output.push_str(r#"{"source":"#);
+            match source {
+                Some(source) => write_json_string(&mut output, source),
+                None => output.push_str("null"),
+            }
+            output.push(',');
+            write_summary(&mut output, &coverage.summary());
+
+            let lines = coverage.lines();
+            let hit: Vec<_> = lines
+                .iter()
+                .filter(|(_, &n)| n > 0)
+                .map(|(line, _)| line.to_string())
+                .collect();
+            let missed: Vec<_> = lines
+                .iter()
+                .filter(|(_, &n)| n == 0)
+                .map(|(line, _)| line.to_string())
+                .collect();
+            let _ = write!(
+                output,
+                r#","lines_hit":[{}],"lines_missed":[{}],"uncovered_branches":["#,
+                hit.join(","),
+                missed.join(",")
+            );
+
+            coverage
+                .branches()
+                .filter(|&(_, _, hits)| hits == 0)
+                .enumerate()
+                .for_each(|(index, (construct, branch, _))| {
+                    if index > 0 {
+                        output.push(',');
+                    }
+                    output.push('[');
+                    write_position(&mut output, construct);
+                    output.push(',');
+                    write_position(&mut output, branch);
+                    output.push(']');
+                });
+
+            output.push_str("]}");
+        }
+
+        output.push_str("]}");
+        output
+    }
+    /// Get the coverage results of a source, creating them if necessary.
+    #[inline(always)]
+    fn source_mut(&mut self, source: &Option<ImmutableString>) -> &mut SourceCoverage {
+        if !self.sources.contains_key(source) {
+            self.sources.insert(source.clone(), Default::default());
+        }
+        self.sources.get_mut(source).unwrap()
+    }
+}
+
+/// Write a string as a JSON string literal.
+fn write_json_string(output: &mut String, s: &str) {
+    output.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", ch as u32);
+            }
+            ch => output.push(ch),
+        }
+    }
+    output.push('"');
+}
+
+/// Is this statement recorded for coverage?
+///
+/// Blocks only group other statements, while no-ops and variable sharing are not written by the
+/// user.
+#[inline(always)]
+fn is_covered(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Noop(_) | Stmt::Block(_, _) => false,
+        #[cfg(not(feature = "no_closure"))]
+        Stmt::Share(_) => false,
+        stmt => !stmt.position().is_none(),
+    }
+}
+
+/// Get the [position][Position] of an optional branch.
+#[inline(always)]
+fn branch_position(stmt: Option<&Stmt>) -> Position {
+    stmt.map_or(Position::NONE, |stmt| stmt.position())
+}
+
+impl Engine {
+    /// Lock the coverage report, if coverage is enabled, and run a closure on the results of the
+    /// current source.
+    #[inline(always)]
+    fn with_coverage(&self, source: &Option<ImmutableString>, f: impl FnOnce(&mut SourceCoverage)) {
+        if let Some(ref report) = self.coverage {
+            #[cfg(not(feature = "sync"))]
+            let mut report = report.borrow_mut();
+            #[cfg(feature = "sync")]
+            let mut report = report.write().unwrap();
+
+            f(report.source_mut(source));
+        }
+    }
+    /// Register all statements and branches of a script, so that those never executed are
+    /// reported.
+    pub(crate) fn coverage_register(
+        &self,
+        source: &Option<ImmutableString>,
+        statements: &[Stmt],
+        _lib: &[&Module],
+    ) {
+        self.with_coverage(source, |coverage| {
+            statements
+                .iter()
+                .for_each(|stmt| coverage.register_stmt(stmt));
+
+            #[cfg(not(feature = "no_function"))]
+            _lib.iter()
+                .flat_map(|m| m.iter_script_fn())
+                .for_each(|(_, _, _, _, fn_def)| coverage.register_stmt(&fn_def.body));
+        });
+    }
+    /// Record the execution of a statement.
+    #[inline(always)]
+    pub(crate) fn coverage_stmt(&self, state: &State, stmt: &Stmt) {
+        if is_covered(stmt) {
+            self.with_coverage(&state.source, |coverage| {
+                *coverage.statements.entry(stmt.position()).or_insert(0) += 1;
+            });
+        }
+    }
+    /// Record a branch being taken.
+    #[inline(always)]
+    pub(crate) fn coverage_branch(&self, state: &State, construct: Position, branch: Position) {
+        self.with_coverage(&state.source, |coverage| {
+            *coverage
+                .branches
+                .entry(construct)
+                .or_default()
+                .entry(branch)
+                .or_insert(0) += 1;
+        });
+    }
+}
+
+/// Make a new, empty, shared [`CoverageReport`].
+#[inline(always)]
+pub(crate) fn new_report() -> Shared<Locked<CoverageReport>> {
+    Shared::new(Locked::new(Default::default()))
+}
@@ -0,0 +1,559 @@
+//! Module defining the debugging interface.
+
+use crate::ast::Stmt;
+use crate::dynamic::AccessMode;
+use crate::engine::State;
+use crate::stdlib::{boxed::Box, fmt, string::ToString, vec::Vec};
+use crate::{Dynamic, Engine, EvalAltResult, EvalContext, FnPtr, ImmutableString, Position, Scope};
+
+/// A break-point for debugging.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum BreakPoint {
+    /// Break at a particular line in a particular source.
+    ///
+    /// A [`None`] source matches any source.
+    AtPosition {
+        /// Source (if any) of the break-point.
+        source: Option<ImmutableString>,
+        /// Line number (1-based) of the break-point.
+        line: usize,
+        /// Is the break-point enabled?
+        enabled: bool,
+    },
+    /// Break when a script-defined function of a particular name is called.
+    AtFunctionName {
+        /// Function name.
+        name: ImmutableString,
+        /// Is the break-point enabled?
+        enabled: bool,
+    },
+}
+
+impl fmt::Display for BreakPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AtPosition {
+                source: Some(source),
+                line,
+                ..
+            } => write!(f, "{} @ line {}", source, line)?,
+            Self::AtPosition { line, .. } => write!(f, "line {}", line)?,
+            Self::AtFunctionName { name, .. } => write!(f, "{} (...)", name)?,
+        }
+
+        if !self.is_enabled() {
+            f.write_str(" (disabled)")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BreakPoint {
+    /// Is this break-point enabled?
+    #[inline(always)]
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Self::AtPosition { enabled, .. } | Self::AtFunctionName { enabled, .. } => *enabled,
+        }
+    }
+    /// Enable/disable this break-point.
+    #[inline(always)]
+    pub fn enable(&mut self, value: bool) {
+        match self {
+            Self::AtPosition { enabled, .. } | Self::AtFunctionName { enabled, .. } => {
+                *enabled = value
+            }
+        }
+    }
+}
+
+/// A command for the debugger to carry out when the debugger callback returns.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DebuggerCommand {
+    /// Continue normal execution until the next break-point.
+    Continue,
+    /// Step into the next statement, entering function calls.
+    StepInto,
+    /// Step over the next statement, without entering function calls.
+    StepOver,
+    /// Run until the current function returns.
+    StepOut,
+}
+
+impl Default for DebuggerCommand {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::Continue
+    }
+}
+
+/// The reason why the debugger callback is called.
+#[derive(Debug, Clone, Copy)]
+pub enum DebuggerEvent<'a> {
+    /// Execution stops after a step command.
+    Step,
+    /// Execution stops at a break-point.
+    /// Wrapped value is the index of the break-point in [`Debugger::break_points`].
+    BreakPoint(usize),
+    /// An error is about to be raised by a statement.
+    Error(&'a EvalAltResult),
+}
+
+/// A frame in the call stack of script-defined functions.
+#[derive(Debug, Clone)]
+pub struct CallStackFrame {
+    /// Name of the function.
+    pub fn_name: ImmutableString,
+    /// Copies of the argument values.
+    pub args: Vec<Dynamic>,
+    /// Source of the function, if any.
+    pub source: Option<ImmutableString>,
+    /// Position of the function call.
+    pub pos: Position,
+    /// Copy of the [`Scope`] of the function, as of the call to the next function in the call
+    /// stack, or the last time execution stopped in this frame.
+    pub scope: Scope<'static>,
+    /// Copy of the value bound to `this`, if any, as of the same time as [`scope`][CallStackFrame::scope].
+    pub this_ptr: Option<Dynamic>,
+}
+
+impl fmt::Display for CallStackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.fn_name)?;
+
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{:?}", arg)?;
+        }
+
+        f.write_str(")")?;
+
+        if let Some(ref source) = self.source {
+            write!(f, " @ {}", source)?;
+        }
+
+        if !self.pos.is_none() {
+            write!(f, " ({})", self.pos)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Current status of the debugger.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+enum DebuggerStatus {
+    /// Run until the next break-point.
+    Continue,
+    /// Stop at the next statement.
+    Next,
+    /// Stop at the next statement at or above a particular call level.
+    NextAtLevel(usize),
+    /// Stop at the next statement above a particular call level.
+    FunctionExit(usize),
+}
+
+/// The state of the debugger during an evaluation.
+///
+/// A [`Debugger`] is created at the beginning of each evaluation and initialized via the
+/// closure passed to [`Engine::register_debugger`][crate::Engine::register_debugger].
+/// While execution is paused, the debugger callback can access it via
+/// [`EvalContext::debugger`][crate::EvalContext::debugger].
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    /// Current status.
+    status: DebuggerStatus,
+    /// Break-points.
+    break_points: Vec<BreakPoint>,
+    /// Stop when an error is raised?
+    break_on_error: bool,
+    /// Call stack of script-defined functions.
+    call_stack: Vec<CallStackFrame>,
+    /// Copy of the global [`Scope`], as of the first function call in the call stack, or the last
+    /// time execution stopped at global level.
+    global_scope: Scope<'static>,
+    /// Break-point hit by a function call, to be reported at the first statement of the function.
+    pending_break_point: Option<usize>,
+    /// Has the current frame's [`Scope`] been copied since the start of the current statement?
+    frame_saved: bool,
+    /// Is the debugger callback running? No debugging events are raised when it is.
+    in_callback: bool,
+    /// Has the current error already been reported?
+    error_reported: bool,
+}
+
+impl Default for Debugger {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debugger {
+    /// Create a new [`Debugger`].
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self {
+            status: DebuggerStatus::Continue,
+            break_points: Default::default(),
+            break_on_error: false,
+            call_stack: Default::default(),
+            global_scope: Default::default(),
+            pending_break_point: None,
+            frame_saved: false,
+            in_callback: false,
+            error_reported: false,
+        }
+    }
+    /// Get the list of break-points.
+    #[inline(always)]
+    pub fn break_points(&self) -> &[BreakPoint] {
+        &self.break_points
+    }
+    /// Get a mutable reference to the list of break-points.
+    #[inline(always)]
+    pub fn break_points_mut(&mut self) -> &mut Vec<BreakPoint> {
+        &mut self.break_points
+    }
+    /// Add a break-point, returning its index.
+    #[inline(always)]
+    pub fn add_break_point(&mut self, break_point: BreakPoint) -> usize {
+        self.break_points.push(break_point);
+        self.break_points.len() - 1
+    }
+    /// Should execution stop when an error is raised?
+    #[inline(always)]
+    pub fn break_on_error(&self) -> bool {
+        self.break_on_error
+    }
+    /// Set whether execution should stop when an error is raised.
+    #[inline(always)]
+    pub fn set_break_on_error(&mut self, enable: bool) -> &mut Self {
+        self.break_on_error = enable;
+        self
+    }
+    /// Stop at the first statement (e.g. to implement _stop-on-entry_).
+    ///
+    /// Call this from the initialization closure.
+    #[inline(always)]
+    pub fn break_at_next_statement(&mut self) -> &mut Self {
+        self.status = DebuggerStatus::Next;
+        self
+    }
+    /// Get the call stack of script-defined functions, with the innermost call last.
+    #[inline(always)]
+    pub fn call_stack(&self) -> &[CallStackFrame] {
+        &self.call_stack
+    }
+    /// Get the copy of the global [`Scope`], as of the first function call in the call stack, or
+    /// the last time execution stopped at global level.
+    ///
+    /// While execution is paused at the global level, use [`EvalContext::scope`][crate::EvalContext::scope]
+    /// to get the live [`Scope`] instead.
+    #[inline(always)]
+    pub fn global_scope(&self) -> &Scope<'static> {
+        &self.global_scope
+    }
+
+    /// Is the debugger active, i.e. not within the debugger callback?
+    #[inline(always)]
+    pub(crate) fn is_active(&self) -> bool {
+        !self.in_callback
+    }
+    /// Mark whether the debugger callback is running.
+    #[inline(always)]
+    pub(crate) fn set_in_callback(&mut self, value: bool) {
+        self.in_callback = value;
+    }
+    /// Carry out a command at a particular call level.
+    pub(crate) fn set_command(&mut self, command: DebuggerCommand, level: usize) {
+        self.status = match command {
+            DebuggerCommand::Continue => DebuggerStatus::Continue,
+            DebuggerCommand::StepInto => DebuggerStatus::Next,
+            DebuggerCommand::StepOver => DebuggerStatus::NextAtLevel(level),
+            DebuggerCommand::StepOut => DebuggerStatus::FunctionExit(level),
+        };
+    }
+    /// Reset the state kept for a statement, at its start.
+    #[inline(always)]
+    pub(crate) fn start_statement(&mut self) {
+        self.error_reported = false;
+        self.frame_saved = false;
+    }
+    /// Copy the current frame's [`Scope`] before calling a function which may run script code,
+    /// unless already done during the current statement.
+    #[inline(always)]
+    pub(crate) fn suspend_frame(&mut self, scope: &Scope, this_ptr: &Option<&mut Dynamic>) {
+        if !self.frame_saved {
+            self.snapshot_frame(scope, this_ptr);
+        }
+    }
+    /// Copy the current frame's [`Scope`], so that it remains available while execution is
+    /// paused in functions called from this frame.
+    ///
+    /// Scopes are only copied when needed, i.e. when execution stops or before calling a function
+    /// which may run script code, as copying them at every statement is expensive.
+    pub(crate) fn snapshot_frame(&mut self, scope: &Scope, this_ptr: &Option<&mut Dynamic>) {
+        self.frame_saved = true;
+
+        match self.call_stack.last_mut() {
+            Some(frame) => {
+                frame.scope = copy_scope(scope);
+                frame.this_ptr = this_ptr.as_ref().map(|v| v.flatten_clone());
+            }
+            None => self.global_scope = copy_scope(scope),
+        }
+    }
+    /// Enter a script-defined function.
+    #[cfg(not(feature = "no_function"))]
+    pub(crate) fn push_frame(
+        &mut self,
+        fn_name: ImmutableString,
+        args: Vec<Dynamic>,
+        source: Option<ImmutableString>,
+        pos: Position,
+    ) {
+        if self.pending_break_point.is_none() {
+            self.pending_break_point = self.break_points.iter().position(|bp| match bp {
+                BreakPoint::AtFunctionName { name, enabled } => *enabled && *name == fn_name,
+                _ => false,
+            });
+        }
+
+        self.call_stack.push(CallStackFrame {
+            fn_name,
+            args,
+            source,
+            pos,
+            scope: Default::default(),
+            this_ptr: None,
+        });
+    }
+    /// Exit a script-defined function.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub(crate) fn pop_frame(&mut self) {
+        self.call_stack.pop();
+    }
+    /// Should execution stop at a statement?
+    pub(crate) fn check_stop(
+        &mut self,
+        source: Option<&str>,
+        pos: Position,
+        level: usize,
+    ) -> Option<DebuggerEvent<'static>> {
+        if let Some(index) = self.pending_break_point.take() {
+            return Some(DebuggerEvent::BreakPoint(index));
+        }
+
+        let step = match self.status {
+            DebuggerStatus::Continue => false,
+            DebuggerStatus::Next => true,
+            DebuggerStatus::NextAtLevel(n) => level <= n,
+            DebuggerStatus::FunctionExit(n) => level < n,
+        };
+
+        if step {
+            return Some(DebuggerEvent::Step);
+        }
+
+        let line = pos.line()?;
+
+        self.break_points
+            .iter()
+            .position(|bp| match bp {
+                BreakPoint::AtPosition {
+                    source: bp_source,
+                    line: bp_line,
+                    enabled,
+                } => {
+                    *enabled
+                        && *bp_line == line
+                        && (bp_source.is_none() || bp_source.as_ref().map(|s| s.as_str()) == source)
+                }
+                _ => false,
+            })
+            .map(DebuggerEvent::BreakPoint)
+    }
+    /// Should execution stop at an error?
+    pub(crate) fn check_error(&mut self, err: &EvalAltResult) -> bool {
+        if !self.break_on_error || self.error_reported {
+            return false;
+        }
+
+        match err {
+            // Not actual errors
+            EvalAltResult::Return(_, _) | EvalAltResult::LoopBreak(_, _) => false,
+            _ => {
+                self.error_reported = true;
+                true
+            }
+        }
+    }
+}
+
+impl Engine {
+    /// Get the debugger of the current evaluation, creating it if necessary.
+    ///
+    /// Returns [`None`] if no debugger is registered, or if the debugger callback is running.
+    fn active_debugger<'s>(&self, state: &'s mut State) -> Option<&'s mut Debugger> {
+        let (init, _) = self.debugger.as_ref()?;
+
+        let debugger = state
+            .debugger
+            .get_or_insert_with(|| new_debugger(init.as_ref()));
+
+        if debugger.is_active() {
+            Some(debugger)
+        } else {
+            None
+        }
+    }
+    /// Invoke the debugger callback with the current evaluation context.
+    fn call_debugger(
+        &self,
+        context: &mut EvalContext,
+        event: DebuggerEvent,
+        pos: Position,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let (_, callback) = match self.debugger {
+            Some(ref debugger) => debugger,
+            None => return Ok(()),
+        };
+
+        if let Some(ref mut debugger) = context.state.debugger {
+            debugger.snapshot_frame(context.scope, context.this_ptr);
+            debugger.set_in_callback(true);
+        }
+
+        let result = callback(context, event, pos);
+
+        if let Some(ref mut debugger) = context.state.debugger {
+            debugger.set_in_callback(false);
+
+            if let Ok(command) = result {
+                debugger.set_command(command, context.level);
+            }
+        }
+
+        result.map(|_| ())
+    }
+    /// Run the debugger before a statement is evaluated.
+    pub(crate) fn run_debugger(
+        &self,
+        context: &mut EvalContext,
+        stmt: &Stmt,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let source = context.state.source.clone();
+        let level = context.level;
+
+        let debugger = match self.active_debugger(context.state) {
+            Some(debugger) => debugger,
+            None => return Ok(()),
+        };
+
+        debugger.start_statement();
+
+        // Blocks are not stopped at - only the statements inside them
+        if let Stmt::Block(_, _) | Stmt::Noop(_) = stmt {
+            return Ok(());
+        }
+
+        let pos = stmt.position();
+
+        match debugger.check_stop(source.as_ref().map(|s| s.as_str()), pos, level) {
+            Some(event) => self.call_debugger(context, event, pos),
+            None => Ok(()),
+        }
+    }
+    /// Run the debugger when a statement raises an error.
+    ///
+    /// Returns the error to raise, which may be replaced by the debugger callback.
+    pub(crate) fn run_debugger_on_error(
+        &self,
+        context: &mut EvalContext,
+        err: Box<EvalAltResult>,
+    ) -> Box<EvalAltResult> {
+        let stop = match self.active_debugger(context.state) {
+            Some(debugger) => debugger.check_error(&err),
+            None => false,
+        };
+
+        if !stop {
+            return err;
+        }
+
+        let event = DebuggerEvent::Error(&err);
+        let pos = err.position();
+
+        match self.call_debugger(context, event, pos) {
+            Ok(_) => err,
+            Err(new_err) => new_err,
+        }
+    }
+    /// Copy the current frame's [`Scope`] before calling a function, if the call may run script
+    /// code, i.e. a script-defined function or a function pointer passed as an argument.
+    pub(crate) fn debugger_suspend_frame(
+        &self,
+        state: &mut State,
+        scope: &Scope,
+        this_ptr: &Option<&mut Dynamic>,
+        calls_script: bool,
+        args: &[Dynamic],
+    ) {
+        if calls_script || args.iter().any(|v| v.is::<FnPtr>()) {
+            if let Some(debugger) = self.active_debugger(state) {
+                debugger.suspend_frame(scope, this_ptr);
+            }
+        }
+    }
+    /// Notify the debugger that a script-defined function is called.
+    #[cfg(not(feature = "no_function"))]
+    pub(crate) fn debugger_enter_fn(
+        &self,
+        state: &mut State,
+        fn_name: &ImmutableString,
+        args: &[&mut Dynamic],
+        source: Option<ImmutableString>,
+        pos: Position,
+    ) {
+        if let Some(debugger) = self.active_debugger(state) {
+            let args = args.iter().map(|v| (**v).clone()).collect();
+            debugger.push_frame(fn_name.clone(), args, source, pos);
+        }
+    }
+    /// Notify the debugger that a script-defined function returns.
+    #[cfg(not(feature = "no_function"))]
+    pub(crate) fn debugger_exit_fn(&self, state: &mut State) {
+        if let Some(debugger) = self.active_debugger(state) {
+            debugger.pop_frame();
+        }
+    }
+}
+
+/// Make a copy of a [`Scope`] that does not borrow any variable names.
+fn copy_scope(scope: &Scope) -> Scope<'static> {
+    let mut copy = Scope::new();
+
+    scope.iter_raw().for_each(|(name, constant, value)| {
+        let access = if constant {
+            AccessMode::ReadOnly
+        } else {
+            AccessMode::ReadWrite
+        };
+        copy.push_dynamic_value(name.to_string(), access, value.flatten_clone());
+    });
+
+    copy
+}
+
+/// Helper to box an initialized [`Debugger`].
+#[inline(always)]
+pub(crate) fn new_debugger(init: &dyn Fn(&mut Debugger)) -> Box<Debugger> {
+    let mut debugger = Debugger::new();
+    init(&mut debugger);
+    Box::new(debugger)
+}
@@ -0,0 +1,710 @@
+//! Module that generates definition files from the functions registered into an [`Engine`].
+#![cfg(feature = "metadata")]
+
+use crate::module::FuncInfo;
+use crate::stdlib::{
+    any::{type_name, TypeId},
+    collections::BTreeMap,
+    fmt::Write,
+    string::{String, ToString},
+    vec::Vec,
+};
+use crate::{Dynamic, Engine, FnPtr, ImmutableString, Module, INT};
+
+#[cfg(not(feature = "no_object"))]
+use crate::engine::{FN_GET, FN_SET};
+
+#[cfg(not(feature = "no_index"))]
+use crate::engine::{FN_IDX_GET, FN_IDX_SET};
+
+#[cfg(not(feature = "no_index"))]
+use crate::Array;
+
+#[cfg(not(feature = "no_object"))]
+use crate::Map;
+
+#[cfg(not(feature = "no_float"))]
+use crate::FLOAT;
+
+/// Name used for types that cannot be determined.
+const UNKNOWN_TYPE: &str = "?";
+
+/// Style of the definitions written by [`Definitions::write`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DefinitionsStyle {
+    /// Rhai-style definitions (usually saved as `.d.rhai`), using Rhai type names.
+    Rhai,
+    /// TypeScript-style declarations (usually saved as `.d.ts`), with Rhai types mapped to their
+    /// nearest TypeScript equivalents.
+    ///
+    /// Only members of custom types are grouped into interfaces. Properties, indexers and methods
+    /// of built-in types (e.g. `string` or `array`) have no TypeScript counterpart and are omitted.
+    TypeScript,
+}
+
+/// Builder for definition files describing the API of an [`Engine`].
+///
+/// Created via [`Engine::definitions`].  Available only under the `metadata` feature.
+///
+/// Functions are grouped by module, while property getters, setters, indexers and methods are
+/// grouped under the custom type they operate on.
+#[derive(Debug, Clone, Copy)]
+pub struct Definitions<'e> {
+    /// The [`Engine`].
+    engine: &'e Engine,
+    /// Include functions in global modules (e.g. standard packages)?
+    include_packages: bool,
+}
+
+/// Parameter of a function, with its name and Rhai type name.
+#[derive(Debug, Clone)]
+struct ParamDef {
+    name: String,
+    typ: String,
+}
+
+/// A function, with types in Rhai names.
+#[derive(Debug, Clone)]
+struct FnDef {
+    name: String,
+    params: Vec<ParamDef>,
+    return_type: String,
+    comments: Vec<String>,
+}
+
+/// Members of a type.
+#[derive(Debug, Clone, Default)]
+struct TypeDef {
+    getters: BTreeMap<String, FnDef>,
+    setters: BTreeMap<String, FnDef>,
+    index_getters: Vec<FnDef>,
+    index_setters: Vec<FnDef>,
+    methods: Vec<FnDef>,
+}
+
+/// A module, with its constants, functions and sub-modules.
+#[derive(Debug, Clone, Default)]
+struct ModuleDef {
+    constants: BTreeMap<String, String>,
+    functions: Vec<FnDef>,
+    modules: BTreeMap<String, ModuleDef>,
+}
+
+/// Kinds of registered functions.
+enum FnKind {
+    #[cfg(not(feature = "no_object"))]
+    Getter(String),
+    #[cfg(not(feature = "no_object"))]
+    Setter(String),
+    #[cfg(not(feature = "no_index"))]
+    IndexGetter,
+    #[cfg(not(feature = "no_index"))]
+    IndexSetter,
+    Method,
+    Function,
+}
+
+impl Engine {
+    /// Create a [`Definitions`] builder to generate definition files (e.g. `.d.rhai` or `.d.ts`)
+    /// describing all functions, types and modules registered into this [`Engine`].
+    ///  Available only under the `metadata` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::{Engine, DefinitionsStyle, Module};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// let mut module = Module::new();
+    /// module.set_var("ANSWER", 42_i64);
+    /// engine.register_static_module("Life", module.into());
+    ///
+    /// let definitions = engine.definitions().include_packages(false);
+    ///
+    /// assert!(definitions.write(DefinitionsStyle::Rhai).contains("const ANSWER: int;"));
+    /// assert!(definitions.write(DefinitionsStyle::TypeScript).contains("const ANSWER: number;"));
+    /// ```
+    #[inline(always)]
+    pub fn definitions(&self) -> Definitions<'_> {
+        Definitions {
+            engine: self,
+            include_packages: true,
+        }
+    }
+}
+
+impl<'e> Definitions<'e> {
+    /// Include functions in global modules, such as the standard packages?  Default `true`.
+    #[inline(always)]
+    pub fn include_packages(mut self, include: bool) -> Self {
+        self.include_packages = include;
+        self
+    }
+    /// Write the definitions in a particular [style][DefinitionsStyle].
+    pub fn write(&self, style: DefinitionsStyle) -> String {
+        let (root, types) = self.collect();
+        let mut output = String::new();
+
+        match style {
+            DefinitionsStyle::Rhai => {
+                output.push_str("module static;\n");
+                write_rhai_module(&mut output, &root, &types, 0);
+            }
+            DefinitionsStyle::TypeScript => write_ts_root(&mut output, &root, &types),
+        }
+
+        output
+    }
+    /// Write Rhai-style definitions (usually saved as `.d.rhai`).
+    #[inline(always)]
+    pub fn write_rhai(&self) -> String {
+        self.write(DefinitionsStyle::Rhai)
+    }
+    /// Write TypeScript-style declarations (usually saved as `.d.ts`).
+    #[inline(always)]
+    pub fn write_typescript(&self) -> String {
+        self.write(DefinitionsStyle::TypeScript)
+    }
+
+    /// Collect all modules and types.
+    fn collect(&self) -> (ModuleDef, BTreeMap<String, TypeDef>) {
+        let mut root = ModuleDef::default();
+        let mut types = BTreeMap::new();
+
+        self.add_module(&mut root, &mut types, &self.engine.global_namespace, true);
+
+        if self.include_packages {
+            for module in self.engine.global_modules.iter() {
+                self.add_module(&mut root, &mut types, module, true);
+            }
+        }
+
+        for (name, module) in self.engine.global_sub_modules.iter() {
+            let mut def = ModuleDef::default();
+            self.add_module(&mut def, &mut types, module, false);
+            root.modules.insert(name.to_string(), def);
+        }
+
+        sort_fns(&mut root.functions);
+        types.values_mut().for_each(|t: &mut TypeDef| {
+            sort_fns(&mut t.index_getters);
+            sort_fns(&mut t.index_setters);
+            sort_fns(&mut t.methods);
+        });
+
+        (root, types)
+    }
+    /// Add the contents of a [`Module`] into a [`ModuleDef`], collecting members of types.
+    fn add_module(
+        &self,
+        def: &mut ModuleDef,
+        types: &mut BTreeMap<String, TypeDef>,
+        module: &Module,
+        is_global: bool,
+    ) {
+        for (name, value) in module.iter_var() {
+            def.constants
+                .insert(name.to_string(), self.value_type(value));
+        }
+
+        for info in module.iter_fn().filter(|f| f.access.is_public()) {
+            let f = self.fn_def(info);
+            let target = f.params.first().map(|p| p.typ.clone());
+            let is_global = is_global || info.namespace.is_global();
+
+            let (kind, target) = match (classify(info, is_global), target) {
+                (FnKind::Function, _) | (_, None) => {
+                    def.functions.push(f);
+                    continue;
+                }
+                (FnKind::Method, Some(target)) if !is_custom_type(&target) => {
+                    def.functions.push(f);
+                    continue;
+                }
+                (kind, Some(target)) => (kind, target),
+            };
+
+            let t = types.entry(target).or_default();
+
+            match kind {
+                #[cfg(not(feature = "no_object"))]
+                FnKind::Getter(prop) => {
+                    t.getters.insert(prop, f);
+                }
+                #[cfg(not(feature = "no_object"))]
+                FnKind::Setter(prop) => {
+                    t.setters.insert(prop, f);
+                }
+                #[cfg(not(feature = "no_index"))]
+                FnKind::IndexGetter => t.index_getters.push(f),
+                #[cfg(not(feature = "no_index"))]
+                FnKind::IndexSetter => t.index_setters.push(f),
+                FnKind::Method => t.methods.push(f),
+                FnKind::Function => unreachable!(),
+            }
+        }
+
+        for (name, sub_module) in module.iter_sub_modules() {
+            let sub_def = def.modules.entry(name.to_string()).or_default();
+            self.add_module(sub_def, types, &sub_module, false);
+            sort_fns(&mut sub_def.functions);
+        }
+    }
+    /// Create a [`FnDef`] from a registered function.
+    fn fn_def(&self, info: &FuncInfo) -> FnDef {
+        #[cfg(not(feature = "no_function"))]
+        if info.func.is_script() {
+            let fn_def = info.func.get_fn_def();
+
+            return FnDef {
+                name: info.name.clone(),
+                params: fn_def
+                    .params
+                    .iter()
+                    .map(|name| ParamDef {
+                        name: name.to_string(),
+                        typ: UNKNOWN_TYPE.into(),
+                    })
+                    .collect(),
+                return_type: UNKNOWN_TYPE.into(),
+                comments: fn_def.comments.clone(),
+            };
+        }
+
+        let params = (0..info.params)
+            .map(|index| {
+                let mut name = "_".to_string();
+                let mut typ = None;
+
+                if let Some(s) = info.param_names.get(index) {
+                    let mut seg = s.splitn(2, ':');
+                    name = seg.next().map(str::trim).unwrap_or("_").to_string();
+                    typ = seg.next().map(|t| self.rust_type(t));
+                }
+
+                let typ = typ.unwrap_or_else(|| match info.param_types.get(index) {
+                    Some(id) => self.type_id_name(*id),
+                    None => UNKNOWN_TYPE.into(),
+                });
+
+                ParamDef { name, typ }
+            })
+            .collect();
+
+        // The return type is listed after the parameters, if available
+        let return_type = info
+            .param_names
+            .get(info.params)
+            .map_or_else(|| UNKNOWN_TYPE.into(), |s| self.rust_type(s));
+
+        FnDef {
+            name: info.name.clone(),
+            params,
+            return_type,
+            comments: info.comments.clone(),
+        }
+    }
+    /// Map the type of a constant value into a Rhai type name.
+    fn value_type(&self, value: &Dynamic) -> String {
+        self.rust_type(self.engine.map_type_name(value.type_name()))
+    }
+    /// Map a [`TypeId`] into a Rhai type name.
+    fn type_id_name(&self, id: TypeId) -> String {
+        if let Some(name) = self.engine.type_ids.get(&id) {
+            return self.rust_type(name);
+        }
+
+        let name = if id == TypeId::of::<INT>() {
+            "int"
+        } else if id == TypeId::of::<bool>() {
+            "bool"
+        } else if id == TypeId::of::<char>() {
+            "char"
+        } else if id == TypeId::of::<ImmutableString>() || id == TypeId::of::<String>() {
+            "string"
+        } else if id == TypeId::of::<FnPtr>() {
+            "Fn"
+        } else if id == TypeId::of::<()>() {
+            "()"
+        } else {
+            #[cfg(not(feature = "no_float"))]
+            if id == TypeId::of::<FLOAT>() {
+                return "float".into();
+            }
+            #[cfg(not(feature = "no_index"))]
+            if id == TypeId::of::<Array>() {
+                return "array".into();
+            }
+            #[cfg(not(feature = "no_object"))]
+            if id == TypeId::of::<Map>() {
+                return "map".into();
+            }
+
+            UNKNOWN_TYPE
+        };
+
+        name.into()
+    }
+    /// Map the name of a Rust type (as written in source code) into a Rhai type name.
+    fn rust_type(&self, name: &str) -> String {
+        let name = name.trim();
+
+        // Strip references
+        let name = name
+            .trim_start_matches('&')
+            .trim_start()
+            .trim_start_matches("mut ")
+            .trim();
+
+        // Unwrap results
+        let name = match name.strip_prefix("Result<") {
+            Some(inner) => split_generic_arg(inner),
+            None => name,
+        };
+
+        // Resolve custom types registered under a pretty-print name
+        if let Some(pretty) = self.engine.type_names.get(name) {
+            return pretty.clone();
+        }
+
+        // Strip paths, except within generic arguments
+        let base = match name.find('<') {
+            Some(pos) => &name[..pos],
+            None => name,
+        };
+        let name = match base.rfind("::") {
+            Some(pos) => &name[pos + 2..],
+            None => name,
+        };
+
+        if let Some(pretty) = self
+            .engine
+            .type_names
+            .iter()
+            .find(|(k, _)| k.rsplit("::").next() == Some(name))
+            .map(|(_, v)| v)
+        {
+            return pretty.clone();
+        }
+
+        match name {
+            "INT" => "int",
+            "FLOAT" => "float",
+            "bool" => "bool",
+            "char" => "char",
+            "()" | "Unit" => "()",
+            "ImmutableString" | "String" | "str" | "string" => "string",
+            "Array" | "array" => "array",
+            "Map" | "map" => "map",
+            "FnPtr" | "Fn" => "Fn",
+            "Instant" | "timestamp" => "timestamp",
+            "Dynamic" => UNKNOWN_TYPE,
+            _ if name == type_name::<INT>() => "int",
+            #[cfg(not(feature = "no_float"))]
+            _ if name == type_name::<FLOAT>() => "float",
+            _ => name,
+        }
+        .into()
+    }
+}
+
+/// Get the first argument of a list of generic arguments (terminated by `>`).
+fn split_generic_arg(args: &str) -> &str {
+    let mut level = 0;
+
+    for (i, ch) in args.char_indices() {
+        match ch {
+            '<' | '(' => level += 1,
+            ')' => level -= 1,
+            '>' if level == 0 => return args[..i].trim(),
+            '>' => level -= 1,
+            ',' if level == 0 => return args[..i].trim(),
+            _ => (),
+        }
+    }
+
+    args.trim()
+}
+
+/// Classify a function by its name and parameters.
+fn classify(info: &FuncInfo, is_global: bool) -> FnKind {
+    #[cfg(not(feature = "no_object"))]
+    if let Some(prop) = info.name.strip_prefix(FN_GET) {
+        return FnKind::Getter(prop.into());
+    }
+    #[cfg(not(feature = "no_object"))]
+    if let Some(prop) = info.name.strip_prefix(FN_SET) {
+        return FnKind::Setter(prop.into());
+    }
+    #[cfg(not(feature = "no_index"))]
+    if info.name == FN_IDX_GET {
+        return FnKind::IndexGetter;
+    }
+    #[cfg(not(feature = "no_index"))]
+    if info.name == FN_IDX_SET {
+        return FnKind::IndexSetter;
+    }
+
+    let is_ident = info
+        .name
+        .chars()
+        .all(|ch| ch == '_' || ch.is_ascii_alphanumeric());
+
+    if is_global && is_ident && info.params > 0 && info.func.is_method() {
+        FnKind::Method
+    } else {
+        FnKind::Function
+    }
+}
+
+/// Is a Rhai type name a custom type?
+fn is_custom_type(name: &str) -> bool {
+    !matches!(
+        name,
+        "int"
+            | "float"
+            | "bool"
+            | "char"
+            | "()"
+            | "string"
+            | "array"
+            | "map"
+            | "Fn"
+            | "timestamp"
+            | UNKNOWN_TYPE
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "u8"
+            | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "isize"
+            | "usize"
+            | "f32"
+            | "f64"
+            | "Decimal"
+    )
+}
+
+/// Sort functions by name, number of parameters and parameter types.
+fn sort_fns(functions: &mut [FnDef]) {
+    functions.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.params.len().cmp(&b.params.len()))
+            .then_with(|| {
+                let a = a.params.iter().map(|p| p.typ.as_str());
+                let b = b.params.iter().map(|p| p.typ.as_str());
+                a.cmp(b)
+            })
+    });
+}
+
+/// Map a Rhai type name into a TypeScript type.
+fn ts_type(name: &str) -> String {
+    match name {
+        "int" | "float" | "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64"
+        | "u128" | "isize" | "usize" | "f32" | "f64" | "Decimal" => "number",
+        "string" | "char" => "string",
+        "bool" => "boolean",
+        "()" => "void",
+        "array" => "any[]",
+        "map" => "Record<string, any>",
+        "Fn" => "Function",
+        UNKNOWN_TYPE | "timestamp" => "any",
+        _ => name,
+    }
+    .into()
+}
+
+/// Write doc-comments at a particular level of indentation.
+fn write_comments(output: &mut String, comments: &[String], indent: &str) {
+    for comment in comments {
+        for line in comment.lines() {
+            let _ = writeln!(output, "{}{}", indent, line);
+        }
+    }
+}
+
+/// Write a list of parameters, skipping a number of leading parameters.
+fn write_params(output: &mut String, params: &[ParamDef], skip: usize, typescript: bool) {
+    let params: Vec<_> = params
+        .iter()
+        .skip(skip)
+        .map(|p| {
+            if typescript {
+                format!("{}: {}", p.name, ts_type(&p.typ))
+            } else {
+                format!("{}: {}", p.name, p.typ)
+            }
+        })
+        .collect();
+    output.push_str(&params.join(", "));
+}
+
+/// Write the return type of a function in Rhai style.
+fn write_rhai_return(output: &mut String, return_type: &str) {
+    if return_type != "()" {
+        let _ = write!(output, " -> {}", return_type);
+    }
+}
+
+/// Write a module in Rhai style.
+fn write_rhai_module(
+    output: &mut String,
+    module: &ModuleDef,
+    types: &BTreeMap<String, TypeDef>,
+    level: usize,
+) {
+    let indent = "    ".repeat(level);
+
+    for (name, typ) in module.constants.iter() {
+        let _ = write!(output, "\n{}const {}: {};\n", indent, name, typ);
+    }
+
+    for f in module.functions.iter() {
+        output.push('\n');
+        write_comments(output, &f.comments, &indent);
+        let _ = write!(output, "{}fn {}(", indent, f.name);
+        write_params(output, &f.params, 0, false);
+        output.push(')');
+        write_rhai_return(output, &f.return_type);
+        output.push_str(";\n");
+    }
+
+    for (name, t) in types.iter() {
+        let _ = write!(output, "\n{}type {} {{\n", indent, name);
+        let member_indent = format!("{}    ", indent);
+
+        for (prop, f) in t.getters.iter() {
+            write_comments(output, &f.comments, &member_indent);
+            let _ = writeln!(output, "{}get {}: {};", member_indent, prop, f.return_type);
+        }
+        for (prop, f) in t.setters.iter() {
+            let typ = f.params.get(1).map_or(UNKNOWN_TYPE, |p| p.typ.as_str());
+            write_comments(output, &f.comments, &member_indent);
+            let _ = writeln!(output, "{}set {}: {};", member_indent, prop, typ);
+        }
+        for f in t.index_getters.iter() {
+            write_comments(output, &f.comments, &member_indent);
+            let _ = write!(output, "{}get [", member_indent);
+            write_params(output, &f.params, 1, false);
+            let _ = writeln!(output, "]: {};", f.return_type);
+        }
+        for f in t.index_setters.iter() {
+            let typ = f.params.get(2).map_or(UNKNOWN_TYPE, |p| p.typ.as_str());
+            write_comments(output, &f.comments, &member_indent);
+            let _ = write!(output, "{}set [", member_indent);
+            let index: Vec<_> = f.params.iter().take(2).cloned().collect();
+            write_params(output, &index, 1, false);
+            let _ = writeln!(output, "]: {};", typ);
+        }
+        for f in t.methods.iter() {
+            write_comments(output, &f.comments, &member_indent);
+            let _ = write!(output, "{}fn {}(", member_indent, f.name);
+            write_params(output, &f.params, 1, false);
+            output.push(')');
+            write_rhai_return(output, &f.return_type);
+            output.push_str(";\n");
+        }
+
+        let _ = writeln!(output, "{}}}", indent);
+    }
+
+    for (name, sub_module) in module.modules.iter() {
+        let _ = write!(output, "\n{}module {} {{\n", indent, name);
+        write_rhai_module(output, sub_module, &Default::default(), level + 1);
+        let _ = writeln!(output, "{}}}", indent);
+    }
+}
+
+/// Write the root module in TypeScript style.
+fn write_ts_root(output: &mut String, module: &ModuleDef, types: &BTreeMap<String, TypeDef>) {
+    for (name, t) in types.iter().filter(|(name, _)| is_custom_type(name)) {
+        let _ = write!(output, "\ndeclare interface {} {{\n", name);
+
+        for (prop, f) in t.getters.iter() {
+            let readonly = if t.setters.contains_key(prop) {
+                ""
+            } else {
+                "readonly "
+            };
+            write_comments(output, &f.comments, "    ");
+            let _ = writeln!(
+                output,
+                "    {}{}: {};",
+                readonly,
+                prop,
+                ts_type(&f.return_type)
+            );
+        }
+        for (prop, f) in t.setters.iter() {
+            if !t.getters.contains_key(prop) {
+                let typ = f.params.get(1).map_or(UNKNOWN_TYPE, |p| p.typ.as_str());
+                write_comments(output, &f.comments, "    ");
+                let _ = writeln!(output, "    {}: {};", prop, ts_type(typ));
+            }
+        }
+        for f in t.index_getters.iter() {
+            write_comments(output, &f.comments, "    ");
+            output.push_str("    [");
+            write_params(output, &f.params, 1, true);
+            let _ = writeln!(output, "]: {};", ts_type(&f.return_type));
+        }
+        for f in t.methods.iter() {
+            write_comments(output, &f.comments, "    ");
+            let _ = write!(output, "    {}(", f.name);
+            write_params(output, &f.params, 1, true);
+            let _ = writeln!(output, "): {};", ts_type(&f.return_type));
+        }
+
+        output.push_str("}\n");
+    }
+
+    write_ts_module(output, module, 0);
+}
+
+/// Write a module in TypeScript style.
+fn write_ts_module(output: &mut String, module: &ModuleDef, level: usize) {
+    let indent = "    ".repeat(level);
+    let declare = if level == 0 { "declare " } else { "" };
+
+    for (name, typ) in module.constants.iter() {
+        let _ = write!(
+            output,
+            "\n{}{}const {}: {};\n",
+            indent,
+            declare,
+            name,
+            ts_type(typ)
+        );
+    }
+
+    for f in module.functions.iter().filter(|f| is_ts_ident(&f.name)) {
+        output.push('\n');
+        write_comments(output, &f.comments, &indent);
+        let _ = write!(output, "{}{}function {}(", indent, declare, f.name);
+        write_params(output, &f.params, 0, true);
+        let _ = writeln!(output, "): {};", ts_type(&f.return_type));
+    }
+
+    for (name, sub_module) in module.modules.iter() {
+        let _ = write!(output, "\n{}{}namespace {} {{\n", indent, declare, name);
+        write_ts_module(output, sub_module, level + 1);
+        let _ = writeln!(output, "{}}}", indent);
+    }
+}
+
+/// Is a function name a valid TypeScript identifier?  Operators are skipped.
+fn is_ts_ident(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|ch: char| ch.is_ascii_digit())
+        && name
+            .chars()
+            .all(|ch| ch == '_' || ch.is_ascii_alphanumeric())
+}
@@ -362,7 +362,7 @@ impl Dynamic {
 
 impl Hash for Dynamic {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        mem::discriminant(self).hash(state);
+        mem::discriminant(&self.0).hash(state);
 
         match &self.0 {
             Union::Unit(_, _) => ().hash(state),
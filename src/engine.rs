@@ -4,9 +4,10 @@ use crate::ast::{Expr, FnCallExpr, Ident, ReturnType, Stmt};
 use crate::dynamic::{map_std_type_name, AccessMode, Union, Variant};
 use crate::fn_call::run_builtin_op_assignment;
 use crate::fn_native::{
-    CallableFunction, IteratorFn, OnDebugCallback, OnPrintCallback, OnProgressCallback,
-    OnVarCallback,
+    CallableFunction, IteratorFn, OnDebugCallback, OnDebuggerCallback, OnDebuggerInitCallback,
+    OnDefVarCallback, OnMissingFnCallback, OnPrintCallback, OnProgressCallback, OnVarCallback,
 };
+use crate::interrupt::InterruptHandle;
 use crate::module::NamespaceRef;
 use crate::optimize::OptimizationLevel;
 use crate::packages::{Package, StandardPackage};
@@ -15,6 +16,7 @@ use crate::stdlib::{
     any::{type_name, TypeId},
     borrow::Cow,
     boxed::Box,
+    cell::RefCell,
     collections::{HashMap, HashSet},
     fmt, format,
     hash::{Hash, Hasher},
@@ -26,10 +28,20 @@ use crate::stdlib::{
 use crate::syntax::CustomSyntax;
 use crate::utils::{get_hasher, StraightHasherBuilder};
 use crate::{
-    calc_native_fn_hash, Dynamic, EvalAltResult, FnPtr, ImmutableString, Module, Position, Scope,
-    Shared, StaticVec,
+    calc_native_fn_hash, Capabilities, Debugger, Dynamic, EvalAltResult, FnPtr, ImmutableString,
+    Module, NativeCallContext, Position, Scope, Shared, StaticVec,
 };
 
+#[cfg(not(feature = "unchecked"))]
+use crate::fn_native::OnClockCallback;
+use crate::stats::EvalStats;
+
+#[cfg(not(feature = "unchecked"))]
+use crate::stdlib::time::Duration;
+
+#[cfg(not(feature = "unchecked"))]
+use crate::stdlib::vec::Vec;
+
 #[cfg(not(feature = "no_index"))]
 use crate::Array;
 
@@ -521,6 +533,35 @@ pub struct State {
         Option<(CallableFunction, Option<ImmutableString>)>,
         StraightHasherBuilder,
     >,
+    /// State of the debugger, if any.
+    pub debugger: Option<Box<Debugger>>,
+    /// State of the execution profiler, if any.
+    #[cfg(not(feature = "no_std"))]
+    pub profiler: Option<Box<crate::profiler::ProfilerState>>,
+    /// Approximate memory (in bytes) used by the values held in the scopes of all the calling
+    /// functions.
+    #[cfg(not(feature = "unchecked"))]
+    pub memory_outer: usize,
+    /// Approximate memory (in bytes) used by the values held in the current scope, as of the last
+    /// statement evaluated.
+    #[cfg(not(feature = "unchecked"))]
+    pub memory: usize,
+    /// Approximate memory (in bytes) used by each variable in the current scope, or [`None`] if
+    /// the variable is modified after its memory is estimated.
+    #[cfg(not(feature = "unchecked"))]
+    pub memory_vars: Vec<Option<usize>>,
+    /// Resource usage recorded so far, if requested.
+    pub stats: Option<Box<EvalStats>>,
+    /// Capabilities granted to this evaluation, if restricted.
+    pub capabilities: Option<Shared<Capabilities>>,
+    /// Handle to request termination of this evaluation, if any.
+    pub interrupt: Option<InterruptHandle>,
+    /// Reading of the [`Engine`]'s clock when evaluation started, if it has.
+    #[cfg(not(feature = "unchecked"))]
+    pub start_time: Option<Duration>,
+    /// Maximum duration of this evaluation, overriding that of the [`Engine`] (if any).
+    #[cfg(not(feature = "unchecked"))]
+    pub max_duration: Option<Duration>,
 }
 
 impl State {
@@ -529,6 +570,22 @@ impl State {
     pub fn is_global(&self) -> bool {
         self.scope_level == 0
     }
+    /// Record the sizes of a value, as calculated by [`calc_data_size`], in the [`EvalStats`] if
+    /// requested.
+    #[inline]
+    pub(crate) fn record_data_size(&mut self, (_arr, _map, s): (usize, usize, usize)) {
+        if let Some(stats) = self.stats.as_mut() {
+            stats.max_string_size = stats.max_string_size.max(s);
+            #[cfg(not(feature = "no_index"))]
+            {
+                stats.max_array_size = stats.max_array_size.max(_arr);
+            }
+            #[cfg(not(feature = "no_object"))]
+            {
+                stats.max_map_size = stats.max_map_size.max(_map);
+            }
+        }
+    }
 }
 
 /// _(INTERNALS)_ A type containing all the limits imposed by the [`Engine`].
@@ -566,6 +623,72 @@ pub struct Limits {
     /// Not available under `no_object`.
     #[cfg(not(feature = "no_object"))]
     pub max_map_size: usize,
+    /// Maximum approximate memory (in bytes) used by all values (0 = unlimited).
+    pub max_memory: usize,
+    /// Maximum wall-clock duration of an evaluation ([`None`] = unlimited).
+    pub max_duration: Option<Duration>,
+}
+
+/// _(INTERNALS)_ A type containing the language features allowed by the [`Engine`] when parsing
+/// scripts.
+/// Exported under the `internals` feature only.
+///
+/// # WARNING
+///
+/// This type is volatile and may change.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LanguageOptions {
+    /// Allow `if` statements to be used as expressions?
+    pub allow_if_expr: bool,
+    /// Allow `switch` statements to be used as expressions?
+    pub allow_switch_expr: bool,
+    /// Allow `while`, `loop`, `do` and `for` loops?
+    pub allow_looping: bool,
+    /// Allow script-defined functions?
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    pub allow_functions: bool,
+    /// Allow anonymous functions and closures?
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    pub allow_anonymous_fn: bool,
+    /// Allow variables to shadow other variables of the same name?
+    pub allow_shadowing: bool,
+    /// Allow calls to `eval`?
+    pub allow_eval: bool,
+    /// Allow `import` statements?
+    /// Not available under `no_module`.
+    #[cfg(not(feature = "no_module"))]
+    pub allow_import: bool,
+    /// Allow the `this` pointer to be used in functions?
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    pub allow_implicit_this: bool,
+    /// Reject variables which are not defined when compiling scripts?
+    pub strict_var: bool,
+}
+
+impl Default for LanguageOptions {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            allow_if_expr: true,
+            allow_switch_expr: true,
+            allow_looping: true,
+            #[cfg(not(feature = "no_function"))]
+            allow_functions: true,
+            #[cfg(not(feature = "no_function"))]
+            allow_anonymous_fn: true,
+            allow_shadowing: true,
+            allow_eval: true,
+            #[cfg(not(feature = "no_module"))]
+            allow_import: true,
+            #[cfg(not(feature = "no_function"))]
+            allow_implicit_this: true,
+
+            strict_var: false,
+        }
+    }
 }
 
 /// Context of a script evaluation process.
@@ -637,6 +760,36 @@ impl<'e, 'x, 'px, 'a, 's, 'm, 'pm, 't, 'pt> EvalContext<'e, 'x, 'px, 'a, 's, 'm,
     pub fn call_level(&self) -> usize {
         self.level
     }
+    /// The current state of the debugger, if any.
+    #[inline(always)]
+    pub fn debugger(&self) -> Option<&Debugger> {
+        self.state.debugger.as_ref().map(|d| d.as_ref())
+    }
+    /// Mutable reference to the current state of the debugger, if any.
+    #[inline(always)]
+    pub fn debugger_mut(&mut self) -> Option<&mut Debugger> {
+        self.state.debugger.as_mut().map(|d| d.as_mut())
+    }
+    /// Evaluate an expression within the current context, e.g. while execution is paused
+    /// in the debugger.
+    ///
+    /// The expression has access to the current [`Scope`], `this` and imported modules.
+    /// Side effects, such as assignments to variables, are kept.
+    pub fn eval_expression(&mut self, script: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+        let ast = self.engine.compile_expression(script)?;
+
+        ast.statements().iter().try_fold(Dynamic::UNIT, |_, stmt| {
+            self.engine.eval_stmt(
+                self.scope,
+                self.mods,
+                self.state,
+                self.lib,
+                self.this_ptr,
+                stmt,
+                self.level,
+            )
+        })
+    }
 }
 
 /// Rhai main scripting engine.
@@ -679,6 +832,9 @@ pub struct Engine {
 
     /// A hashmap mapping type names to pretty-print names.
     pub(crate) type_names: HashMap<String, String>,
+    /// A hashmap mapping type ID's of custom types to pretty-print names.
+    #[cfg(feature = "metadata")]
+    pub(crate) type_ids: HashMap<TypeId, String>,
 
     /// A hashset containing symbols to disable.
     pub(crate) disabled_symbols: HashSet<String>,
@@ -688,6 +844,13 @@ pub struct Engine {
     pub(crate) custom_syntax: HashMap<ImmutableString, CustomSyntax>,
     /// Callback closure for resolving variable access.
     pub(crate) resolve_var: Option<OnVarCallback>,
+    /// A hashset containing names of variables resolved externally, e.g. via the variable
+    /// resolver, which are allowed under strict variables mode.
+    pub(crate) external_vars: HashSet<String>,
+    /// Callback closure for observing variable definitions.
+    pub(crate) def_var: Option<OnDefVarCallback>,
+    /// Callback closure for resolving functions that are not found.
+    pub(crate) missing_fn: Option<OnMissingFnCallback>,
 
     /// Callback closure for implementing the `print` command.
     pub(crate) print: OnPrintCallback,
@@ -695,13 +858,29 @@ pub struct Engine {
     pub(crate) debug: OnDebugCallback,
     /// Callback closure for progress reporting.
     pub(crate) progress: Option<OnProgressCallback>,
+    /// Handle to request termination of all evaluations running on the engine.
+    pub(crate) interrupt: InterruptHandle,
+    /// Callback closures for initializing the debugger and for debugging.
+    pub(crate) debugger: Option<(OnDebuggerInitCallback, OnDebuggerCallback)>,
+    /// Report of the execution profiler, if enabled.
+    #[cfg(not(feature = "no_std"))]
+    pub(crate) profiler: Option<Shared<crate::fn_native::Locked<crate::ProfileReport>>>,
+    /// Report of statement and branch coverage, if enabled.
+    pub(crate) coverage: Option<Shared<crate::fn_native::Locked<crate::CoverageReport>>>,
 
     /// Optimize the AST after compilation.
     pub(crate) optimization_level: OptimizationLevel,
 
+    /// Language features allowed when parsing scripts.
+    pub(crate) options: LanguageOptions,
+
     /// Max limits.
     #[cfg(not(feature = "unchecked"))]
     pub(crate) limits: Limits,
+    /// Callback closure for reading a monotonic clock, used to enforce the maximum duration and to
+    /// measure the time taken by evaluations.
+    #[cfg(not(feature = "unchecked"))]
+    pub(crate) clock: Option<OnClockCallback>,
 
     /// Disable doc-comments?
     pub(crate) disable_doc_comments: bool,
@@ -766,6 +945,89 @@ fn default_debug(_s: &str, _source: Option<&str>, _pos: Position) {
     }
 }
 
+/// Monotonic clock measuring the time elapsed since it is created.
+///
+/// There is no default clock under `no_std`.
+#[cfg(not(feature = "unchecked"))]
+#[inline(always)]
+fn default_clock() -> Option<OnClockCallback> {
+    #[cfg(not(feature = "no_std"))]
+    #[cfg(not(target_arch = "wasm32"))]
+    let start = crate::stdlib::time::Instant::now();
+    #[cfg(not(feature = "no_std"))]
+    #[cfg(target_arch = "wasm32")]
+    let start = instant::Instant::now();
+
+    #[cfg(not(feature = "no_std"))]
+    return Some(Box::new(move || start.elapsed()));
+    #[cfg(feature = "no_std")]
+    return None;
+}
+
+/// Recursively calculate the size of a value (especially `Array` and `Map`), returning the total
+/// number of items in arrays, the total number of items in object maps, and the length of a string.
+fn calc_data_size(value: &Dynamic) -> (usize, usize, usize) {
+    match value {
+        #[cfg(not(feature = "no_index"))]
+        Dynamic(Union::Array(arr, _)) => {
+            let mut arrays = 0;
+            let mut maps = 0;
+
+            arr.iter().for_each(|value| match value {
+                Dynamic(Union::Array(_, _)) => {
+                    let (a, m, _) = calc_data_size(value);
+                    arrays += a;
+                    maps += m;
+                }
+                #[cfg(not(feature = "no_object"))]
+                Dynamic(Union::Map(_, _)) => {
+                    let (a, m, _) = calc_data_size(value);
+                    arrays += a;
+                    maps += m;
+                }
+                _ => arrays += 1,
+            });
+
+            (arrays, maps, 0)
+        }
+        #[cfg(not(feature = "no_object"))]
+        Dynamic(Union::Map(map, _)) => {
+            let mut arrays = 0;
+            let mut maps = 0;
+
+            map.values().for_each(|value| match value {
+                #[cfg(not(feature = "no_index"))]
+                Dynamic(Union::Array(_, _)) => {
+                    let (a, m, _) = calc_data_size(value);
+                    arrays += a;
+                    maps += m;
+                }
+                Dynamic(Union::Map(_, _)) => {
+                    let (a, m, _) = calc_data_size(value);
+                    arrays += a;
+                    maps += m;
+                }
+                _ => maps += 1,
+            });
+
+            (arrays, maps, 0)
+        }
+        Dynamic(Union::Str(s, _)) => (0, 0, s.len()),
+        _ => (0, 0, 0),
+    }
+}
+
+/// Does a dot/index chain contain a method call (which may modify the object)?
+#[cfg(not(feature = "unchecked"))]
+#[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+fn has_method_call(expr: &Expr) -> bool {
+    match expr {
+        Expr::FnCall(_, _) => true,
+        Expr::Dot(x, _) | Expr::Index(x, _) => has_method_call(&x.lhs) || has_method_call(&x.rhs),
+        _ => false,
+    }
+}
+
 /// Search for a module within an imports stack.
 /// [`Position`] in [`EvalAltResult`] is [`None`][Position::None] and must be set afterwards.
 pub fn search_imports(
@@ -813,12 +1075,17 @@ impl Engine {
             module_resolver: Box::new(crate::module::resolvers::DummyModuleResolver::new()),
 
             type_names: Default::default(),
+            #[cfg(feature = "metadata")]
+            type_ids: Default::default(),
             disabled_symbols: Default::default(),
             custom_keywords: Default::default(),
             custom_syntax: Default::default(),
 
             // variable resolver
             resolve_var: None,
+            external_vars: Default::default(),
+            def_var: None,
+            missing_fn: None,
 
             // default print/debug implementations
             print: Box::new(default_print),
@@ -827,6 +1094,19 @@ impl Engine {
             // progress callback
             progress: None,
 
+            // interrupt handle
+            interrupt: InterruptHandle::new(),
+
+            // debugger
+            debugger: None,
+
+            // profiler
+            #[cfg(not(feature = "no_std"))]
+            profiler: None,
+
+            // coverage
+            coverage: None,
+
             // optimization level
             optimization_level: if cfg!(feature = "no_optimize") {
                 OptimizationLevel::None
@@ -834,6 +1114,9 @@ impl Engine {
                 OptimizationLevel::Simple
             },
 
+            // language options
+            options: Default::default(),
+
             #[cfg(not(feature = "unchecked"))]
             limits: Limits {
                 #[cfg(not(feature = "no_function"))]
@@ -849,7 +1132,11 @@ impl Engine {
                 max_array_size: 0,
                 #[cfg(not(feature = "no_object"))]
                 max_map_size: 0,
+                max_memory: 0,
+                max_duration: None,
             },
+            #[cfg(not(feature = "unchecked"))]
+            clock: default_clock(),
 
             disable_doc_comments: false,
         };
@@ -875,15 +1162,25 @@ impl Engine {
             module_resolver: Box::new(crate::module::resolvers::DummyModuleResolver::new()),
 
             type_names: Default::default(),
+            #[cfg(feature = "metadata")]
+            type_ids: Default::default(),
             disabled_symbols: Default::default(),
             custom_keywords: Default::default(),
             custom_syntax: Default::default(),
 
             resolve_var: None,
+            external_vars: Default::default(),
+            def_var: None,
+            missing_fn: None,
 
             print: Box::new(|_| {}),
             debug: Box::new(|_, _, _| {}),
             progress: None,
+            interrupt: InterruptHandle::new(),
+            debugger: None,
+            #[cfg(not(feature = "no_std"))]
+            profiler: None,
+            coverage: None,
 
             optimization_level: if cfg!(feature = "no_optimize") {
                 OptimizationLevel::None
@@ -891,6 +1188,8 @@ impl Engine {
                 OptimizationLevel::Simple
             },
 
+            options: Default::default(),
+
             #[cfg(not(feature = "unchecked"))]
             limits: Limits {
                 #[cfg(not(feature = "no_function"))]
@@ -906,7 +1205,11 @@ impl Engine {
                 max_array_size: 0,
                 #[cfg(not(feature = "no_object"))]
                 max_map_size: 0,
+                max_memory: 0,
+                max_duration: None,
             },
+            #[cfg(not(feature = "unchecked"))]
+            clock: default_clock(),
 
             disable_doc_comments: false,
         }
@@ -1021,6 +1324,41 @@ impl Engine {
         Ok((val.into(), *pos))
     }
 
+    /// Check a variable definition with the variable definition callback, if any.
+    fn check_def_var(
+        &self,
+        scope: &mut Scope,
+        mods: &mut Imports,
+        state: &mut State,
+        lib: &[&Module],
+        this_ptr: &mut Option<&mut Dynamic>,
+        name: &str,
+        is_const: bool,
+        pos: Position,
+        level: usize,
+    ) -> Result<(), Box<EvalAltResult>> {
+        if let Some(ref def_var) = self.def_var {
+            let scope_level = state.scope_level;
+            let context = EvalContext {
+                engine: self,
+                scope,
+                mods,
+                state,
+                lib,
+                this_ptr,
+                level,
+            };
+
+            if !def_var(name, is_const, scope_level, &context)
+                .map_err(|err| err.fill_position(pos))?
+            {
+                return EvalAltResult::ErrorForbiddenVariable(name.to_string(), pos).into();
+            }
+        }
+
+        Ok(())
+    }
+
     /// Chain-evaluate a dot/index chain.
     /// [`Position`] in [`EvalAltResult`] is [`None`][Position::None] and must be set afterwards.
     #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
@@ -1371,6 +1709,12 @@ impl Engine {
 
                 self.inc_operations(state, *var_pos)?;
 
+                // Assignments and method calls may modify the variable
+                #[cfg(not(feature = "unchecked"))]
+                if self.max_memory() > 0 && (new_val.is_some() || has_method_call(rhs)) {
+                    self.touch_memory(scope, state, lhs);
+                }
+
                 let (target, pos) =
                     self.search_namespace(scope, mods, state, lib, this_ptr, lhs)?;
 
@@ -1432,6 +1776,12 @@ impl Engine {
                     })
                     .collect::<Result<StaticVec<_>, _>>()?;
 
+                if self.debugger.is_some() {
+                    let calls_script =
+                        self.has_override(Some(mods), lib, None, x.hash_script, false);
+                    self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &arg_values);
+                }
+
                 idx_values.push(arg_values.into());
             }
             Expr::FnCall(_, _) if parent_chain_type == ChainType::Dot => {
@@ -1455,13 +1805,27 @@ impl Engine {
                     Expr::FnCall(x, _)
                         if parent_chain_type == ChainType::Dot && x.namespace.is_none() =>
                     {
-                        x.args
+                        let arg_values = x
+                            .args
                             .iter()
                             .map(|arg_expr| {
                                 self.eval_expr(scope, mods, state, lib, this_ptr, arg_expr, level)
                             })
-                            .collect::<Result<StaticVec<Dynamic>, _>>()?
-                            .into()
+                            .collect::<Result<StaticVec<Dynamic>, _>>()?;
+
+                        if self.debugger.is_some() {
+                            let calls_script =
+                                self.has_override(Some(mods), lib, None, x.hash_script, false);
+                            self.debugger_suspend_frame(
+                                state,
+                                scope,
+                                this_ptr,
+                                calls_script,
+                                &arg_values,
+                            );
+                        }
+
+                        arg_values.into()
                     }
                     Expr::FnCall(_, _) if parent_chain_type == ChainType::Dot => {
                         unreachable!("function call in dot chain should not be namespace-qualified")
@@ -1888,6 +2252,26 @@ impl Engine {
                 self.eval_in_expr(scope, mods, state, lib, this_ptr, &x.lhs, &x.rhs, level)
             }
 
+            Expr::And(x, pos) if self.coverage.is_some() => {
+                let lhs = self
+                    .eval_expr(scope, mods, state, lib, this_ptr, &x.lhs, level)?
+                    .as_bool()
+                    .map_err(|err| self.make_type_mismatch_err::<bool>(err, x.lhs.position()))?;
+
+                // Short-circuit using &&
+                if !lhs {
+                    self.coverage_branch(state, *pos, Position::NONE);
+                    return Ok(lhs.into());
+                }
+
+                self.coverage_branch(state, *pos, x.rhs.position());
+                Ok(self
+                    .eval_expr(scope, mods, state, lib, this_ptr, &x.rhs, level)?
+                    .as_bool()
+                    .map_err(|err| self.make_type_mismatch_err::<bool>(err, x.rhs.position()))?
+                    .into())
+            }
+
             Expr::And(x, _) => {
                 Ok((self
                     .eval_expr(scope, mods, state, lib, this_ptr, &x.lhs, level)?
@@ -1901,6 +2285,26 @@ impl Engine {
                 .into())
             }
 
+            Expr::Or(x, pos) if self.coverage.is_some() => {
+                let lhs = self
+                    .eval_expr(scope, mods, state, lib, this_ptr, &x.lhs, level)?
+                    .as_bool()
+                    .map_err(|err| self.make_type_mismatch_err::<bool>(err, x.lhs.position()))?;
+
+                // Short-circuit using ||
+                if lhs {
+                    self.coverage_branch(state, *pos, Position::NONE);
+                    return Ok(lhs.into());
+                }
+
+                self.coverage_branch(state, *pos, x.rhs.position());
+                Ok(self
+                    .eval_expr(scope, mods, state, lib, this_ptr, &x.rhs, level)?
+                    .as_bool()
+                    .map_err(|err| self.make_type_mismatch_err::<bool>(err, x.rhs.position()))?
+                    .into())
+            }
+
             Expr::Or(x, _) => {
                 Ok((self
                     .eval_expr(scope, mods, state, lib, this_ptr, &x.lhs, level)?
@@ -1938,7 +2342,7 @@ impl Engine {
             _ => unreachable!("expression cannot be evaluated: {:?}", expr),
         };
 
-        self.check_data_size(result, expr.position())
+        self.check_data_size(state, result, expr.position())
     }
 
     /// Evaluate a statements block.
@@ -1980,11 +2384,59 @@ impl Engine {
 
     /// Evaluate a statement.
     ///
+    /// The debugger, if any, is invoked before the statement is evaluated and when it raises an error.
+    /// The statement is also recorded for coverage, if enabled.
+    pub(crate) fn eval_stmt(
+        &self,
+        scope: &mut Scope,
+        mods: &mut Imports,
+        state: &mut State,
+        lib: &[&Module],
+        this_ptr: &mut Option<&mut Dynamic>,
+        stmt: &Stmt,
+        level: usize,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        if self.coverage.is_some() {
+            self.coverage_stmt(state, stmt);
+        }
+
+        if self.debugger.is_none() {
+            return self.eval_stmt_raw(scope, mods, state, lib, this_ptr, stmt, level);
+        }
+
+        let context = &mut EvalContext {
+            engine: self,
+            scope,
+            mods,
+            state,
+            lib,
+            this_ptr,
+            level,
+        };
+
+        self.run_debugger(context, stmt)?;
+
+        match self.eval_stmt_raw(
+            context.scope,
+            context.mods,
+            context.state,
+            lib,
+            context.this_ptr,
+            stmt,
+            level,
+        ) {
+            Err(err) => Err(self.run_debugger_on_error(context, err)),
+            result => result,
+        }
+    }
+
+    /// Evaluate a statement, without invoking the debugger.
+    ///
     /// # Safety
     ///
     /// This method uses some unsafe code, mainly for avoiding cloning of local variable names via
     /// direct lifetime casting.
-    pub(crate) fn eval_stmt(
+    fn eval_stmt_raw(
         &self,
         scope: &mut Scope,
         mods: &mut Imports,
@@ -2009,6 +2461,7 @@ impl Engine {
                 let mut rhs_val = self
                     .eval_expr(scope, mods, state, lib, this_ptr, rhs_expr, level)?
                     .flatten();
+                self.touch_memory(scope, state, lhs_expr);
                 let (mut lhs_ptr, pos) =
                     self.search_namespace(scope, mods, state, lib, this_ptr, lhs_expr)?;
 
@@ -2072,17 +2525,24 @@ impl Engine {
                             let args = &mut [lhs_ptr_inner, &mut rhs_val];
 
                             // Overriding exact implementation
-                            let source = if source.is_none() {
-                                state.source.as_ref()
+                            let source = source.or_else(|| state.source.as_ref()).cloned();
+                            let operations = state.operations;
+
+                            let shared_state = RefCell::new(&mut *state);
+                            let context =
+                                NativeCallContext::from((self, source.as_ref(), &*mods, lib))
+                                    .with_state(&shared_state)
+                                    .with_position(*op_pos);
+
+                            let result = if func.is_plugin_fn() {
+                                func.get_plugin_fn().call(context, args)
                             } else {
-                                source
+                                func.get_native_fn()(context, args)
                             };
-                            if func.is_plugin_fn() {
-                                func.get_plugin_fn()
-                                    .call((self, source, &*mods, lib).into(), args)?;
-                            } else {
-                                func.get_native_fn()((self, source, &*mods, lib).into(), args)?;
-                            }
+
+                            // Charge any cost added by the function
+                            self.sync_operations(state, operations, *op_pos)?;
+                            result?;
                         }
                         // Built-in op-assignment function
                         _ if run_builtin_op_assignment(op, lhs_ptr.as_mut(), &rhs_val)?
@@ -2109,6 +2569,13 @@ impl Engine {
                             }
                         }
                     }
+
+                    // The result is not checked, but the new value should be recorded
+                    if state.stats.is_some() {
+                        if let Some(value) = lhs_ptr.as_mut().read_lock::<Dynamic>() {
+                            state.record_data_size(calc_data_size(&value));
+                        }
+                    }
                     Ok(Dynamic::UNIT)
                 }
             }
@@ -2172,24 +2639,33 @@ impl Engine {
             }
 
             // If statement
-            Stmt::If(expr, x, _) => {
+            Stmt::If(expr, x, pos) => {
                 let (if_block, else_block) = x.as_ref();
                 self.eval_expr(scope, mods, state, lib, this_ptr, expr, level)?
                     .as_bool()
                     .map_err(|err| self.make_type_mismatch_err::<bool>(err, expr.position()))
                     .and_then(|guard_val| {
                         if guard_val {
+                            if self.coverage.is_some() {
+                                self.coverage_branch(state, *pos, if_block.position());
+                            }
                             self.eval_stmt(scope, mods, state, lib, this_ptr, if_block, level)
                         } else if let Some(stmt) = else_block {
+                            if self.coverage.is_some() {
+                                self.coverage_branch(state, *pos, stmt.position());
+                            }
                             self.eval_stmt(scope, mods, state, lib, this_ptr, stmt, level)
                         } else {
+                            if self.coverage.is_some() {
+                                self.coverage_branch(state, *pos, Position::NONE);
+                            }
                             Ok(Dynamic::UNIT)
                         }
                     })
             }
 
             // Switch statement
-            Stmt::Switch(match_expr, x, _) => {
+            Stmt::Switch(match_expr, x, pos) => {
                 let (table, def_stmt) = x.as_ref();
 
                 let hasher = &mut get_hasher();
@@ -2202,10 +2678,19 @@ impl Engine {
                 let hash = hasher.finish();
 
                 if let Some(stmt) = table.get(&hash) {
+                    if self.coverage.is_some() {
+                        self.coverage_branch(state, *pos, stmt.position());
+                    }
                     self.eval_stmt(scope, mods, state, lib, this_ptr, stmt, level)
                 } else if let Some(def_stmt) = def_stmt {
+                    if self.coverage.is_some() {
+                        self.coverage_branch(state, *pos, def_stmt.position());
+                    }
                     self.eval_stmt(scope, mods, state, lib, this_ptr, def_stmt, level)
                 } else {
+                    if self.coverage.is_some() {
+                        self.coverage_branch(state, *pos, Position::NONE);
+                    }
                     Ok(Dynamic::UNIT)
                 }
             }
@@ -2258,7 +2743,7 @@ impl Engine {
             },
 
             // For loop
-            Stmt::For(expr, x, _) => {
+            Stmt::For(expr, x, for_pos) => {
                 let (name, stmt) = x.as_ref();
                 let iter_obj = self.eval_expr(scope, mods, state, lib, this_ptr, expr, level)?;
                 let iter_type = iter_obj.type_id();
@@ -2274,6 +2759,10 @@ impl Engine {
                     .or_else(|| mods.get_iter(iter_type));
 
                 if let Some(func) = func {
+                    self.check_def_var(
+                        scope, mods, state, lib, this_ptr, name, false, *for_pos, level,
+                    )?;
+
                     // Add the loop variable
                     let var_name: Cow<'_, str> = if state.is_global() {
                         name.clone().into()
@@ -2294,6 +2783,7 @@ impl Engine {
                             *loop_var = value;
                         }
 
+                        self.touch_memory_index(state, index);
                         self.inc_operations(state, stmt.position())?;
 
                         match self.eval_stmt(scope, mods, state, lib, this_ptr, stmt, level) {
@@ -2345,6 +2835,7 @@ impl Engine {
 
                         if let Some(Ident { name, .. }) = err_var {
                             scope.push(unsafe_cast_var_name_to_lifetime(&name), value);
+                            self.touch_memory_index(state, scope.len() - 1);
                         }
 
                         let result =
@@ -2405,6 +2896,19 @@ impl Engine {
                 } else {
                     Dynamic::UNIT
                 };
+
+                self.check_def_var(
+                    scope,
+                    mods,
+                    state,
+                    lib,
+                    this_ptr,
+                    &var_def.name,
+                    entry_type == AccessMode::ReadOnly,
+                    var_def.pos,
+                    level,
+                )?;
+
                 let (var_name, _alias): (Cow<'_, str>, _) = if state.is_global() {
                     (
                         var_def.name.to_string().into(),
@@ -2420,6 +2924,7 @@ impl Engine {
                     (unsafe_cast_var_name_to_lifetime(&var_def.name).into(), None)
                 };
                 scope.push_dynamic_value(var_name, entry_type, val);
+                self.touch_memory_index(state, scope.len() - 1);
 
                 #[cfg(not(feature = "no_module"))]
                 if let Some(alias) = _alias {
@@ -2489,132 +2994,244 @@ impl Engine {
                     if !val.is_shared() {
                         // Replace the variable with a shared value.
                         *val = crate::stdlib::mem::take(val).into_shared();
+                        self.touch_memory_index(state, index);
                     }
                 }
                 Ok(Dynamic::UNIT)
             }
         };
 
-        self.check_data_size(result, stmt.position())
+        let result = self.check_data_size(state, result, stmt.position());
+        self.check_memory(scope, state, result, stmt.position())
     }
 
     /// Check a result to ensure that the data size is within allowable limit.
-    /// [`Position`] in [`EvalAltResult`] may be None and should be set afterwards.
+    ///
+    /// The sizes are also recorded in the [`EvalStats`], if requested.
+    fn check_data_size(
+        &self,
+        state: &mut State,
+        result: Result<Dynamic, Box<EvalAltResult>>,
+        _pos: Position,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let _checked = match result {
+            // Simply return all errors
+            Err(_) => return result,
+            // String with limit
+            #[cfg(not(feature = "unchecked"))]
+            Ok(Dynamic(Union::Str(_, _))) => self.max_string_size() > 0,
+            // Array with limit
+            #[cfg(not(feature = "unchecked"))]
+            #[cfg(not(feature = "no_index"))]
+            Ok(Dynamic(Union::Array(_, _))) => self.max_array_size() > 0,
+            // Map with limit
+            #[cfg(not(feature = "unchecked"))]
+            #[cfg(not(feature = "no_object"))]
+            Ok(Dynamic(Union::Map(_, _))) => self.max_map_size() > 0,
+            // Everything else is not checked
+            Ok(_) => false,
+        };
+
+        if !_checked && state.stats.is_none() {
+            return result;
+        }
+
+        let (_arr, _map, s) = calc_data_size(result.as_ref().unwrap());
+
+        state.record_data_size((_arr, _map, s));
+
+        #[cfg(not(feature = "unchecked"))]
+        if _checked {
+            if s > self.max_string_size() {
+                return EvalAltResult::ErrorDataTooLarge("Length of string".to_string(), _pos)
+                    .into();
+            }
+
+            #[cfg(not(feature = "no_index"))]
+            if _arr > self.max_array_size() {
+                return EvalAltResult::ErrorDataTooLarge("Size of array".to_string(), _pos).into();
+            }
+
+            #[cfg(not(feature = "no_object"))]
+            if _map > self.max_map_size() {
+                return EvalAltResult::ErrorDataTooLarge("Size of object map".to_string(), _pos)
+                    .into();
+            }
+        }
+
+        result
+    }
+
+    /// Mark a variable as modified, so that the memory it uses is estimated again after the
+    /// current statement.
     #[cfg(feature = "unchecked")]
     #[inline(always)]
-    fn check_data_size(
+    pub(crate) fn touch_memory(&self, _scope: &Scope, _state: &mut State, _expr: &Expr) {}
+
+    /// Mark the variable at a particular index of the [`Scope`] as modified.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    fn touch_memory_index(&self, _state: &mut State, _index: usize) {}
+
+    /// Mark a variable as modified, so that the memory it uses is estimated again after the
+    /// current statement.
+    ///
+    /// Only variables in the current [`Scope`] are tracked.
+    #[cfg(not(feature = "unchecked"))]
+    pub(crate) fn touch_memory(&self, scope: &Scope, state: &mut State, expr: &Expr) {
+        if self.max_memory() == 0 {
+            return;
+        }
+
+        let index = match expr {
+            Expr::Variable(x) if x.1.is_none() && (x.2).name != KEYWORD_THIS => {
+                let (index, _, Ident { name, .. }) = x.as_ref();
+
+                match index {
+                    Some(index) if !state.always_search => scope.len().checked_sub(index.get()),
+                    _ => scope.get_index(name).map(|(i, _)| i),
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(index) = index {
+            self.touch_memory_index(state, index);
+        }
+    }
+
+    /// Mark the variable at a particular index of the [`Scope`] as modified.
+    #[cfg(not(feature = "unchecked"))]
+    fn touch_memory_index(&self, state: &mut State, index: usize) {
+        if let Some(size) = state.memory_vars.get_mut(index).and_then(Option::take) {
+            state.memory -= size;
+        }
+    }
+
+    /// Check the approximate memory used by all values to ensure that it is within allowable limit.
+    #[cfg(feature = "unchecked")]
+    #[inline(always)]
+    fn check_memory(
         &self,
+        _scope: &Scope,
+        _state: &mut State,
         result: Result<Dynamic, Box<EvalAltResult>>,
         _pos: Position,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
         result
     }
 
-    /// Check a result to ensure that the data size is within allowable limit.
+    /// Check the approximate memory used by all values to ensure that it is within allowable limit.
+    ///
+    /// The memory used by the values held in the current [`Scope`] plus the result is added to that
+    /// used by the calling functions.
+    ///
+    /// The memory used by each variable is tracked incrementally: only variables which are added
+    /// to the [`Scope`] or marked as modified (via [`touch_memory`][Engine::touch_memory]) since
+    /// the last statement are estimated again.
+    /// Values shared via strings or closures are counted once within each variable, and their
+    /// memory is divided among all the references to them.
     #[cfg(not(feature = "unchecked"))]
-    fn check_data_size(
+    fn check_memory(
         &self,
+        scope: &Scope,
+        state: &mut State,
         result: Result<Dynamic, Box<EvalAltResult>>,
         pos: Position,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
-        // If no data size limits, just return
-        let mut total = 0;
-
-        total += self.max_string_size();
-        #[cfg(not(feature = "no_index"))]
-        {
-            total += self.max_array_size();
-        }
-        #[cfg(not(feature = "no_object"))]
-        {
-            total += self.max_map_size();
-        }
-
-        if total == 0 {
+        if self.max_memory() == 0 {
             return result;
         }
 
-        // Recursively calculate the size of a value (especially `Array` and `Map`)
-        fn calc_size(value: &Dynamic) -> (usize, usize, usize) {
-            match value {
-                #[cfg(not(feature = "no_index"))]
-                Dynamic(Union::Array(arr, _)) => {
-                    let mut arrays = 0;
-                    let mut maps = 0;
-
-                    arr.iter().for_each(|value| match value {
-                        Dynamic(Union::Array(_, _)) => {
-                            let (a, m, _) = calc_size(value);
-                            arrays += a;
-                            maps += m;
-                        }
-                        #[cfg(not(feature = "no_object"))]
-                        Dynamic(Union::Map(_, _)) => {
-                            let (a, m, _) = calc_size(value);
-                            arrays += a;
-                            maps += m;
-                        }
-                        _ => arrays += 1,
-                    });
+        // Recursively calculate the approximate memory used by a value
+        fn calc_memory(value: &Dynamic, seen: &mut HashSet<usize>) -> usize {
+            use crate::stdlib::mem::{size_of, size_of_val};
 
-                    (arrays, maps, 0)
+            let size = size_of::<Dynamic>();
+
+            match value.0 {
+                Union::Str(ref s, _) if seen.insert(s.as_ptr() as usize) => {
+                    size + (size_of::<String>() + 2 * size_of::<usize>() + s.len())
+                        / s.strong_count()
+                }
+                #[cfg(not(feature = "no_index"))]
+                Union::Array(ref arr, _) => {
+                    size + size_of::<Array>()
+                        + arr.iter().map(|v| calc_memory(v, seen)).sum::<usize>()
                 }
                 #[cfg(not(feature = "no_object"))]
-                Dynamic(Union::Map(map, _)) => {
-                    let mut arrays = 0;
-                    let mut maps = 0;
+                Union::Map(ref map, _) => {
+                    size + size_of::<Map>()
+                        + map
+                            .iter()
+                            .map(|(k, v)| {
+                                let key = if seen.insert(k.as_ptr() as usize) {
+                                    (size_of::<String>() + 2 * size_of::<usize>() + k.len())
+                                        / k.strong_count()
+                                } else {
+                                    0
+                                };
+                                size_of::<ImmutableString>() + key + calc_memory(v, seen)
+                            })
+                            .sum::<usize>()
+                }
+                Union::FnPtr(ref f, _) => {
+                    size + size_of::<FnPtr>()
+                        + f.curry()
+                            .iter()
+                            .map(|v| calc_memory(v, seen))
+                            .sum::<usize>()
+                }
+                #[cfg(not(feature = "no_std"))]
+                Union::TimeStamp(ref t, _) => size + size_of_val(t.as_ref()),
+                Union::Variant(ref v, _) => size + size_of_val(v.as_ref().as_ref()),
+                #[cfg(not(feature = "no_closure"))]
+                Union::Shared(ref cell, _) => {
+                    if !seen.insert(crate::Shared::as_ptr(cell) as *const u8 as usize) {
+                        return size;
+                    }
 
-                    map.values().for_each(|value| match value {
-                        #[cfg(not(feature = "no_index"))]
-                        Dynamic(Union::Array(_, _)) => {
-                            let (a, m, _) = calc_size(value);
-                            arrays += a;
-                            maps += m;
-                        }
-                        Dynamic(Union::Map(_, _)) => {
-                            let (a, m, _) = calc_size(value);
-                            arrays += a;
-                            maps += m;
-                        }
-                        _ => maps += 1,
-                    });
+                    // The value may be locked during a method call, in which case it is skipped
+                    #[cfg(not(feature = "sync"))]
+                    let inner = cell.try_borrow().ok();
+                    #[cfg(feature = "sync")]
+                    let inner = cell.try_read().ok();
 
-                    (arrays, maps, 0)
+                    size + (size_of::<crate::Locked<Dynamic>>()
+                        + 2 * size_of::<usize>()
+                        + inner.map_or(0, |v| calc_memory(&v, seen)))
+                        / crate::Shared::strong_count(cell)
                 }
-                Dynamic(Union::Str(s, _)) => (0, 0, s.len()),
-                _ => (0, 0, 0),
+                _ => size,
             }
         }
 
-        match result {
-            // Simply return all errors
+        let value = match result {
+            Ok(ref value) => value,
             Err(_) => return result,
-            // String with limit
-            Ok(Dynamic(Union::Str(_, _))) if self.max_string_size() > 0 => (),
-            // Array with limit
-            #[cfg(not(feature = "no_index"))]
-            Ok(Dynamic(Union::Array(_, _))) if self.max_array_size() > 0 => (),
-            // Map with limit
-            #[cfg(not(feature = "no_object"))]
-            Ok(Dynamic(Union::Map(_, _))) if self.max_map_size() > 0 => (),
-            // Everything else is simply returned
-            Ok(_) => return result,
         };
 
-        let (_arr, _map, s) = calc_size(result.as_ref().unwrap());
-
-        if s > self.max_string_size() {
-            return EvalAltResult::ErrorDataTooLarge("Length of string".to_string(), pos).into();
+        // Forget the variables removed from the scope
+        if scope.len() < state.memory_vars.len() {
+            let removed: usize = state.memory_vars.drain(scope.len()..).flatten().sum();
+            state.memory -= removed;
         }
 
-        #[cfg(not(feature = "no_index"))]
-        if _arr > self.max_array_size() {
-            return EvalAltResult::ErrorDataTooLarge("Size of array".to_string(), pos).into();
+        // Estimate the memory used by the variables added or modified
+        state.memory_vars.resize(scope.len(), None);
+
+        for ((_, _, value), size) in scope.iter_raw().zip(state.memory_vars.iter_mut()) {
+            if size.is_none() {
+                let value_size = calc_memory(value, &mut HashSet::new());
+                state.memory += value_size;
+                *size = Some(value_size);
+            }
         }
 
-        #[cfg(not(feature = "no_object"))]
-        if _map > self.max_map_size() {
-            return EvalAltResult::ErrorDataTooLarge("Size of object map".to_string(), pos).into();
+        if state.memory_outer + state.memory + calc_memory(value, &mut HashSet::new())
+            > self.max_memory()
+        {
+            return EvalAltResult::ErrorDataTooLarge("Memory usage".to_string(), pos).into();
         }
 
         result
@@ -2635,6 +3252,27 @@ impl Engine {
             return EvalAltResult::ErrorTooManyOperations(pos).into();
         }
 
+        #[cfg(not(feature = "unchecked"))]
+        // Guard against running for too long
+        if let Some(max_duration) = state.max_duration.or(self.limits.max_duration) {
+            if let Some(clock) = &self.clock {
+                let now = clock();
+                let start = *state.start_time.get_or_insert(now);
+                let elapsed = now.checked_sub(start).unwrap_or_default();
+
+                if elapsed > max_duration {
+                    return EvalAltResult::ErrorTimeout(elapsed, pos).into();
+                }
+            }
+        }
+
+        // Terminate script if requested via the interrupt handle of the engine or the evaluation
+        for interrupt in once(&self.interrupt).chain(state.interrupt.as_ref()) {
+            if let Some(reason) = interrupt.reason() {
+                return EvalAltResult::ErrorTerminated(reason, pos).into();
+            }
+        }
+
         // Report progress - only in steps
         if let Some(progress) = &self.progress {
             if let Some(token) = progress(state.operations) {
@@ -2646,6 +3284,22 @@ impl Engine {
         Ok(())
     }
 
+    /// Check the limits after calling a native Rust function, which may have added a cost via
+    /// [`NativeCallContext::add_cost`] or called back into the [`Engine`].
+    #[inline(always)]
+    pub(crate) fn sync_operations(
+        &self,
+        state: &mut State,
+        operations: u64,
+        pos: Position,
+    ) -> Result<(), Box<EvalAltResult>> {
+        if state.operations > operations {
+            state.operations -= 1;
+            self.inc_operations(state, pos)?;
+        }
+        Ok(())
+    }
+
     /// Map a type_name into a pretty-print name
     #[inline(always)]
     pub(crate) fn map_type_name<'a>(&'a self, name: &'a str) -> &'a str {
@@ -2,20 +2,21 @@
 
 use crate::dynamic::Variant;
 use crate::engine::{EvalContext, Imports, State};
-use crate::fn_native::{FnCallArgs, SendSync};
+use crate::fn_native::{FnCallArgs, ResolvedFn, SendSync};
 use crate::optimize::OptimizationLevel;
 use crate::stdlib::{
     any::{type_name, TypeId},
     boxed::Box,
     format,
     hash::{Hash, Hasher},
+    ops::RangeInclusive,
     string::String,
     vec::Vec,
 };
 use crate::utils::get_hasher;
 use crate::{
-    scope::Scope, Dynamic, Engine, EvalAltResult, FnAccess, FnNamespace, Module, NativeCallContext,
-    ParseError, Position, Shared, AST,
+    scope::Scope, Capabilities, Dynamic, Engine, EvalAltResult, EvalOptions, FnAccess, FnNamespace,
+    Module, NativeCallContext, ParseError, Position, Shared, AST,
 };
 
 #[cfg(not(feature = "no_index"))]
@@ -67,6 +68,142 @@ impl Engine {
         );
         self
     }
+    /// Register a variadic function with the [`Engine`].
+    ///
+    /// The function accepts any number of arguments within `arity`, each of any type.
+    /// Arguments are passed in as a mutable slice of [`Dynamic`] values, which are owned by the function.
+    /// Use [`FromArgs`][crate::FromArgs] to convert them into a tuple of typed values.
+    ///
+    /// A function registered for the actual argument types, or a built-in operator, always takes
+    /// precedence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Dynamic, Engine, FromArgs};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_variadic_fn("total", 1..=12, |_, args| {
+    ///     Ok(args.iter().map(|v| v.as_int().unwrap_or(0)).sum::<i64>())
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<i64>("total(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)")?, 55);
+    ///
+    /// # #[cfg(not(feature = "no_index"))]
+    /// # {
+    /// engine.register_variadic_fn("swap", 2..=2, |_, args| {
+    ///     let (x, y) = <(i64, String)>::from_args(args).ok_or("expects an integer and a string")?;
+    ///     // Tuples convert into arrays
+    ///     let swapped: Dynamic = (y, x).into();
+    ///     Ok(swapped)
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<String>(r#"swap(42, "x")[0]"#)?, "x");
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn register_variadic_fn<T: Variant + Clone>(
+        &mut self,
+        name: &str,
+        arity: RangeInclusive<usize>,
+        func: impl Fn(NativeCallContext, &mut [Dynamic]) -> Result<T, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> &mut Self {
+        self.global_namespace.set_variadic_fn(
+            name,
+            FnNamespace::Global,
+            FnAccess::Public,
+            arity,
+            func,
+        );
+        self
+    }
+    /// Set the cost of all native Rust functions with a particular name registered on the
+    /// [`Engine`], i.e. the number of operations added to the count for each call.
+    ///
+    /// Expensive functions can thus count towards the
+    /// [maximum number of operations][Engine::set_max_operations] in proportion to the work they do.
+    /// Functions in [packages][crate::packages::Package] are not affected.
+    ///
+    /// A cost which depends on the arguments can be added during a call via
+    /// [`NativeCallContext::add_cost`].
+    ///
+    /// Setting the cost to zero removes it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "unchecked"))]
+    /// # {
+    /// use rhai::{Engine, EvalAltResult, RegisterFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_fn("query", || 42_i64);
+    /// engine.set_fn_cost("query", 1000);
+    /// engine.set_max_operations(5000);
+    ///
+    /// assert_eq!(engine.eval::<i64>("query() + query()").unwrap(), 84);
+    ///
+    /// assert!(matches!(
+    ///     *engine.consume("for x in range(0, 10) { query(); }").expect_err("should error"),
+    ///     EvalAltResult::ErrorTooManyOperations(_)
+    /// ));
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn set_fn_cost(&mut self, name: &str, cost: u64) -> &mut Self {
+        self.global_namespace.update_fn_cost_by_name(name, cost);
+        self
+    }
+    /// Set the names of the [capabilities][Capabilities] required to call all native Rust
+    /// functions with a particular name registered on the [`Engine`], replacing any set previously.
+    ///
+    /// When such a function is called in an evaluation restricted to a set of capabilities via
+    /// [`EvalOptions::with_capabilities`], it fails with [`ErrorPermissionDenied`][EvalAltResult::ErrorPermissionDenied] unless all of
+    /// them are granted.
+    /// Functions in [packages][crate::packages::Package] are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Capabilities, Engine, EvalAltResult, EvalOptions, RegisterFn, Scope};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_fn("delete_file", |path: &str| path.len() as i64);
+    /// engine.set_fn_capabilities("delete_file", &["fs.write"]);
+    ///
+    /// let ast = engine.compile(r#"delete_file("/tmp/data")"#)?;
+    ///
+    /// // Unrestricted evaluations can call all functions
+    /// assert_eq!(engine.eval_ast::<i64>(&ast)?, 9);
+    ///
+    /// let options = EvalOptions::new().with_capabilities(Capabilities::new().grant("fs.read"));
+    /// let result = engine.eval_ast_with_scope_and_options::<i64>(&mut Scope::new(), &ast, options);
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorPermissionDenied(f, c, _) if f == "delete_file" && c == "fs.write"
+    /// ));
+    ///
+    /// let options = EvalOptions::new().with_capabilities(Capabilities::new().grant("fs"));
+    /// assert_eq!(engine.eval_ast_with_scope_and_options::<i64>(&mut Scope::new(), &ast, options)?, 9);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn set_fn_capabilities(&mut self, name: &str, capabilities: &[&str]) -> &mut Self {
+        self.global_namespace
+            .update_fn_capabilities_by_name(name, capabilities);
+        self
+    }
     /// Register a custom type for use with the [`Engine`].
     /// The type must implement [`Clone`].
     ///
@@ -152,6 +289,44 @@ impl Engine {
     pub fn register_type_with_name<T: Variant + Clone>(&mut self, name: &str) -> &mut Self {
         // Add the pretty-print type name into the map
         self.type_names.insert(type_name::<T>().into(), name.into());
+        #[cfg(feature = "metadata")]
+        self.type_ids.insert(TypeId::of::<T>(), name.into());
+        self
+    }
+    /// Register a custom type, together with its API, for use with the [`Engine`].
+    /// The type must implement [`CustomType`][crate::plugin::CustomType],
+    /// usually via `#[derive(CustomType)]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::plugin::*;
+    ///
+    /// #[derive(Debug, Clone, PartialEq, CustomType)]
+    /// #[rhai(name = "Point", constructor, debug, eq)]
+    /// pub struct Point {
+    ///     pub x: i64,
+    ///     #[rhai(readonly)]
+    ///     pub y: i64,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.build_type::<Point>();
+    ///
+    /// assert_eq!(
+    ///     engine.eval::<Point>("let p = Point(1, 2); p.x = p.x + p.y; p")?,
+    ///     Point { x: 3, y: 2 }
+    /// );
+    /// assert_eq!(engine.eval::<String>("type_of(Point(0, 0))")?, "Point");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_object"))]
+    #[inline(always)]
+    pub fn build_type<T: crate::plugin::CustomType>(&mut self) -> &mut Self {
+        T::build(self);
         self
     }
     /// Register an type iterator for an iterable type with the [`Engine`].
@@ -1451,8 +1626,9 @@ impl Engine {
         ast: &AST,
     ) -> Result<T, Box<EvalAltResult>> {
         let mods = &mut (&self.global_sub_modules).into();
+        let state = &mut Default::default();
 
-        let result = self.eval_ast_with_scope_raw(scope, mods, ast, 0)?;
+        let result = self.eval_ast_with_scope_raw(scope, mods, state, ast, 0)?;
 
         let typ = self.map_type_name(result.type_name());
 
@@ -1465,19 +1641,120 @@ impl Engine {
             .into()
         });
     }
+    /// Evaluate an [`AST`] with own scope, using [options][EvalOptions] overriding the settings of
+    /// this [`Engine`], e.g. a timeout, the [capabilities][Capabilities] granted, an
+    /// [`InterruptHandle`][crate::InterruptHandle], or a report to fill with the resources used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_std"))]
+    /// # #[cfg(not(feature = "unchecked"))]
+    /// # {
+    /// use std::time::Duration;
+    /// use rhai::{Engine, EvalAltResult, EvalOptions, EvalStats, Scope};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast = engine.compile("x += 2; while x > 0 {} x")?;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push("x", -2_i64);
+    ///
+    /// let timeout = Duration::from_millis(10);
+    /// let mut stats = EvalStats::default();
+    ///
+    /// let options = EvalOptions::new().with_timeout(timeout).with_stats(&mut stats);
+    /// assert_eq!(engine.eval_ast_with_scope_and_options::<i64>(&mut scope, &ast, options)?, 0);
+    /// assert!(stats.operations > 0);
+    ///
+    /// let options = EvalOptions::new().with_timeout(timeout);
+    /// let result = engine.eval_ast_with_scope_and_options::<i64>(&mut scope, &ast, options);
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorTimeout(_, _)
+    /// ));
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn eval_ast_with_scope_and_options<T: Variant + Clone>(
+        &self,
+        scope: &mut Scope,
+        ast: &AST,
+        options: EvalOptions,
+    ) -> Result<T, Box<EvalAltResult>> {
+        self.eval_with_options(options, |state| {
+            let mods = &mut (&self.global_sub_modules).into();
+
+            let result = self.eval_ast_with_scope_raw(scope, mods, state, ast, 0)?;
+
+            let typ = self.map_type_name(result.type_name());
+
+            result.try_cast::<T>().ok_or_else(|| {
+                EvalAltResult::ErrorMismatchOutputType(
+                    self.map_type_name(type_name::<T>()).into(),
+                    typ.into(),
+                    Position::NONE,
+                )
+                .into()
+            })
+        })
+    }
+    /// Run an evaluation with a new [`State`] set up from [options][EvalOptions], filling in the
+    /// report on the resources used, if requested.
+    fn eval_with_options<T>(
+        &self,
+        options: EvalOptions,
+        eval: impl FnOnce(&mut State) -> Result<T, Box<EvalAltResult>>,
+    ) -> Result<T, Box<EvalAltResult>> {
+        let (mut state, stats_out) = options.into_state();
+
+        let stats_out = match stats_out {
+            Some(stats_out) => stats_out,
+            None => return eval(&mut state),
+        };
+
+        #[cfg(not(feature = "unchecked"))]
+        let start = self.clock.as_ref().map(|clock| clock());
+        let result = eval(&mut state);
+
+        let mut stats = state.stats.take().map(|s| *s).unwrap_or_default();
+        stats.operations = state.operations;
+        #[cfg(not(feature = "no_module"))]
+        {
+            stats.modules = state.modules;
+        }
+        #[cfg(not(feature = "unchecked"))]
+        {
+            stats.elapsed = start.and_then(|start| {
+                self.clock
+                    .as_ref()
+                    .map(|clock| clock().checked_sub(start).unwrap_or_default())
+            });
+        }
+        *stats_out = stats;
+
+        result
+    }
     /// Evaluate an [`AST`] with own scope.
     #[inline(always)]
     pub(crate) fn eval_ast_with_scope_raw<'a>(
         &self,
         scope: &mut Scope,
         mods: &mut Imports,
+        state: &mut State,
         ast: &'a AST,
         level: usize,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
-        let state = &mut State {
-            source: ast.clone_source(),
-            ..Default::default()
-        };
+        state.source = ast.clone_source();
+
+        if self.coverage.is_some() {
+            self.coverage_register(&state.source, ast.statements(), &[ast.lib()]);
+        }
         self.eval_statements_raw(scope, mods, state, ast.statements(), &[ast.lib()], level)
     }
     /// Evaluate a file, but throw away the result and only return error (if any).
@@ -1546,6 +1823,9 @@ impl Engine {
             source: ast.clone_source(),
             ..Default::default()
         };
+        if self.coverage.is_some() {
+            self.coverage_register(&state.source, ast.statements(), &[ast.lib()]);
+        }
         self.eval_statements_raw(scope, mods, state, ast.statements(), &[ast.lib()], 0)?;
         Ok(())
     }
@@ -1597,8 +1877,14 @@ impl Engine {
         let mut arg_values = args.into_vec();
         let mut args: crate::StaticVec<_> = arg_values.as_mut().iter_mut().collect();
 
-        let result =
-            self.call_fn_dynamic_raw(scope, &[ast.lib()], name, &mut None, args.as_mut())?;
+        let result = self.call_fn_dynamic_raw(
+            scope,
+            &mut Default::default(),
+            &[ast.lib()],
+            name,
+            &mut None,
+            args.as_mut(),
+        )?;
 
         let typ = self.map_type_name(result.type_name());
 
@@ -1611,6 +1897,75 @@ impl Engine {
             .into()
         });
     }
+    /// Call a script function defined in an [`AST`] with multiple arguments, using
+    /// [options][EvalOptions] overriding the settings of this [`Engine`], e.g. a timeout, the
+    /// [capabilities][Capabilities] granted, an [`InterruptHandle`][crate::InterruptHandle], or a
+    /// report to fill with the resources used.
+    /// Arguments are passed as a tuple.
+    ///
+    /// Not available under `no_function`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::{Capabilities, Engine, EvalOptions, EvalStats, Scope};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast = engine.compile("fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }")?;
+    ///
+    /// let mut stats = EvalStats::default();
+    ///
+    /// let options = EvalOptions::new()
+    ///     .with_capabilities(Capabilities::new())
+    ///     .with_stats(&mut stats);
+    ///
+    /// let result: i64 = engine.call_fn_with_options(&mut Scope::new(), &ast, "fact", (5_i64,), options)?;
+    ///
+    /// assert_eq!(result, 120);
+    /// assert_eq!(stats.max_call_depth, 4);
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    #[inline]
+    pub fn call_fn_with_options<T: Variant + Clone>(
+        &self,
+        scope: &mut Scope,
+        ast: &AST,
+        name: &str,
+        args: impl crate::fn_args::FuncArgs,
+        options: EvalOptions,
+    ) -> Result<T, Box<EvalAltResult>> {
+        let mut arg_values = args.into_vec();
+        let mut args: crate::StaticVec<_> = arg_values.as_mut().iter_mut().collect();
+
+        self.eval_with_options(options, |state| {
+            let result = self.call_fn_dynamic_raw(
+                scope,
+                state,
+                &[ast.lib()],
+                name,
+                &mut None,
+                args.as_mut(),
+            )?;
+
+            let typ = self.map_type_name(result.type_name());
+
+            result.try_cast().ok_or_else(|| {
+                EvalAltResult::ErrorMismatchOutputType(
+                    self.map_type_name(type_name::<T>()).into(),
+                    typ.into(),
+                    Position::NONE,
+                )
+                .into()
+            })
+        })
+    }
     /// Call a script function defined in an [`AST`] with multiple [`Dynamic`] arguments
     /// and optionally a value for binding to the `this` pointer.
     ///
@@ -1672,7 +2027,14 @@ impl Engine {
     ) -> Result<Dynamic, Box<EvalAltResult>> {
         let mut args: crate::StaticVec<_> = arg_values.as_mut().iter_mut().collect();
 
-        self.call_fn_dynamic_raw(scope, &[lib.as_ref()], name, &mut this_ptr, args.as_mut())
+        self.call_fn_dynamic_raw(
+            scope,
+            &mut Default::default(),
+            &[lib.as_ref()],
+            name,
+            &mut this_ptr,
+            args.as_mut(),
+        )
     }
     /// Call a script function defined in an [`AST`] with multiple [`Dynamic`] arguments.
     ///
@@ -1687,6 +2049,7 @@ impl Engine {
     pub(crate) fn call_fn_dynamic_raw(
         &self,
         scope: &mut Scope,
+        state: &mut State,
         lib: &[&crate::Module],
         name: &str,
         this_ptr: &mut Option<&mut Dynamic>,
@@ -1697,9 +2060,12 @@ impl Engine {
             .find_map(|&m| m.get_script_fn(name, args.len(), true))
             .ok_or_else(|| EvalAltResult::ErrorFunctionNotFound(name.into(), Position::NONE))?;
 
-        let mut state = Default::default();
         let mut mods = (&self.global_sub_modules).into();
 
+        if self.coverage.is_some() {
+            self.coverage_register(&None, &[], lib);
+        }
+
         // Check for data race.
         if cfg!(not(feature = "no_closure")) {
             crate::fn_call::ensure_no_data_race(name, args, false)?;
@@ -1708,7 +2074,7 @@ impl Engine {
         self.call_script_fn(
             scope,
             &mut mods,
-            &mut state,
+            state,
             lib,
             this_ptr,
             fn_def,
@@ -1777,6 +2143,39 @@ impl Engine {
 
         signatures
     }
+    /// Generate a list of all registered functions that can be called with a particular set of
+    /// [capabilities][Capabilities] granted.
+    ///
+    /// Functions are included in the same order as [`gen_fn_signatures`][Engine::gen_fn_signatures].
+    pub fn gen_fn_signatures_with_capabilities(
+        &self,
+        include_packages: bool,
+        granted: &Capabilities,
+    ) -> Vec<String> {
+        let mut signatures: Vec<_> = Default::default();
+
+        signatures.extend(
+            self.global_namespace
+                .gen_fn_signatures_with_capabilities(granted),
+        );
+
+        self.global_sub_modules.iter().for_each(|(name, m)| {
+            signatures.extend(
+                m.gen_fn_signatures_with_capabilities(granted)
+                    .map(|f| format!("{}::{}", name, f)),
+            )
+        });
+
+        if include_packages {
+            signatures.extend(
+                self.global_modules
+                    .iter()
+                    .flat_map(|m| m.gen_fn_signatures_with_capabilities(granted)),
+            );
+        }
+
+        signatures
+    }
     /// Provide a callback that will be invoked before each variable access.
     ///
     /// # Return Value of Callback
@@ -1819,6 +2218,117 @@ impl Engine {
         self.resolve_var = Some(Box::new(callback));
         self
     }
+    /// Provide a callback that will be invoked before each variable definition via `let`,
+    /// `const` or a `for` loop.
+    ///
+    /// The callback receives the name of the variable, whether it is a constant, the current
+    /// scope level (0 = global) and the current [`EvalContext`].
+    ///
+    /// # Return Value of Callback
+    ///
+    /// Return `Ok(true)` to continue with the definition.  
+    /// Return `Ok(false)` to fail with
+    /// [`ErrorForbiddenVariable`][EvalAltResult::ErrorForbiddenVariable].
+    ///
+    /// # Errors in Callback
+    ///
+    /// Return `Err(...)` if there is an error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, EvalAltResult};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Do not allow variables named with a leading underscore.
+    /// engine.on_def_var(|name, _, _, _| Ok(!name.starts_with('_')));
+    ///
+    /// assert_eq!(engine.eval::<i64>("let x = 42; x")?, 42);
+    ///
+    /// assert!(matches!(
+    ///     *engine.eval::<i64>("let _x = 42; _x").expect_err("should error"),
+    ///     EvalAltResult::ErrorForbiddenVariable(name, _) if name == "_x"
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn on_def_var(
+        &mut self,
+        callback: impl Fn(&str, bool, usize, &EvalContext) -> Result<bool, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> &mut Self {
+        self.def_var = Some(Box::new(callback));
+        self
+    }
+    /// Provide a callback that will be invoked when a function to call is not found, in order to
+    /// resolve it lazily, e.g. by loading it from a database or by generating a proxy to a remote
+    /// service.
+    ///
+    /// The callback receives the name of the function, its namespace (e.g. `foo::bar`, if
+    /// qualified), the types of the arguments and a [`NativeCallContext`].
+    ///
+    /// It is not invoked for property getters/setters and indexers.
+    ///
+    /// # Return Value of Callback
+    ///
+    /// Return `Ok(None)` to continue with the function not found.  
+    /// Return `Ok(Some(ResolvedFn::Function(...)))` with a native Rust function to call.
+    /// It is cached so that the callback is not invoked again for the same function and argument
+    /// types during the same evaluation.
+    /// Returning a script-defined function or an iterator is an error.  
+    /// Return `Ok(Some(ResolvedFn::Value(...)))` with the result of the call.
+    ///
+    /// # Errors in Callback
+    ///
+    /// Return `Err(...)` if there is an error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use std::any::TypeId;
+    /// use rhai::{CallableFunction, Engine, ResolvedFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.on_missing_fn(|name, namespace, arg_types, _| {
+    ///     match (name, namespace, arg_types) {
+    ///         ("double", None, [t]) if *t == TypeId::of::<i64>() => {
+    ///             let func = CallableFunction::from_pure(Box::new(|_, args| {
+    ///                 Ok((args[0].as_int().unwrap() * 2).into())
+    ///             }));
+    ///             Ok(Some(ResolvedFn::Function(func)))
+    ///         }
+    ///         ("answer", None, []) => Ok(Some(ResolvedFn::Value(42_i64.into()))),
+    ///         _ => Ok(None),
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<i64>("double(21)")?, 42);
+    /// assert_eq!(engine.eval::<i64>("answer()")?, 42);
+    /// assert!(engine.eval::<i64>("triple(21)").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn on_missing_fn(
+        &mut self,
+        callback: impl Fn(
+                &str,
+                Option<&str>,
+                &[TypeId],
+                NativeCallContext,
+            ) -> Result<Option<ResolvedFn>, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> &mut Self {
+        self.missing_fn = Some(Box::new(callback));
+        self
+    }
     /// Register a callback for script evaluation progress.
     ///
     /// # Example
@@ -1861,6 +2371,45 @@ impl Engine {
         self.progress = Some(Box::new(callback));
         self
     }
+    /// Get a handle to request termination of _all_ evaluations running on this [`Engine`].
+    ///
+    /// All handles obtained from the same [`Engine`] control the same evaluations.
+    /// To stop a single evaluation instead, pass a new [`InterruptHandle`][crate::InterruptHandle]
+    /// to [`EvalOptions::with_interrupt`].
+    /// See [`InterruptHandle`][crate::InterruptHandle] for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, EvalAltResult, RegisterFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// let handle = engine.interrupt_handle();
+    ///
+    /// // Native functions can also request termination
+    /// engine.register_fn("cancel", move || handle.interrupt(42_i64));
+    ///
+    /// let result = engine.consume("let x = 0; loop { x += 1; if x == 100 { cancel(); } }");
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorTerminated(reason, _) if reason.as_int().unwrap() == 42
+    /// ));
+    ///
+    /// // The request remains in effect until reset
+    /// assert!(engine.consume("let x = 42;").is_err());
+    ///
+    /// engine.interrupt_handle().reset();
+    /// engine.consume("let x = 42;")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn interrupt_handle(&self) -> crate::InterruptHandle {
+        self.interrupt.clone()
+    }
     /// Override default action of `print` (print to stdout using [`println!`])
     ///
     /// # Example
@@ -1926,4 +2475,73 @@ impl Engine {
         self.debug = Box::new(callback);
         self
     }
+    /// Register a debugger.
+    ///
+    /// The `init` closure is called at the beginning of each evaluation to set up the
+    /// [`Debugger`][crate::Debugger], e.g. to add break-points.
+    ///
+    /// The `callback` closure is called whenever execution stops at a statement, either
+    /// because of a step command, a break-point, or an error (if enabled via
+    /// [`Debugger::set_break_on_error`][crate::Debugger::set_break_on_error]).
+    /// Via the [`EvalContext`][crate::EvalContext], it has access to the call stack, the current
+    /// [`Scope`], `this` and imported modules, and it can evaluate expressions.
+    /// It returns a [`DebuggerCommand`][crate::DebuggerCommand] to resume execution,
+    /// or an error to terminate it.
+    ///
+    /// Evaluation is slower when a debugger is registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # use std::sync::RwLock;
+    /// # use std::sync::Arc;
+    /// use rhai::{BreakPoint, DebuggerCommand, Engine};
+    ///
+    /// let result = Arc::new(RwLock::new(Vec::new()));
+    /// let logger = result.clone();
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_debugger(
+    ///     |debugger| {
+    ///         debugger.add_break_point(BreakPoint::AtPosition {
+    ///             source: None,
+    ///             line: 3,
+    ///             enabled: true,
+    ///         });
+    ///     },
+    ///     move |context, _event, _pos| {
+    ///         let x = context.eval_expression("x * 10")?;
+    ///         logger.write().unwrap().push(x.as_int().unwrap());
+    ///         Ok(DebuggerCommand::Continue)
+    ///     },
+    /// );
+    ///
+    /// engine.consume(r"
+    ///     for x in range(0, 3) {
+    ///         let y = x;
+    ///         y += 1;
+    ///     }
+    /// ")?;
+    ///
+    /// assert_eq!(*result.read().unwrap(), vec![0, 10, 20]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn register_debugger(
+        &mut self,
+        init: impl Fn(&mut crate::Debugger) + SendSync + 'static,
+        callback: impl Fn(
+                &mut EvalContext,
+                crate::DebuggerEvent,
+                Position,
+            ) -> Result<crate::DebuggerCommand, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> &mut Self {
+        self.debugger = Some((Box::new(init), Box::new(callback)));
+        self
+    }
 }
@@ -4,9 +4,15 @@ use crate::stdlib::{format, num::NonZeroU8, string::String};
 use crate::token::Token;
 use crate::Engine;
 
-#[cfg(not(feature = "no_module"))]
+#[cfg(any(not(feature = "no_module"), not(feature = "unchecked")))]
 use crate::stdlib::boxed::Box;
 
+#[cfg(not(feature = "unchecked"))]
+use crate::fn_native::SendSync;
+
+#[cfg(not(feature = "unchecked"))]
+use crate::stdlib::time::Duration;
+
 impl Engine {
     /// Control whether and how the [`Engine`] will optimize an [`AST`][crate::AST] after compilation.
     ///
@@ -35,6 +41,384 @@ impl Engine {
         self.disable_doc_comments = !enable;
         self
     }
+    /// Allow/disallow `if` statements to be used as expressions.
+    ///
+    /// When disallowed, using `if` as an expression is a parse error.
+    /// `if` statements are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_allow_if_expression(false);
+    ///
+    /// assert!(engine.compile("if true { 42 }").is_ok());
+    /// assert!(engine.compile("let x = if true { 42 } else { 0 };").is_err());
+    /// ```
+    #[inline(always)]
+    pub fn set_allow_if_expression(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_if_expr = enable;
+        self
+    }
+    /// Are `if` statements allowed to be used as expressions?
+    #[inline(always)]
+    pub fn allow_if_expression(&self) -> bool {
+        self.options.allow_if_expr
+    }
+    /// Allow/disallow `switch` statements to be used as expressions.
+    ///
+    /// When disallowed, using `switch` as an expression is a parse error.
+    /// `switch` statements are not affected.
+    #[inline(always)]
+    pub fn set_allow_switch_expression(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_switch_expr = enable;
+        self
+    }
+    /// Are `switch` statements allowed to be used as expressions?
+    #[inline(always)]
+    pub fn allow_switch_expression(&self) -> bool {
+        self.options.allow_switch_expr
+    }
+    /// Allow/disallow `while`, `loop`, `do` and `for` loops.
+    #[inline(always)]
+    pub fn set_allow_looping(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_looping = enable;
+        self
+    }
+    /// Are loops allowed?
+    #[inline(always)]
+    pub fn allow_looping(&self) -> bool {
+        self.options.allow_looping
+    }
+    /// Allow/disallow script-defined functions.
+    ///
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn set_allow_functions(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_functions = enable;
+        self
+    }
+    /// Are script-defined functions allowed?
+    ///
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn allow_functions(&self) -> bool {
+        self.options.allow_functions
+    }
+    /// Allow/disallow anonymous functions and closures.
+    ///
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn set_allow_anonymous_fn(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_anonymous_fn = enable;
+        self
+    }
+    /// Are anonymous functions and closures allowed?
+    ///
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn allow_anonymous_fn(&self) -> bool {
+        self.options.allow_anonymous_fn
+    }
+    /// Allow/disallow defining a variable with the same name as another variable already
+    /// defined in the script.
+    ///
+    /// Variables in a [`Scope`][crate::Scope] passed in for evaluation are not checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_allow_shadowing(false);
+    ///
+    /// assert!(engine.compile("let x = 42; let y = x + 1;").is_ok());
+    /// assert!(engine.compile("let x = 42; let x = x + 1;").is_err());
+    /// ```
+    #[inline(always)]
+    pub fn set_allow_shadowing(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_shadowing = enable;
+        self
+    }
+    /// Is variable shadowing allowed?
+    #[inline(always)]
+    pub fn allow_shadowing(&self) -> bool {
+        self.options.allow_shadowing
+    }
+    /// Allow/disallow calls to `eval`.
+    #[inline(always)]
+    pub fn set_allow_eval(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_eval = enable;
+        self
+    }
+    /// Are calls to `eval` allowed?
+    #[inline(always)]
+    pub fn allow_eval(&self) -> bool {
+        self.options.allow_eval
+    }
+    /// Allow/disallow `import` statements.
+    ///
+    /// Not available under `no_module`.
+    #[cfg(not(feature = "no_module"))]
+    #[inline(always)]
+    pub fn set_allow_import(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_import = enable;
+        self
+    }
+    /// Are `import` statements allowed?
+    ///
+    /// Not available under `no_module`.
+    #[cfg(not(feature = "no_module"))]
+    #[inline(always)]
+    pub fn allow_import(&self) -> bool {
+        self.options.allow_import
+    }
+    /// Allow/disallow the `this` pointer in functions, which implicitly binds to the object
+    /// that a function is called on in method-call style.
+    ///
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn set_allow_implicit_this(&mut self, enable: bool) -> &mut Self {
+        self.options.allow_implicit_this = enable;
+        self
+    }
+    /// Is the `this` pointer allowed in functions?
+    ///
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn allow_implicit_this(&self) -> bool {
+        self.options.allow_implicit_this
+    }
+    /// Enable/disable strict variables mode.
+    ///
+    /// Under strict variables mode, compiling a script fails with
+    /// [`ParseErrorType::VariableUndefined`][crate::ParseErrorType::VariableUndefined] when it
+    /// refers to a variable that is not defined earlier, is not a function parameter or a variable
+    /// captured by a closure, is not in the [`Scope`][crate::Scope] that the script is compiled
+    /// with, and is not registered via
+    /// [`register_external_var`][Engine::register_external_var].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::{Engine, Scope};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_strict_variables(true);
+    ///
+    /// assert!(engine.compile("let x = 40; x + 2").is_ok());
+    /// assert!(engine.compile("let x = 40; y + 2").is_err());
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push("y", 42_i64);
+    ///
+    /// assert!(engine.compile_with_scope(&scope, "let x = 40; y + 2").is_ok());
+    /// ```
+    #[inline(always)]
+    pub fn set_strict_variables(&mut self, enable: bool) -> &mut Self {
+        self.options.strict_var = enable;
+        self
+    }
+    /// Is strict variables mode enabled?
+    #[inline(always)]
+    pub fn strict_variables(&self) -> bool {
+        self.options.strict_var
+    }
+    /// Register the name of a variable that is resolved externally, e.g. via
+    /// [`on_var`][Engine::on_var], so that scripts referring to it can be compiled under strict
+    /// variables mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.on_var(|name, _, _| match name {
+    ///     "MYSTIC_NUMBER" => Ok(Some(42_i64.into())),
+    ///     _ => Ok(None),
+    /// });
+    ///
+    /// engine.set_strict_variables(true);
+    /// assert!(engine.compile("MYSTIC_NUMBER").is_err());
+    ///
+    /// engine.register_external_var("MYSTIC_NUMBER");
+    /// assert_eq!(engine.eval::<i64>("MYSTIC_NUMBER")?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn register_external_var(&mut self, name: &str) -> &mut Self {
+        self.external_vars.insert(name.into());
+        self
+    }
+    /// Enable/disable the execution profiler.
+    ///
+    /// When enabled, the number of calls, the inclusive and exclusive time, and the number of
+    /// operations of each script-defined function and native Rust function called are recorded
+    /// across evaluations. Use [`profile_report`][Engine::profile_report] to get the results.
+    ///
+    /// Disabling the profiler discards all results.
+    ///
+    /// Not available under `no_std`.
+    #[cfg(not(feature = "no_std"))]
+    #[inline(always)]
+    pub fn set_profiling(&mut self, enable: bool) -> &mut Self {
+        self.profiler = if enable {
+            self.profiler
+                .take()
+                .or_else(|| Some(crate::profiler::new_report()))
+        } else {
+            None
+        };
+        self
+    }
+    /// Is the execution profiler enabled?
+    ///
+    /// Not available under `no_std`.
+    #[cfg(not(feature = "no_std"))]
+    #[inline(always)]
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+    /// Get a copy of the results of the execution profiler, or [`None`] if it is not enabled.
+    ///
+    /// Not available under `no_std`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # #[cfg(not(feature = "no_index"))]
+    /// # {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.set_profiling(true);
+    ///
+    /// engine.consume("fn foo(x) { x.len() } for i in range(0, 10) { foo([i]); }")?;
+    ///
+    /// let report = engine.profile_report().unwrap();
+    ///
+    /// assert_eq!(report.find("foo").next().unwrap().calls, 10);
+    /// assert!(report.folded_stacks().contains("<main>;foo;len "));
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_std"))]
+    pub fn profile_report(&self) -> Option<crate::ProfileReport> {
+        self.profiler.as_ref().map(|report| {
+            #[cfg(not(feature = "sync"))]
+            return report.borrow().clone();
+            #[cfg(feature = "sync")]
+            return report.read().unwrap().clone();
+        })
+    }
+    /// Discard the results of the execution profiler, if it is enabled.
+    ///
+    /// Not available under `no_std`.
+    #[cfg(not(feature = "no_std"))]
+    pub fn clear_profile_report(&self) {
+        if let Some(ref report) = self.profiler {
+            #[cfg(not(feature = "sync"))]
+            let mut report = report.borrow_mut();
+            #[cfg(feature = "sync")]
+            let mut report = report.write().unwrap();
+
+            *report = Default::default();
+        }
+    }
+    /// Enable/disable statement and branch coverage recording.
+    ///
+    /// When enabled, the number of times each statement is executed, and each branch of `if`,
+    /// `switch`, `&&` and `||` is taken, is recorded across evaluations, keyed by source and
+    /// [position][crate::Position]. Use [`coverage_report`][Engine::coverage_report] to get the
+    /// results.
+    ///
+    /// All statements and branches of an [`AST`][crate::AST] are registered when it is evaluated,
+    /// so those never executed are also reported.
+    ///
+    /// While coverage is enabled, scripts are compiled _without_ optimization so that results are
+    /// reported against the code as written. [`AST`][crate::AST]'s compiled before enabling
+    /// coverage may have had code eliminated by the optimizer.
+    ///
+    /// Disabling coverage discards all results.
+    #[inline(always)]
+    pub fn set_coverage(&mut self, enable: bool) -> &mut Self {
+        self.coverage = if enable {
+            self.coverage
+                .take()
+                .or_else(|| Some(crate::coverage::new_report()))
+        } else {
+            None
+        };
+        self
+    }
+    /// Is statement and branch coverage recording enabled?
+    #[inline(always)]
+    pub fn is_coverage(&self) -> bool {
+        self.coverage.is_some()
+    }
+    /// Get a copy of the statement and branch coverage results, or [`None`] if coverage is not
+    /// enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.set_coverage(true);
+    ///
+    /// engine.consume("let x = 42;\nif x > 0 { print(x); } else { print(-x); }")?;
+    ///
+    /// let report = engine.coverage_report().unwrap();
+    /// let summary = report.summary();
+    ///
+    /// assert_eq!(summary.statements, 4);
+    /// assert_eq!(summary.statements_hit, 3);
+    /// assert_eq!(summary.branches, 2);
+    /// assert_eq!(summary.branches_hit, 1);
+    ///
+    /// assert!(report.to_lcov().contains("BRDA:2,0,1,0\n"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn coverage_report(&self) -> Option<crate::CoverageReport> {
+        self.coverage.as_ref().map(|report| {
+            #[cfg(not(feature = "sync"))]
+            return report.borrow().clone();
+            #[cfg(feature = "sync")]
+            return report.read().unwrap().clone();
+        })
+    }
+    /// Discard the statement and branch coverage results, if coverage is enabled.
+    pub fn clear_coverage_report(&self) {
+        if let Some(ref report) = self.coverage {
+            #[cfg(not(feature = "sync"))]
+            let mut report = report.borrow_mut();
+            #[cfg(feature = "sync")]
+            let mut report = report.write().unwrap();
+
+            *report = Default::default();
+        }
+    }
     /// Set the maximum levels of function calls allowed for a script in order to avoid
     /// infinite recursion and stack overflows.
     ///
@@ -193,6 +577,153 @@ impl Engine {
     pub fn max_map_size(&self) -> usize {
         self.limits.max_map_size
     }
+    /// Set the maximum approximate memory (in bytes) used by all values (0 for unlimited).
+    ///
+    /// After each statement, the memory used by all values held in the [`Scope`][crate::Scope]
+    /// of the current function and of all the calling functions (including values captured in
+    /// closures and shared values), plus the result of the statement, is estimated.
+    /// If it exceeds the limit, evaluation fails with
+    /// [`ErrorDataTooLarge`][crate::EvalAltResult::ErrorDataTooLarge].
+    ///
+    /// The memory used is tracked incrementally: after each statement, only the variables added or
+    /// modified by it are estimated again, taking time proportional to the amount of data they
+    /// hold. Values modified in ways not visible to the script (e.g. shared values modified via
+    /// another variable) may therefore be estimated late.
+    ///
+    /// Not available under `unchecked`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "no_index"))]
+    /// # {
+    /// use rhai::{Engine, EvalAltResult};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.set_max_memory(100_000);
+    ///
+    /// let result = engine.consume("let x = []; loop { x.push(\"hello\" + x.len()); }");
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorDataTooLarge(_, _)
+    /// ));
+    /// # }
+    /// ```
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn set_max_memory(&mut self, max_memory: usize) -> &mut Self {
+        self.limits.max_memory = if max_memory == usize::MAX {
+            0
+        } else {
+            max_memory
+        };
+        self
+    }
+    /// The maximum approximate memory (in bytes) used by all values (0 for unlimited).
+    ///
+    /// Not available under `unchecked`.
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn max_memory(&self) -> usize {
+        self.limits.max_memory
+    }
+    /// Set the maximum wall-clock duration of an evaluation (zero for unlimited).
+    ///
+    /// The time elapsed is checked, using the clock set via [`set_clock`][Engine::set_clock],
+    /// whenever an operation is counted. If it exceeds the limit, evaluation fails with
+    /// [`ErrorTimeout`][crate::EvalAltResult::ErrorTimeout].
+    ///
+    /// A long-running native Rust function cannot be interrupted, so the time it takes is only
+    /// detected after it returns.
+    ///
+    /// Use [`EvalOptions::with_timeout`][crate::EvalOptions::with_timeout] to override this limit
+    /// for a single evaluation.
+    ///
+    /// Not available under `unchecked`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "no_std"))]
+    /// # {
+    /// use std::time::Duration;
+    /// use rhai::{Engine, EvalAltResult};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.set_max_duration(Duration::from_millis(10));
+    ///
+    /// let result = engine.consume("let x = 0; loop { x += 1; }");
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorTimeout(elapsed, _) if elapsed > Duration::from_millis(10)
+    /// ));
+    /// # }
+    /// ```
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn set_max_duration(&mut self, max_duration: Duration) -> &mut Self {
+        self.limits.max_duration = if max_duration == Duration::from_secs(0) {
+            None
+        } else {
+            Some(max_duration)
+        };
+        self
+    }
+    /// The maximum wall-clock duration of an evaluation ([`None`] for unlimited).
+    ///
+    /// Not available under `unchecked`.
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.limits.max_duration
+    }
+    /// Set the clock used to enforce the maximum duration of an evaluation, and to measure the
+    /// time reported in [`EvalStats`][crate::EvalStats].
+    ///
+    /// The clock returns the time elapsed since an arbitrary (but fixed) point in time,
+    /// and must never go backwards.
+    ///
+    /// By default, a clock based on [`Instant`][std::time::Instant] is used.
+    /// There is no default clock under `no_std`, so one must be set in order for
+    /// [`set_max_duration`][Engine::set_max_duration] to have any effect.
+    ///
+    /// Not available under `unchecked`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::atomic::{AtomicU64, Ordering};
+    /// # use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use rhai::{Engine, EvalAltResult};
+    ///
+    /// // A fake clock that advances by one millisecond every time it is read
+    /// let ticks = Arc::new(AtomicU64::new(0));
+    /// let clock = ticks.clone();
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine
+    ///     .set_clock(move || Duration::from_millis(clock.fetch_add(1, Ordering::Relaxed)))
+    ///     .set_max_duration(Duration::from_millis(100));
+    ///
+    /// let result = engine.consume("let x = 0; loop { x += 1; }");
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorTimeout(elapsed, _) if elapsed == Duration::from_millis(101)
+    /// ));
+    /// ```
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn set_clock(&mut self, clock: impl Fn() -> Duration + SendSync + 'static) -> &mut Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
     /// Set the module resolution service used by the [`Engine`].
     ///
     /// Not available under `no_module`.
@@ -205,6 +736,14 @@ impl Engine {
         self.module_resolver = Box::new(resolver);
         self
     }
+    /// The module resolution service used by the [`Engine`].
+    ///
+    /// Not available under `no_module`.
+    #[cfg(not(feature = "no_module"))]
+    #[inline(always)]
+    pub fn module_resolver(&self) -> &dyn crate::ModuleResolver {
+        self.module_resolver.as_ref()
+    }
     /// Disable a particular keyword or operator in the language.
     ///
     /// # Examples
@@ -292,18 +831,18 @@ impl Engine {
             // Disabled keywords are OK
             Some(token) if token.is_keyword() => {
                 if !self.disabled_symbols.contains(token.syntax().as_ref()) {
-                    return Err(format!("'{}' is a reserved keyword", keyword).into());
+                    return Err(format!("'{}' is a reserved keyword", keyword));
                 }
             }
             // Active standard operators cannot be made custom
             Some(token) if token.is_operator() => {
                 if !self.disabled_symbols.contains(token.syntax().as_ref()) {
-                    return Err(format!("'{}' is a reserved operator", keyword).into());
+                    return Err(format!("'{}' is a reserved operator", keyword));
                 }
             }
             // Active standard symbols cannot be made custom
             Some(token) if !self.disabled_symbols.contains(token.syntax().as_ref()) => {
-                return Err(format!("'{}' is a reserved symbol", keyword).into())
+                return Err(format!("'{}' is a reserved symbol", keyword))
             }
             // Disabled symbols are OK
             Some(_) => (),
@@ -0,0 +1,110 @@
+//! Module defining the options of a single evaluation.
+
+use crate::engine::State;
+use crate::{Capabilities, EvalStats, InterruptHandle};
+
+#[cfg(not(feature = "unchecked"))]
+use crate::stdlib::time::Duration;
+
+/// Options for a single evaluation, overriding the settings of the [`Engine`][crate::Engine].
+///
+/// Used with [`Engine::eval_ast_with_scope_and_options`][crate::Engine::eval_ast_with_scope_and_options]
+/// and [`Engine::call_fn_with_options`][crate::Engine::call_fn_with_options].
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+/// use rhai::{Capabilities, Engine, EvalOptions, EvalStats, InterruptHandle, Scope};
+///
+/// let engine = Engine::new();
+///
+/// let ast = engine.compile(r#"let s = "hello"; s += ", world!"; s.len"#)?;
+///
+/// let mut stats = EvalStats::default();
+///
+/// let options = EvalOptions::new()
+///     .with_capabilities(Capabilities::new())
+///     .with_interrupt(InterruptHandle::new())
+///     .with_stats(&mut stats);
+///
+/// assert_eq!(engine.eval_ast_with_scope_and_options::<i64>(&mut Scope::new(), &ast, options)?, 13);
+/// assert_eq!(stats.max_string_size, 13);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct EvalOptions<'a> {
+    /// Maximum duration of the evaluation.
+    #[cfg(not(feature = "unchecked"))]
+    timeout: Option<Duration>,
+    /// Capabilities granted to the evaluation.
+    capabilities: Option<Capabilities>,
+    /// Handle to request termination of the evaluation.
+    interrupt: Option<InterruptHandle>,
+    /// Report to fill with the resources used by the evaluation.
+    stats: Option<&'a mut EvalStats>,
+}
+
+impl<'a> EvalOptions<'a> {
+    /// Create a new set of options, with nothing overridden.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Fail the evaluation with [`ErrorTimeout`][crate::EvalAltResult::ErrorTimeout] if it runs
+    /// for longer than `timeout`.
+    ///
+    /// This overrides the maximum duration set via
+    /// [`Engine::set_max_duration`][crate::Engine::set_max_duration].
+    ///
+    /// Not available under `unchecked`.
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Restrict the evaluation to calling functions that require only the
+    /// [capabilities][Capabilities] granted.
+    ///
+    /// Calling any other function fails with
+    /// [`ErrorPermissionDenied`][crate::EvalAltResult::ErrorPermissionDenied], which can be caught.
+    #[inline(always)]
+    pub fn with_capabilities(mut self, granted: Capabilities) -> Self {
+        self.capabilities = Some(granted);
+        self
+    }
+    /// Allow the evaluation to be terminated via an [`InterruptHandle`].
+    ///
+    /// Requesting termination via `handle` ends this evaluation (and any other evaluation started
+    /// with the same handle) with [`ErrorTerminated`][crate::EvalAltResult::ErrorTerminated], but
+    /// not the other evaluations running on the [`Engine`][crate::Engine].
+    #[inline(always)]
+    pub fn with_interrupt(mut self, handle: InterruptHandle) -> Self {
+        self.interrupt = Some(handle);
+        self
+    }
+    /// Fill `stats` with a report on the resources used by the evaluation.
+    ///
+    /// The report is filled whether the evaluation succeeds or not.
+    #[inline(always)]
+    pub fn with_stats(mut self, stats: &'a mut EvalStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+    /// Create a new [`State`] for the evaluation, returning also the report to fill afterwards,
+    /// if any.
+    pub(crate) fn into_state(self) -> (State, Option<&'a mut EvalStats>) {
+        let state = State {
+            stats: self.stats.as_ref().map(|_| Default::default()),
+            capabilities: self.capabilities.map(Into::into),
+            interrupt: self.interrupt,
+            #[cfg(not(feature = "unchecked"))]
+            max_duration: self.timeout,
+            ..Default::default()
+        };
+
+        (state, self.stats)
+    }
+}
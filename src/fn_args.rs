@@ -1,8 +1,10 @@
-//! Helper module which defines [`FuncArgs`] to make function calling easier.
+//! Helper module which defines [`FuncArgs`] to make function calling easier,
+//! and [`FromArgs`] to extract typed values from a list of arguments.
 
 #![allow(non_snake_case)]
 
 use crate::dynamic::Variant;
+use crate::stdlib::{mem, vec::Vec};
 use crate::{Dynamic, StaticVec};
 
 /// Trait that represents arguments to a function call.
@@ -13,7 +15,37 @@ pub trait FuncArgs {
     fn into_vec(self) -> StaticVec<Dynamic>;
 }
 
-/// Macro to implement [`FuncArgs`] for tuples of standard types (each can be
+/// A [`Vec`] passes any number of arguments to a function call.
+impl<T: Variant + Clone> FuncArgs for Vec<T> {
+    #[inline]
+    fn into_vec(self) -> StaticVec<Dynamic> {
+        self.into_iter().map(Variant::into_dynamic).collect()
+    }
+}
+
+/// Trait that represents a tuple of values extracted from a list of [`Dynamic`] values,
+/// such as the arguments of a variadic function or the items of an array.
+///
+/// # Example
+///
+/// ```
+/// use rhai::{Dynamic, FromArgs};
+///
+/// let mut args = vec![Dynamic::from(42_i64), Dynamic::from("hello")];
+///
+/// let (x, s) = <(i64, String)>::from_args(&mut args).unwrap();
+///
+/// assert_eq!(x, 42);
+/// assert_eq!(s, "hello");
+/// ```
+pub trait FromArgs: Sized {
+    /// Extract the values, consuming them.
+    ///
+    /// Returns [`None`] if the number of values is wrong or any value is not of the correct type.
+    fn from_args(args: &mut [Dynamic]) -> Option<Self>;
+}
+
+/// Macro to implement [`FuncArgs`] and [`FromArgs`] for tuples of standard types (each can be
 /// converted into a [`Dynamic`]).
 macro_rules! impl_args {
     ($($p:ident),*) => {
@@ -30,6 +62,21 @@ macro_rules! impl_args {
             }
         }
 
+        impl<$($p: Variant + Clone),*> FromArgs for ($($p,)*)
+        {
+            #[inline]
+            fn from_args(args: &mut [Dynamic]) -> Option<Self> {
+                let mut _iter = args.iter_mut();
+
+                $(let $p = mem::take(_iter.next()?).try_cast::<$p>()?;)*
+
+                match _iter.next() {
+                    Some(_) => None,
+                    None => Some(($($p,)*)),
+                }
+            }
+        }
+
         impl_args!(@pop $($p),*);
     };
     (@pop) => {
@@ -43,3 +90,29 @@ macro_rules! impl_args {
 }
 
 impl_args!(A, B, C, D, E, F, G, H, J, K, L, M, N, P, Q, R, S, T, U, V);
+
+/// Macro to implement conversion of tuples of standard types into arrays.
+#[cfg(not(feature = "no_index"))]
+macro_rules! impl_tuple_into_array {
+    ($($p:ident),+) => {
+        impl<$($p: Variant + Clone),+> From<($($p,)+)> for Dynamic
+        {
+            #[inline]
+            fn from(value: ($($p,)+)) -> Self {
+                let ($($p,)+) = value;
+                let array: crate::Array = vec![$($p.into_dynamic()),+];
+                array.into()
+            }
+        }
+
+        impl_tuple_into_array!(@pop $($p),+);
+    };
+    (@pop $head:ident) => {
+    };
+    (@pop $head:ident $(, $tail:ident)+) => {
+        impl_tuple_into_array!($($tail),+);
+    };
+}
+
+#[cfg(not(feature = "no_index"))]
+impl_tuple_into_array!(A, B, C, D, E, F, G, H, J, K, L, M, N, P, Q, R, S, T, U, V);
@@ -0,0 +1,458 @@
+//! Module that defines async native Rust functions and the evaluation of scripts as futures.
+//!
+//! The `async` feature implies `sync`, because values are sent between the thread running an
+//! evaluation and the task driving it.
+//!
+//! # Limitations
+//!
+//! The evaluator is not itself async: a script cannot be suspended in the middle of a call.
+//! Each evaluation therefore runs on its own worker thread, spawned when the future is first
+//! polled and joined when it completes or is dropped, while async native Rust functions run on
+//! the task polling the future.
+//!
+//! Because the worker thread may outlive any borrow held by the future, the [`Engine`] must be
+//! [`Shared`] and a [`Scope`] is passed in by value and handed back afterwards.
+//!
+//! Dropping the future blocks until the worker thread ends, i.e. until the script reaches its
+//! next operation.  A long-running synchronous native Rust function delays this until it returns.
+#![cfg(feature = "async")]
+
+use crate::dynamic::Variant;
+use crate::fn_register::{by_value, map_type_id};
+use crate::stdlib::{
+    any::{type_name, Any, TypeId},
+    boxed::Box,
+    cell::RefCell,
+    format,
+    future::Future,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    string::ToString,
+    sync::{
+        mpsc::{channel, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+    vec::Vec,
+};
+use crate::{
+    CallableFunction, Dynamic, Engine, EvalAltResult, EvalOptions, FnAccess, FnNamespace,
+    InterruptHandle, Position, Scope, Shared, AST,
+};
+
+/// Result of a native Rust function call.
+type CallResult = Result<Dynamic, Box<EvalAltResult>>;
+
+/// A boxed future returned by an async native Rust function.
+///
+/// It is created and polled only by the task driving an [`EvalFuture`].
+type BoxedFuture = Pin<Box<dyn Future<Output = CallResult> + Send>>;
+
+/// An async native Rust function, taking its arguments by value.
+type AsyncFn = dyn Fn(Vec<Dynamic>) -> BoxedFuture + Send + Sync;
+
+/// A request sent from the thread running an evaluation to the task driving it.
+enum Request {
+    /// Call an async native Rust function and send back its result.
+    Call(Arc<AsyncFn>, Vec<Dynamic>),
+    /// The evaluation is complete.
+    Done(CallResult, Scope<'static>),
+    /// The evaluation panicked.
+    Panicked(Box<dyn Any + Send>),
+}
+
+/// Channels to the task driving an evaluation, used by the thread running it.
+struct Bridge {
+    requests: Sender<Request>,
+    replies: Receiver<CallResult>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl Bridge {
+    /// Send a request to the task driving the evaluation, waking it up.
+    fn send(&self, request: Request) -> bool {
+        let sent = self.requests.send(request).is_ok();
+
+        if let Some(waker) = self.waker.lock().unwrap().as_ref() {
+            waker.wake_by_ref();
+        }
+
+        sent
+    }
+    /// Call an async native Rust function on the task driving the evaluation,
+    /// blocking until it completes.
+    fn call(&self, name: &str, func: Arc<AsyncFn>, args: Vec<Dynamic>) -> CallResult {
+        if !self.send(Request::Call(func, args)) {
+            return Err(format!("evaluation cancelled while calling '{}'", name).into());
+        }
+
+        self.replies.recv().unwrap_or_else(|_| {
+            Err(format!("evaluation cancelled while calling '{}'", name).into())
+        })
+    }
+}
+
+thread_local! {
+    /// The [`Bridge`] of the evaluation running on this thread, if any.
+    static BRIDGE: RefCell<Option<Bridge>> = const { RefCell::new(None) };
+}
+
+/// Channels to the thread running an evaluation, used by the task driving it.
+struct Driver {
+    requests: Receiver<Request>,
+    replies: Sender<CallResult>,
+}
+
+/// An evaluation running on a worker thread, shared by [`EvalFuture`] and [`ScopedEvalFuture`].
+struct Evaluation {
+    engine: Shared<Engine>,
+    start: Option<(Scope<'static>, AST, Bridge)>,
+    driver: Option<Driver>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    pending: Option<BoxedFuture>,
+    interrupt: InterruptHandle,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Evaluation {
+    /// Create a new evaluation, which starts when first polled.
+    fn new(engine: Shared<Engine>, scope: Scope<'static>, ast: &AST) -> Self {
+        let (request_sender, requests) = channel();
+        let (replies, reply_receiver) = channel();
+        let waker = Arc::new(Mutex::new(None));
+
+        let bridge = Bridge {
+            requests: request_sender,
+            replies: reply_receiver,
+            waker: waker.clone(),
+        };
+
+        Self {
+            engine,
+            start: Some((scope, ast.clone(), bridge)),
+            driver: Some(Driver { requests, replies }),
+            waker,
+            pending: None,
+            interrupt: InterruptHandle::new(),
+            worker: None,
+        }
+    }
+    /// Start the evaluation on a worker thread.
+    fn start(&mut self) {
+        if let Some((mut scope, ast, bridge)) = self.start.take() {
+            let engine = self.engine.clone();
+            let interrupt = self.interrupt.clone();
+
+            self.worker = Some(thread::spawn(move || {
+                BRIDGE.with(|b| *b.borrow_mut() = Some(bridge));
+
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    engine.eval_ast_with_scope_and_options::<Dynamic>(
+                        &mut scope,
+                        &ast,
+                        EvalOptions::new().with_interrupt(interrupt),
+                    )
+                }));
+
+                if let Some(bridge) = BRIDGE.with(|b| b.borrow_mut().take()) {
+                    bridge.send(match result {
+                        Ok(result) => Request::Done(result, scope),
+                        Err(err) => Request::Panicked(err),
+                    });
+                }
+            }));
+        }
+    }
+    /// Wait for the worker thread, if any, to end.
+    fn join(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            // Panics are caught on the worker thread and resumed when polled
+            let _ = worker.join();
+        }
+    }
+    /// Drive the evaluation, polling the pending call to an async native Rust function, if any.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<(CallResult, Option<Scope<'static>>)> {
+        // Register the waker before checking for requests, so none is missed
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        self.start();
+
+        loop {
+            let driver = match self.driver {
+                Some(ref driver) => driver,
+                None => return Poll::Ready((Err("evaluation already completed".into()), None)),
+            };
+
+            if let Some(ref mut pending) = self.pending {
+                match pending.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(result) => {
+                        self.pending = None;
+                        // If the evaluation thread is gone, the error surfaces below
+                        let _ = driver.replies.send(result);
+                    }
+                }
+            }
+
+            match driver.requests.try_recv() {
+                Ok(Request::Call(func, args)) => self.pending = Some(func(args)),
+                Ok(Request::Done(result, scope)) => {
+                    self.driver = None;
+                    self.join();
+                    return Poll::Ready((result, Some(scope)));
+                }
+                Ok(Request::Panicked(err)) => {
+                    self.driver = None;
+                    self.join();
+                    panic::resume_unwind(err)
+                }
+                Err(TryRecvError::Empty) => return Poll::Pending,
+                Err(TryRecvError::Disconnected) => {
+                    self.driver = None;
+                    self.join();
+                    return Poll::Ready((Err("evaluation ended unexpectedly".into()), None));
+                }
+            }
+        }
+    }
+    /// Convert the result of the evaluation into the requested type.
+    fn finish<T: Variant + Clone>(&self, result: CallResult) -> Result<T, Box<EvalAltResult>> {
+        let result = result?;
+        let typ = self.engine.map_type_name(result.type_name());
+
+        result.try_cast::<T>().ok_or_else(|| {
+            EvalAltResult::ErrorMismatchOutputType(
+                self.engine.map_type_name(type_name::<T>()).into(),
+                typ.into(),
+                Position::NONE,
+            )
+            .into()
+        })
+    }
+}
+
+impl Drop for Evaluation {
+    fn drop(&mut self) {
+        // Terminate the evaluation, if still running: the script ends at its next operation,
+        // and a pending call fails once its reply channel is closed
+        self.interrupt.interrupt("evaluation cancelled");
+        self.pending = None;
+        self.driver = None;
+        self.join();
+    }
+}
+
+/// A [`Future`] evaluating an [`AST`], created by [`Engine::eval_ast_async`].
+///
+/// The evaluation runs on a worker thread. Whenever the script calls an async native Rust
+/// function, its future is polled by the task polling this future, which returns
+/// [`Poll::Pending`] while the call is pending, yielding to the executor.
+///
+/// Dropping this future terminates the evaluation: any pending call fails with an error, and the
+/// script is terminated at its next operation.  The worker thread is waited for.
+#[must_use = "futures do nothing unless polled"]
+pub struct EvalFuture<T> {
+    evaluation: Evaluation,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: Variant + Clone> Future for EvalFuture<T> {
+    type Output = Result<T, Box<EvalAltResult>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let evaluation = &mut self.get_mut().evaluation;
+
+        evaluation
+            .poll(cx)
+            .map(|(result, _)| evaluation.finish(result))
+    }
+}
+
+/// A [`Future`] evaluating an [`AST`] with a [`Scope`], created by
+/// [`Engine::eval_ast_with_scope_async`].
+///
+/// It resolves to the [`Scope`], as modified by the script, together with the result.
+/// The [`Scope`] is empty if the evaluation ended unexpectedly.
+///
+/// See [`EvalFuture`] for details.
+#[must_use = "futures do nothing unless polled"]
+pub struct ScopedEvalFuture<T> {
+    evaluation: Evaluation,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: Variant + Clone> Future for ScopedEvalFuture<T> {
+    type Output = (Scope<'static>, Result<T, Box<EvalAltResult>>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let evaluation = &mut self.get_mut().evaluation;
+
+        evaluation
+            .poll(cx)
+            .map(|(result, scope)| (scope.unwrap_or_default(), evaluation.finish(result)))
+    }
+}
+
+/// Trait to register async native Rust functions with the [`Engine`].
+pub trait RegisterAsyncFn<FN, ARGS, RET> {
+    /// Register an async native Rust function with the [`Engine`].
+    ///
+    /// Arguments are passed in by value.
+    ///
+    /// The function can only be called during [`eval_ast_async`][Engine::eval_ast_async] or
+    /// [`eval_ast_with_scope_async`][Engine::eval_ast_with_scope_async].
+    /// It is called, and the future it returns is polled, by the task driving the evaluation.
+    /// Calling it during a normal evaluation results in an error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use std::sync::Arc;
+    /// use rhai::{Engine, RegisterAsyncFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // You must use the trait rhai::RegisterAsyncFn to get this method.
+    /// engine.register_async_fn("fetch", |id: i64| async move { Ok(id * 2) });
+    ///
+    /// let engine = Arc::new(engine);
+    /// let ast = engine.compile("fetch(21)")?;
+    ///
+    /// // Drive the future with any executor, e.g. `tokio`
+    /// let future = engine.clone().eval_ast_async::<i64>(&ast);
+    /// # drop(future);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn register_async_fn(&mut self, name: &str, f: FN) -> &mut Self;
+}
+
+macro_rules! def_register_async {
+    () => {
+        def_register_async!(imp);
+    };
+    (imp $($par:ident),*) => {
+        impl<
+            $($par: Variant + Clone,)*
+            FN: Fn($($par),*) -> FUT + Send + Sync + 'static,
+            FUT: Future<Output = Result<RET, Box<EvalAltResult>>> + Send + 'static,
+            RET: Variant + Clone
+        > RegisterAsyncFn<FN, ($($par,)*), RET> for Engine
+        {
+            #[inline]
+            fn register_async_fn(&mut self, name: &str, f: FN) -> &mut Self {
+                let func: Arc<AsyncFn> = Arc::new(move |args: Vec<Dynamic>| {
+                    // The arguments are assumed to be of the correct number and types!
+                    let mut _drain = args.into_iter();
+                    let future = f($(by_value::<$par>(&mut _drain.next().unwrap())),*);
+
+                    Box::pin(async move { future.await.map(Dynamic::from) })
+                });
+
+                self.register_async_fn_raw(name, &[$(map_type_id::<$par>()),*], func)
+            }
+        }
+    };
+    ($p0:ident $(, $p:ident)*) => {
+        def_register_async!(imp $p0 $(, $p)*);
+        def_register_async!($($p),*);
+    };
+}
+
+def_register_async!(A, B, C, D, E, F, G, H, J, K, L, M, N, P, Q, R, S, T, U, V);
+
+impl Engine {
+    /// Register an async native Rust function, taking its arguments by value, with the
+    /// [`Engine`].
+    fn register_async_fn_raw(
+        &mut self,
+        name: &str,
+        arg_types: &[TypeId],
+        func: Arc<AsyncFn>,
+    ) -> &mut Self {
+        let fn_name = name.to_string();
+
+        let f = move |_: crate::NativeCallContext, args: &mut crate::fn_native::FnCallArgs| {
+            let args = args.iter_mut().map(|v| mem::take(*v)).collect();
+
+            BRIDGE.with(|bridge| match *bridge.borrow() {
+                Some(ref bridge) => bridge.call(&fn_name, func.clone(), args),
+                None => Err(format!(
+                    "async function '{}' can only be called via eval_ast_async",
+                    fn_name
+                )
+                .into()),
+            })
+        };
+
+        self.global_namespace.set_fn(
+            name,
+            FnNamespace::Global,
+            FnAccess::Public,
+            None,
+            arg_types,
+            CallableFunction::from_pure(Box::new(f)),
+        );
+        self
+    }
+
+    /// Evaluate an [`AST`], returning a [`Future`] of the result.
+    ///
+    /// The evaluation runs on a worker thread, while async native Rust functions registered via
+    /// [`register_async_fn`][RegisterAsyncFn::register_async_fn] run on the task polling the
+    /// future.  The future yields to the executor whenever such a call is pending.
+    ///
+    /// All other settings, such as the [maximum number of operations][Engine::set_max_operations]
+    /// and the [progress callback][Engine::on_progress], still apply.
+    ///
+    /// Dropping the future terminates the evaluation, waiting for the worker thread to end.
+    #[inline(always)]
+    pub fn eval_ast_async<T: Variant + Clone>(self: Shared<Self>, ast: &AST) -> EvalFuture<T> {
+        EvalFuture {
+            evaluation: Evaluation::new(self, Scope::new(), ast),
+            phantom: PhantomData,
+        }
+    }
+    /// Evaluate an [`AST`] with a [`Scope`], returning a [`Future`] of the [`Scope`] and the
+    /// result.
+    ///
+    /// The [`Scope`] is taken by value because the evaluation runs on a worker thread.
+    /// It is handed back, as modified by the script, when the evaluation completes.
+    ///
+    /// See [`eval_ast_async`][Engine::eval_ast_async] for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use std::sync::Arc;
+    /// use rhai::{Engine, Scope};
+    ///
+    /// let engine = Arc::new(Engine::new());
+    /// let ast = engine.compile("x += 1; x")?;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push("x", 41_i64);
+    ///
+    /// // Drive the future with any executor, e.g. `tokio`
+    /// let future = engine.clone().eval_ast_with_scope_async::<i64>(scope, &ast);
+    /// # drop(future);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn eval_ast_with_scope_async<T: Variant + Clone>(
+        self: Shared<Self>,
+        scope: Scope<'static>,
+        ast: &AST,
+    ) -> ScopedEvalFuture<T> {
+        ScopedEvalFuture {
+            evaluation: Evaluation::new(self, scope, ast),
+            phantom: PhantomData,
+        }
+    }
+}
@@ -1,16 +1,17 @@
 //! Implement function-calling mechanism for [`Engine`].
 
-use crate::ast::{Expr, Stmt};
+use crate::ast::{Expr, Ident, Stmt};
 use crate::engine::{
     search_imports, Imports, State, KEYWORD_DEBUG, KEYWORD_EVAL, KEYWORD_FN_PTR,
     KEYWORD_FN_PTR_CALL, KEYWORD_FN_PTR_CURRY, KEYWORD_IS_DEF_VAR, KEYWORD_PRINT, KEYWORD_TYPE_OF,
 };
-use crate::fn_native::FnCallArgs;
+use crate::fn_native::{FnCallArgs, ResolvedFn};
 use crate::module::NamespaceRef;
 use crate::optimize::OptimizationLevel;
 use crate::stdlib::{
     any::{type_name, TypeId},
     boxed::Box,
+    cell::RefCell,
     convert::TryFrom,
     format,
     iter::{empty, once},
@@ -20,12 +21,16 @@ use crate::stdlib::{
     string::ToString,
     vec::Vec,
 };
-use crate::utils::combine_hashes;
+use crate::token::Token;
+use crate::utils::{calc_variadic_fn_hash, combine_hashes};
 use crate::{
-    calc_native_fn_hash, calc_script_fn_hash, Dynamic, Engine, EvalAltResult, FnPtr,
-    ImmutableString, Module, ParseErrorType, Position, Scope, StaticVec, INT,
+    calc_native_fn_hash, calc_script_fn_hash, CalledFn, Dynamic, Engine, EvalAltResult, FnPtr,
+    ImmutableString, Module, NativeCallContext, ParseErrorType, Position, Scope, StaticVec, INT,
 };
 
+#[cfg(not(feature = "no_std"))]
+use crate::profiler::ProfiledFn;
+
 #[cfg(not(feature = "no_float"))]
 use crate::FLOAT;
 
@@ -153,6 +158,132 @@ pub fn ensure_no_data_race(
 }
 
 impl Engine {
+    /// Resolve a function that is not found via the callback registered with
+    /// [`on_missing_fn`][Engine::on_missing_fn], if any.
+    ///
+    /// A resolved function is cached in the [`State`] under `hash_fn`.
+    fn resolve_missing_fn(
+        &self,
+        mods: &Imports,
+        state: &mut State,
+        lib: &[&Module],
+        fn_name: &str,
+        namespace: Option<&NamespaceRef>,
+        hash_fn: NonZeroU64,
+        args: &FnCallArgs,
+        pos: Position,
+    ) -> Result<Option<ResolvedFn>, Box<EvalAltResult>> {
+        // Resolved before?
+        if let Some(Some((func, _))) = state.functions_cache.get(&hash_fn) {
+            return Ok(Some(ResolvedFn::Function(func.clone())));
+        }
+
+        let missing_fn = match self.missing_fn {
+            Some(ref missing_fn) => missing_fn,
+            None => return Ok(None),
+        };
+
+        // Property getters/setters and indexers are not resolved
+        #[cfg(not(feature = "no_object"))]
+        if fn_name.starts_with(crate::engine::FN_GET) || fn_name.starts_with(crate::engine::FN_SET)
+        {
+            return Ok(None);
+        }
+        #[cfg(not(feature = "no_index"))]
+        if fn_name == crate::engine::FN_IDX_GET || fn_name == crate::engine::FN_IDX_SET {
+            return Ok(None);
+        }
+
+        let namespace = namespace.map(|ns| {
+            ns.iter()
+                .map(|Ident { name, .. }| name.as_str())
+                .collect::<Vec<_>>()
+                .join(Token::DoubleColon.syntax().as_ref())
+        });
+        let arg_types: StaticVec<_> = args.iter().map(|a| a.type_id()).collect();
+
+        let source = state.source.clone();
+        let operations = state.operations;
+
+        let shared_state = RefCell::new(&mut *state);
+        let context = NativeCallContext::from((self, source.as_ref(), mods, lib))
+            .with_state(&shared_state)
+            .with_position(pos);
+        let result = missing_fn(fn_name, namespace.as_deref(), &arg_types, context)
+            .map_err(|err| err.fill_position(pos));
+
+        // Charge any cost added by the callback
+        self.sync_operations(state, operations, pos)?;
+
+        match result? {
+            Some(ResolvedFn::Function(func)) if func.is_native() && !func.is_iter() => {
+                state
+                    .functions_cache
+                    .insert(hash_fn, Some((func.clone(), None)));
+                Ok(Some(ResolvedFn::Function(func)))
+            }
+            // Only native Rust functions can be resolved
+            Some(ResolvedFn::Function(func)) => EvalAltResult::ErrorRuntime(
+                format!(
+                    "Function '{}' cannot be resolved to {}, only to a native Rust function",
+                    fn_name,
+                    if func.is_iter() {
+                        "an iterator"
+                    } else {
+                        "a script-defined function"
+                    }
+                )
+                .into(),
+                pos,
+            )
+            .into(),
+            resolved => Ok(resolved),
+        }
+    }
+
+    /// Record the call to a native Rust function in an error returned by it.
+    ///
+    /// If the function reports its own call, the position and argument types are filled in.
+    /// Errors from script-defined functions called back by the function are wrapped in
+    /// `ErrorInFunctionCall`. Other errors are returned unchanged.
+    fn record_native_fn_call(
+        &self,
+        mut err: Box<EvalAltResult>,
+        fn_name: &str,
+        source: Option<&str>,
+        arg_types: &[&str],
+        pos: Position,
+    ) -> Box<EvalAltResult> {
+        let arg_type_names = || {
+            arg_types
+                .iter()
+                .map(|t| self.map_type_name(t).to_string())
+                .collect::<Vec<_>>()
+        };
+
+        match *err {
+            // The function reports its own call, so fill in the position and argument types
+            EvalAltResult::ErrorInFunctionCall(ref mut name, _, _, ref mut p)
+                if *name == *fn_name && p.is_none() =>
+            {
+                name.fill_arg_types(arg_type_names);
+                *p = pos;
+                err
+            }
+            // Errors from script-defined functions called by the function are wrapped in
+            // `ErrorInFunctionCall` to record the call
+            EvalAltResult::ErrorInFunctionCall(_, _, _, _) => {
+                Box::new(EvalAltResult::ErrorInFunctionCall(
+                    CalledFn::new(fn_name, arg_type_names()),
+                    source.unwrap_or("").to_string(),
+                    err,
+                    pos,
+                ))
+            }
+            _ => err,
+        }
+    }
+
     /// Call a native Rust function registered with the [`Engine`].
     ///
     /// # WARNING
@@ -175,13 +306,11 @@ impl Engine {
     ) -> Result<(Dynamic, bool), Box<EvalAltResult>> {
         self.inc_operations(state, pos)?;
 
-        // Check if function access already in the cache
-        let func = &*state.functions_cache.entry(hash_fn).or_insert_with(|| {
-            // Search for the native function
-            // First search registered functions (can override packages)
-            // Then search packages
-            // Finally search modules
-
+        // Search for the native function
+        // First search registered functions (can override packages)
+        // Then search packages
+        // Finally search modules
+        let search = |hash_fn| {
             //lib.get_fn(hash_fn, pub_only)
             self.global_namespace
                 .get_fn(hash_fn, pub_only)
@@ -197,9 +326,15 @@ impl Engine {
                     mods.get_fn(hash_fn)
                         .map(|(f, source)| (f.clone(), source.cloned()))
                 })
-        });
+        };
+
+        // Check if function access already in the cache
+        let func = &*state
+            .functions_cache
+            .entry(hash_fn)
+            .or_insert_with(|| search(hash_fn));
 
-        if let Some((func, source)) = func {
+        if let Some((func, source)) = func.clone() {
             assert!(func.is_native());
 
             // Calling pure function but the first argument is a reference?
@@ -207,21 +342,56 @@ impl Engine {
             backup.change_first_arg_to_copy(is_ref && func.is_pure(), args);
 
             // Run external function
-            let source = if source.is_none() {
-                state.source.as_ref()
-            } else {
-                source.as_ref()
-            };
+            #[cfg(not(feature = "no_std"))]
+            if self.profiler.is_some() {
+                self.profiler_enter(&mut state.profiler, state.operations, fn_name);
+            }
+
+            #[cfg(not(feature = "no_std"))]
+            let fn_source = source.clone();
+            let source = source.or_else(|| state.source.clone());
+            let operations = state.operations;
+            // Arguments may be consumed, so record their types for the backtrace beforehand
+            let arg_types: StaticVec<_> = args.iter().map(|a| a.type_name()).collect();
+
+            let shared_state = RefCell::new(&mut *state);
+            let context = NativeCallContext::from((self, source.as_ref(), mods, lib))
+                .with_state(&shared_state)
+                .with_position(pos);
+
             let result = if func.is_plugin_fn() {
-                func.get_plugin_fn()
-                    .call((self, source, mods, lib).into(), args)
+                func.get_plugin_fn().call(context, args)
             } else {
-                func.get_native_fn()((self, source, mods, lib).into(), args)
+                func.get_native_fn()(context, args)
             };
 
+            #[cfg(not(feature = "no_std"))]
+            if self.profiler.is_some() {
+                self.profiler_exit(
+                    &mut state.profiler,
+                    state.operations,
+                    ProfiledFn {
+                        hash: hash_fn.get(),
+                        name: fn_name,
+                        source: fn_source.as_ref().map(|s| s.as_str()),
+                        is_native: true,
+                    },
+                );
+            }
+
             // Restore the original reference
             backup.restore_first_arg(args);
 
+            let is_method = func.is_method();
+
+            let result = result.map_err(|err| {
+                let source = source.as_ref().map(|s| s.as_str());
+                self.record_native_fn_call(err, fn_name, source, &arg_types, pos)
+            });
+
+            // Charge any cost added by the function
+            self.sync_operations(state, operations, pos)?;
+
             let result = result?;
 
             // See if the function match print/debug (which requires special processing)
@@ -247,7 +417,7 @@ impl Engine {
                     let source = state.source.as_ref().map(|s| s.as_str());
                     ((self.debug)(text, source, pos).into(), false)
                 }
-                _ => (result, func.is_method()),
+                _ => (result, is_method),
             });
         }
 
@@ -264,6 +434,36 @@ impl Engine {
             return Ok((val.clone(), false));
         }
 
+        // Search for a variadic function taking the number of arguments
+        let hash_variadic = calc_variadic_fn_hash(fn_name, args.len());
+
+        if hash_variadic != hash_fn && search(hash_variadic).is_some() {
+            return self.call_native_fn(
+                mods,
+                state,
+                lib,
+                fn_name,
+                hash_variadic,
+                args,
+                is_ref,
+                pub_only,
+                pos,
+                def_val,
+            );
+        }
+
+        // Resolve the function via the callback (if any)
+        match self.resolve_missing_fn(mods, state, lib, fn_name, None, hash_fn, args, pos)? {
+            // The function is now cached, so call it again
+            Some(ResolvedFn::Function(_)) => {
+                return self.call_native_fn(
+                    mods, state, lib, fn_name, hash_fn, args, is_ref, pub_only, pos, def_val,
+                )
+            }
+            Some(ResolvedFn::Value(value)) => return Ok((value, false)),
+            None => (),
+        }
+
         // Getter function not found?
         #[cfg(not(feature = "no_object"))]
         if let Some(prop) = extract_prop_from_getter(fn_name) {
@@ -369,12 +569,42 @@ impl Engine {
             return Err(Box::new(EvalAltResult::ErrorStackOverflow(pos)));
         }
 
+        if let Some(stats) = state.stats.as_mut() {
+            stats.max_call_depth = stats.max_call_depth.max(level);
+        }
+
+        if self.debugger.is_some() {
+            let source = fn_def
+                .lib
+                .as_ref()
+                .and_then(|m| m.id())
+                .map(Into::into)
+                .or_else(|| state.source.clone());
+            self.debugger_enter_fn(state, &fn_def.name, args, source, pos);
+        }
+
         let orig_scope_level = state.scope_level;
         state.scope_level += 1;
 
+        // The memory used by the caller is added to that of all the calling functions
+        #[cfg(not(feature = "unchecked"))]
+        let orig_memory = (
+            state.memory_outer,
+            state.memory,
+            mem::take(&mut state.memory_vars),
+        );
+        #[cfg(not(feature = "unchecked"))]
+        {
+            state.memory_outer += state.memory;
+            state.memory = 0;
+        }
+
         let prev_scope_len = scope.len();
         let prev_mods_len = mods.len();
 
+        // Arguments are consumed, so record their types for the backtrace beforehand
+        let arg_types: StaticVec<_> = args.iter().map(|a| a.type_name()).collect();
+
         // Put arguments into scope as variables
         // Actually consume the arguments instead of cloning them
         scope.extend(
@@ -409,6 +639,11 @@ impl Engine {
             mods.extend(fn_def.mods.iter_raw().map(|(n, m)| (n.clone(), m.clone())));
         }
 
+        #[cfg(not(feature = "no_std"))]
+        if self.profiler.is_some() {
+            self.profiler_enter(&mut state.profiler, state.operations, &fn_def.name);
+        }
+
         // Evaluate the function
         let stmt = &fn_def.body;
 
@@ -417,36 +652,14 @@ impl Engine {
             .or_else(|err| match *err {
                 // Convert return statement to return value
                 EvalAltResult::Return(x, _) => Ok(x),
-                EvalAltResult::ErrorInFunctionCall(name, src, err, _) => {
-                    EvalAltResult::ErrorInFunctionCall(
-                        format!(
-                            "{}{} < {}",
-                            name,
-                            if src.is_empty() {
-                                "".to_string()
-                            } else {
-                                format!(" @ '{}'", src)
-                            },
-                            fn_def.name
-                        ),
-                        fn_def
-                            .lib
-                            .as_ref()
-                            .map(|m| m.id())
-                            .flatten()
-                            .or_else(|| state.source.as_ref().map(|s| s.as_str()))
-                            .unwrap_or("")
-                            .to_string(),
-                        err,
-                        pos,
-                    )
-                    .into()
-                }
                 // System errors are passed straight-through
                 err if err.is_system_exception() => Err(Box::new(err)),
                 // Other errors are wrapped in `ErrorInFunctionCall`
-                _ => EvalAltResult::ErrorInFunctionCall(
-                    fn_def.name.to_string(),
+                err => EvalAltResult::ErrorInFunctionCall(
+                    CalledFn::new(
+                        fn_def.name.as_str(),
+                        arg_types.iter().map(|t| self.map_type_name(t).to_string()),
+                    ),
                     fn_def
                         .lib
                         .as_ref()
@@ -455,7 +668,7 @@ impl Engine {
                         .or_else(|| state.source.as_ref().map(|s| s.as_str()))
                         .unwrap_or("")
                         .to_string(),
-                    err,
+                    err.into(),
                     pos,
                 )
                 .into(),
@@ -466,6 +679,39 @@ impl Engine {
         mods.truncate(prev_mods_len);
         state.scope_level = orig_scope_level;
 
+        #[cfg(not(feature = "unchecked"))]
+        {
+            state.memory_outer = orig_memory.0;
+            state.memory = orig_memory.1;
+            state.memory_vars = orig_memory.2;
+        }
+
+        #[cfg(not(feature = "no_std"))]
+        if self.profiler.is_some() {
+            let hash = calc_script_fn_hash(empty(), &fn_def.name, fn_def.params.len());
+            let hash = hash.map_or(0, |h| h.get());
+            let source = fn_def
+                .lib
+                .as_ref()
+                .map(|m| m.id_raw().cloned())
+                .flatten()
+                .or_else(|| state.source.clone());
+            self.profiler_exit(
+                &mut state.profiler,
+                state.operations,
+                ProfiledFn {
+                    hash,
+                    name: &fn_def.name,
+                    source: source.as_ref().map(|s| s.as_str()),
+                    is_native: false,
+                },
+            );
+        }
+
+        if self.debugger.is_some() {
+            self.debugger_exit_fn(state);
+        }
+
         result
     }
 
@@ -727,7 +973,7 @@ impl Engine {
         // Compile the script text
         // No optimizations because we only run it once
         let ast = self.compile_with_scope_and_optimization_level(
-            &Default::default(),
+            scope,
             &[script],
             OptimizationLevel::None,
         )?;
@@ -741,6 +987,10 @@ impl Engine {
         let mut new_state = State {
             source: state.source.clone(),
             operations: state.operations,
+            debugger: state.debugger.take(),
+            interrupt: state.interrupt.clone(),
+            #[cfg(not(feature = "unchecked"))]
+            memory_outer: state.memory_outer,
             ..Default::default()
         };
 
@@ -748,6 +998,14 @@ impl Engine {
             self.eval_statements_raw(scope, mods, &mut new_state, ast.statements(), lib, level);
 
         state.operations = new_state.operations;
+        state.debugger = new_state.debugger;
+
+        // The script may have modified any variable, so estimate them all again
+        #[cfg(not(feature = "unchecked"))]
+        {
+            state.memory = 0;
+            state.memory_vars.clear();
+        }
         result
     }
 
@@ -1053,6 +1311,11 @@ impl Engine {
         if args_expr.is_empty() && curry.is_empty() {
             // No arguments
             args = Default::default();
+
+            if self.debugger.is_some() {
+                let calls_script = self.has_override(Some(mods), lib, None, hash_script, false);
+                self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &[]);
+            }
         } else {
             // If the first argument is a variable, and there is no curried arguments, convert to method-call style
             // in order to leverage potential &mut first argument and avoid cloning the value
@@ -1064,6 +1327,13 @@ impl Engine {
                     .map(|expr| self.eval_expr(scope, mods, state, lib, this_ptr, expr, level))
                     .collect::<Result<_, _>>()?;
 
+                if self.debugger.is_some() {
+                    let calls_script = self.has_override(Some(mods), lib, None, hash_script, false);
+                    self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &arg_values);
+                }
+
+                self.touch_memory(scope, state, &args_expr[0]);
+
                 let (mut target, pos) =
                     self.search_namespace(scope, mods, state, lib, this_ptr, &args_expr[0])?;
 
@@ -1090,6 +1360,11 @@ impl Engine {
                     .map(|expr| self.eval_expr(scope, mods, state, lib, this_ptr, expr, level))
                     .collect::<Result<_, _>>()?;
 
+                if self.debugger.is_some() {
+                    let calls_script = self.has_override(Some(mods), lib, None, hash_script, false);
+                    self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &arg_values);
+                }
+
                 args = curry.iter_mut().chain(arg_values.iter_mut()).collect();
             }
         }
@@ -1114,6 +1389,21 @@ impl Engine {
         .map(|(v, _)| v)
     }
 
+    /// Is a namespace-qualified function call to a script-defined function?
+    fn is_qualified_script_fn(
+        &self,
+        mods: &Imports,
+        state: &mut State,
+        namespace: &NamespaceRef,
+        hash_script: NonZeroU64,
+    ) -> bool {
+        search_imports(mods, state, namespace).map_or(false, |module| {
+            module
+                .get_qualified_fn(hash_script)
+                .map_or(false, |f| f.is_script())
+        })
+    }
+
     /// Call a namespace-qualified function in normal function-call style.
     pub(crate) fn make_qualified_function_call(
         &self,
@@ -1140,6 +1430,11 @@ impl Engine {
         if args_expr.is_empty() {
             // No arguments
             args = Default::default();
+
+            if self.debugger.is_some() {
+                let calls_script = self.is_qualified_script_fn(mods, state, namespace, hash_script);
+                self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &[]);
+            }
         } else {
             // See if the first argument is a variable (not namespace-qualified).
             // If so, convert to method-call style in order to leverage potential
@@ -1159,6 +1454,14 @@ impl Engine {
                     })
                     .collect::<Result<_, _>>()?;
 
+                if self.debugger.is_some() {
+                    let calls_script =
+                        self.is_qualified_script_fn(mods, state, namespace, hash_script);
+                    self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &arg_values);
+                }
+
+                self.touch_memory(scope, state, &args_expr[0]);
+
                 // Get target reference to first argument
                 let (target, pos) =
                     self.search_scope_only(scope, mods, state, lib, this_ptr, &args_expr[0])?;
@@ -1182,6 +1485,12 @@ impl Engine {
                     .map(|expr| self.eval_expr(scope, mods, state, lib, this_ptr, expr, level))
                     .collect::<Result<_, _>>()?;
 
+                if self.debugger.is_some() {
+                    let calls_script =
+                        self.is_qualified_script_fn(mods, state, namespace, hash_script);
+                    self.debugger_suspend_frame(state, scope, this_ptr, calls_script, &arg_values);
+                }
+
                 args = arg_values.iter_mut().collect();
             }
         }
@@ -1204,12 +1513,55 @@ impl Engine {
                 // 3) The two hashes are combined.
                 let hash_qualified_fn = combine_hashes(hash_script, hash_fn_args);
 
-                module.get_qualified_fn(hash_qualified_fn)
+                module.get_qualified_fn(hash_qualified_fn).or_else(|| {
+                    // Then search for a variadic function taking the number of arguments
+                    let hash_fn_args = calc_variadic_fn_hash("", args.len());
+                    module.get_qualified_fn(combine_hashes(hash_script, hash_fn_args))
+                })
             }
             r => r,
         };
 
-        match func {
+        // Resolve the function via the callback (if any)
+        let resolved;
+        let func = match func {
+            None if def_val.is_none() => {
+                let hash_fn_args =
+                    calc_native_fn_hash(empty(), "", args.iter().map(|a| a.type_id())).unwrap();
+                let hash_qualified_fn = combine_hashes(hash_script, hash_fn_args);
+
+                match self.resolve_missing_fn(
+                    mods,
+                    state,
+                    lib,
+                    fn_name,
+                    Some(namespace),
+                    hash_qualified_fn,
+                    args.as_ref(),
+                    pos,
+                )? {
+                    Some(ResolvedFn::Function(f)) => {
+                        resolved = f;
+                        Some(&resolved)
+                    }
+                    Some(ResolvedFn::Value(value)) => return Ok(value),
+                    None => None,
+                }
+            }
+            f => f,
+        };
+
+        #[cfg(not(feature = "no_std"))]
+        let profile_name = match func {
+            Some(f) if self.profiler.is_some() && (f.is_plugin_fn() || f.is_native()) => {
+                let name = format!("{}{}", namespace, fn_name);
+                self.profiler_enter(&mut state.profiler, state.operations, &name);
+                Some(name)
+            }
+            _ => None,
+        };
+
+        let result = match func {
             #[cfg(not(feature = "no_function"))]
             Some(f) if f.is_script() => {
                 // Clone first argument
@@ -1236,10 +1588,26 @@ impl Engine {
 
                 result
             }
-            Some(f) if f.is_plugin_fn() => f
-                .get_plugin_fn()
-                .clone()
-                .call((self, module.id_raw(), &*mods, lib).into(), args.as_mut()),
+            Some(f) if f.is_plugin_fn() => {
+                let operations = state.operations;
+                let arg_types: StaticVec<_> = args.iter().map(|a| a.type_name()).collect();
+
+                let shared_state = RefCell::new(&mut *state);
+                let context = NativeCallContext::from((self, module.id_raw(), &*mods, lib))
+                    .with_state(&shared_state)
+                    .with_position(pos);
+                let result = f
+                    .get_plugin_fn()
+                    .clone()
+                    .call(context, args.as_mut())
+                    .map_err(|err| {
+                        self.record_native_fn_call(err, fn_name, module.id(), &arg_types, pos)
+                    });
+
+                // Charge any cost added by the function
+                self.sync_operations(state, operations, pos)?;
+                result
+            }
             Some(f) if f.is_native() => {
                 if !f.is_method() {
                     // Clone first argument
@@ -1250,7 +1618,20 @@ impl Engine {
                     }
                 }
 
-                f.get_native_fn()((self, module.id_raw(), &*mods, lib).into(), args.as_mut())
+                let operations = state.operations;
+                let arg_types: StaticVec<_> = args.iter().map(|a| a.type_name()).collect();
+
+                let shared_state = RefCell::new(&mut *state);
+                let context = NativeCallContext::from((self, module.id_raw(), &*mods, lib))
+                    .with_state(&shared_state)
+                    .with_position(pos);
+                let result = f.get_native_fn()(context, args.as_mut()).map_err(|err| {
+                    self.record_native_fn_call(err, fn_name, module.id(), &arg_types, pos)
+                });
+
+                // Charge any cost added by the function
+                self.sync_operations(state, operations, pos)?;
+                result
             }
             Some(f) => unreachable!("unknown function type: {:?}", f),
             None if def_val.is_some() => Ok(def_val.unwrap().clone()),
@@ -1271,7 +1652,23 @@ impl Engine {
                 pos,
             )
             .into(),
+        };
+
+        #[cfg(not(feature = "no_std"))]
+        if let Some(name) = profile_name {
+            self.profiler_exit(
+                &mut state.profiler,
+                state.operations,
+                ProfiledFn {
+                    hash: hash_script.get(),
+                    name: &name,
+                    source: module.id(),
+                    is_native: true,
+                },
+            );
         }
+
+        result
     }
 }
 
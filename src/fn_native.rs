@@ -1,10 +1,12 @@
 //! Module defining interfaces to native-Rust functions.
 
 use crate::ast::{FnAccess, ScriptFnDef};
-use crate::engine::Imports;
+use crate::engine::{Imports, State};
 use crate::plugin::PluginFunction;
 use crate::stdlib::{
+    any::TypeId,
     boxed::Box,
+    cell::RefCell,
     convert::{TryFrom, TryInto},
     fmt,
     iter::empty,
@@ -14,8 +16,8 @@ use crate::stdlib::{
 };
 use crate::token::is_valid_identifier;
 use crate::{
-    calc_script_fn_hash, Dynamic, Engine, EvalAltResult, EvalContext, ImmutableString, Module,
-    Position,
+    calc_script_fn_hash, Debugger, DebuggerCommand, DebuggerEvent, Dynamic, Engine, EvalAltResult,
+    EvalContext, ImmutableString, Module, Position,
 };
 
 #[cfg(not(feature = "sync"))]
@@ -45,11 +47,9 @@ pub type Shared<T> = Rc<T>;
 pub type Shared<T> = Arc<T>;
 
 /// Synchronized shared object.
-#[cfg(not(feature = "no_closure"))]
 #[cfg(not(feature = "sync"))]
 pub type Locked<T> = crate::stdlib::cell::RefCell<T>;
 /// Synchronized shared object.
-#[cfg(not(feature = "no_closure"))]
 #[cfg(feature = "sync")]
 pub type Locked<T> = crate::stdlib::sync::RwLock<T>;
 
@@ -60,6 +60,8 @@ pub struct NativeCallContext<'e, 's, 'a, 'm, 'pm: 'm> {
     source: Option<&'s str>,
     pub(crate) mods: Option<&'a Imports>,
     pub(crate) lib: &'m [&'pm Module],
+    state: Option<&'a RefCell<&'a mut State>>,
+    pos: Position,
 }
 
 impl<'e, 's, 'a, 'm, 'pm: 'm, M: AsRef<[&'pm Module]> + ?Sized>
@@ -73,6 +75,8 @@ impl<'e, 's, 'a, 'm, 'pm: 'm, M: AsRef<[&'pm Module]> + ?Sized>
             source: value.1.map(|s| s.as_str()),
             mods: Some(value.2),
             lib: value.3.as_ref(),
+            state: None,
+            pos: Position::NONE,
         }
     }
 }
@@ -87,6 +91,8 @@ impl<'e, 'm, 'pm: 'm, M: AsRef<[&'pm Module]> + ?Sized> From<(&'e Engine, &'m M)
             source: None,
             mods: None,
             lib: value.1.as_ref(),
+            state: None,
+            pos: Position::NONE,
         }
     }
 }
@@ -100,6 +106,8 @@ impl<'e, 's, 'a, 'm, 'pm> NativeCallContext<'e, 's, 'a, 'm, 'pm> {
             source: None,
             mods: None,
             lib: lib.as_ref(),
+            state: None,
+            pos: Position::NONE,
         }
     }
     /// _(INTERNALS)_ Create a new [`NativeCallContext`].
@@ -118,8 +126,23 @@ impl<'e, 's, 'a, 'm, 'pm> NativeCallContext<'e, 's, 'a, 'm, 'pm> {
             source: source.as_ref().map(|s| s.as_str()),
             mods: Some(imports),
             lib: lib.as_ref(),
+            state: None,
+            pos: Position::NONE,
         }
     }
+    /// Share the [`State`] of the current evaluation, so that its limits and settings also apply
+    /// to [`add_cost`][NativeCallContext::add_cost] and to functions called within this context.
+    #[inline(always)]
+    pub(crate) fn with_state(mut self, state: &'a RefCell<&'a mut State>) -> Self {
+        self.state = Some(state);
+        self
+    }
+    /// Set the [`Position`] of the function call.
+    #[inline(always)]
+    pub(crate) fn with_position(mut self, pos: Position) -> Self {
+        self.pos = pos;
+        self
+    }
     /// The current [`Engine`].
     #[inline(always)]
     pub fn engine(&self) -> &Engine {
@@ -130,6 +153,85 @@ impl<'e, 's, 'a, 'm, 'pm> NativeCallContext<'e, 's, 'a, 'm, 'pm> {
     pub fn source(&self) -> Option<&str> {
         self.source
     }
+    /// [Position][`Position`] of the function call, or [`Position::NONE`] if not called from
+    /// a script.
+    ///
+    /// Functions called within this context are reported at this position.
+    #[inline(always)]
+    pub fn position(&self) -> Position {
+        self.pos
+    }
+    /// Add a cost to the number of operations performed, e.g. in proportion to the amount of work
+    /// done by the function.
+    ///
+    /// If the maximum number of operations is exceeded, an error is returned which should be
+    /// passed back to the caller.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "unchecked"))]
+    /// # {
+    /// use rhai::{Engine, EvalAltResult, FnAccess, FnNamespace, ImmutableString, Module, INT};
+    ///
+    /// let mut module = Module::new();
+    ///
+    /// module.set_raw_fn("lookup", FnNamespace::Global, FnAccess::Public,
+    ///                   &[std::any::TypeId::of::<ImmutableString>()],
+    ///                   |context, args| {
+    ///                       let len = args[0].clone().cast::<ImmutableString>().len();
+    ///                       // Each byte of the key costs one operation
+    ///                       context.add_cost(len as u64)?;
+    ///                       Ok(len as INT)
+    ///                   });
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_global_module(module.into());
+    /// engine.set_max_operations(1000);
+    ///
+    /// assert!(engine.consume("lookup(\"hello\");").is_ok());
+    ///
+    /// let result = engine.consume(r#"
+    ///     let key = "0123456789";
+    ///     key += key; key += key; key += key; key += key; key += key; key += key; key += key;
+    ///     lookup(key);
+    /// "#);
+    ///
+    /// assert!(matches!(
+    ///     *result.expect_err("should error"),
+    ///     EvalAltResult::ErrorTooManyOperations(_)
+    /// ));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn add_cost(&self, cost: u64) -> Result<(), Box<EvalAltResult>> {
+        if let Some(state) = self.state {
+            let mut state = state.borrow_mut();
+            let count = state.operations.saturating_add(cost);
+            state.operations = count;
+
+            #[cfg(not(feature = "unchecked"))]
+            // Guard against too many operations
+            if self.engine.max_operations() > 0 && count > self.engine.max_operations() {
+                return EvalAltResult::ErrorTooManyOperations(Position::NONE).into();
+            }
+        }
+
+        Ok(())
+    }
+    /// Is a [capability][crate::Capabilities] granted to the current evaluation?
+    ///
+    /// Always `true` if the evaluation is not restricted to a set of capabilities.
+    #[inline(always)]
+    pub fn is_granted(&self, capability: &str) -> bool {
+        self.state.map_or(true, |state| {
+            state
+                .borrow()
+                .capabilities
+                .as_ref()
+                .map_or(true, |capabilities| capabilities.is_granted(capability))
+        })
+    }
     /// Get an iterator over the current set of modules imported via `import` statements.
     #[cfg(not(feature = "no_module"))]
     #[inline(always)]
@@ -177,10 +279,25 @@ impl<'e, 's, 'a, 'm, 'pm> NativeCallContext<'e, 's, 'a, 'm, 'pm> {
         args: &mut [&mut Dynamic],
         def_value: Option<&Dynamic>,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        // The function is called within the same evaluation as the caller, so that operations,
+        // time and other resources it uses are counted towards those of the caller
+        let mut new_state;
+        let mut state_guard;
+        let state = match self.state {
+            Some(state) => {
+                state_guard = state.borrow_mut();
+                &mut **state_guard
+            }
+            None => {
+                new_state = Default::default();
+                &mut new_state
+            }
+        };
+
         self.engine()
             .exec_fn_call(
                 &mut self.mods.cloned().unwrap_or_default(),
-                &mut Default::default(),
+                state,
                 self.lib,
                 fn_name,
                 calc_script_fn_hash(empty(), fn_name, args.len() - if is_method { 1 } else { 0 }),
@@ -188,7 +305,7 @@ impl<'e, 's, 'a, 'm, 'pm> NativeCallContext<'e, 's, 'a, 'm, 'pm> {
                 is_method,
                 is_method,
                 public_only,
-                Position::NONE,
+                self.pos,
                 None,
                 def_value,
                 0,
@@ -395,6 +512,15 @@ pub type OnProgressCallback = Box<dyn Fn(u64) -> Option<Dynamic> + 'static>;
 #[cfg(feature = "sync")]
 pub type OnProgressCallback = Box<dyn Fn(u64) -> Option<Dynamic> + Send + Sync + 'static>;
 
+/// A standard callback function for reading a monotonic clock.
+#[cfg(not(feature = "unchecked"))]
+#[cfg(not(feature = "sync"))]
+pub type OnClockCallback = Box<dyn Fn() -> crate::stdlib::time::Duration + 'static>;
+/// A standard callback function for reading a monotonic clock.
+#[cfg(not(feature = "unchecked"))]
+#[cfg(feature = "sync")]
+pub type OnClockCallback = Box<dyn Fn() -> crate::stdlib::time::Duration + Send + Sync + 'static>;
+
 /// A standard callback function for printing.
 #[cfg(not(feature = "sync"))]
 pub type OnPrintCallback = Box<dyn Fn(&str) + 'static>;
@@ -422,6 +548,79 @@ pub type OnVarCallback = Box<
         + 'static,
 >;
 
+/// A standard callback function for variable definition.
+#[cfg(not(feature = "sync"))]
+pub type OnDefVarCallback =
+    Box<dyn Fn(&str, bool, usize, &EvalContext) -> Result<bool, Box<EvalAltResult>> + 'static>;
+/// A standard callback function for variable definition.
+#[cfg(feature = "sync")]
+pub type OnDefVarCallback = Box<
+    dyn Fn(&str, bool, usize, &EvalContext) -> Result<bool, Box<EvalAltResult>>
+        + Send
+        + Sync
+        + 'static,
+>;
+
+/// A standard callback function for resolving functions that are not found.
+#[cfg(not(feature = "sync"))]
+pub type OnMissingFnCallback = Box<
+    dyn Fn(
+            &str,
+            Option<&str>,
+            &[TypeId],
+            NativeCallContext,
+        ) -> Result<Option<ResolvedFn>, Box<EvalAltResult>>
+        + 'static,
+>;
+/// A standard callback function for resolving functions that are not found.
+#[cfg(feature = "sync")]
+pub type OnMissingFnCallback = Box<
+    dyn Fn(
+            &str,
+            Option<&str>,
+            &[TypeId],
+            NativeCallContext,
+        ) -> Result<Option<ResolvedFn>, Box<EvalAltResult>>
+        + Send
+        + Sync
+        + 'static,
+>;
+
+/// A standard callback function for initializing the debugger.
+#[cfg(not(feature = "sync"))]
+pub type OnDebuggerInitCallback = Box<dyn Fn(&mut Debugger) + 'static>;
+/// A standard callback function for initializing the debugger.
+#[cfg(feature = "sync")]
+pub type OnDebuggerInitCallback = Box<dyn Fn(&mut Debugger) + Send + Sync + 'static>;
+
+/// A standard callback function for debugging.
+#[cfg(not(feature = "sync"))]
+pub type OnDebuggerCallback = Box<
+    dyn Fn(&mut EvalContext, DebuggerEvent, Position) -> Result<DebuggerCommand, Box<EvalAltResult>>
+        + 'static,
+>;
+/// A standard callback function for debugging.
+#[cfg(feature = "sync")]
+pub type OnDebuggerCallback = Box<
+    dyn Fn(&mut EvalContext, DebuggerEvent, Position) -> Result<DebuggerCommand, Box<EvalAltResult>>
+        + Send
+        + Sync
+        + 'static,
+>;
+
+/// Resolution of a function that is not found, returned by the callback registered via
+/// [`Engine::on_missing_fn`][crate::Engine::on_missing_fn].
+#[derive(Debug, Clone)]
+pub enum ResolvedFn {
+    /// A native Rust function to call.
+    ///
+    /// It is cached, so that later calls to the same function with the same argument types
+    /// during the same evaluation do not invoke the callback again.
+    Function(CallableFunction),
+    /// A value to use directly as the result of the call.
+    Value(Dynamic),
+}
+
 /// A type encapsulating a function callable by Rhai.
 #[derive(Clone)]
 pub enum CallableFunction {
@@ -156,7 +156,7 @@ pub fn map_result(
 
 /// Remap `&str` | `String` to `ImmutableString`.
 #[inline(always)]
-fn map_type_id<T: 'static>() -> TypeId {
+pub(crate) fn map_type_id<T: 'static>() -> TypeId {
     let id = TypeId::of::<T>();
 
     if id == TypeId::of::<&str>() {
@@ -0,0 +1,122 @@
+//! Module defining the handle to interrupt running evaluations.
+
+use crate::fn_native::{Locked, Shared};
+use crate::Dynamic;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The state shared by all clones of an [`InterruptHandle`].
+#[derive(Debug, Default)]
+struct InterruptState {
+    /// Has termination been requested?
+    requested: AtomicBool,
+    /// The reason for termination.
+    reason: Locked<Option<Dynamic>>,
+}
+
+/// A handle to request termination of running evaluations.
+///
+/// A new handle, created via [`InterruptHandle::new`], controls only the evaluations started with
+/// it via [`EvalOptions::with_interrupt`][crate::EvalOptions::with_interrupt].
+///
+/// The handle obtained via [`Engine::interrupt_handle`][crate::Engine::interrupt_handle] instead
+/// stops _everything_ running on the [`Engine`][crate::Engine].
+///
+/// Once [`interrupt`][InterruptHandle::interrupt] is called, the evaluations controlled by the
+/// handle, including script functions called back from native Rust functions, end with
+/// [`ErrorTerminated`][crate::EvalAltResult::ErrorTerminated] carrying the reason, at the next
+/// operation.  This remains in effect, also for any such evaluation started afterwards, until
+/// [`reset`][InterruptHandle::reset] is called.
+///
+/// # Thread Safety
+///
+/// Under the `sync` feature, [`InterruptHandle`] is [`Send`] `+` [`Sync`] and can be used from
+/// another thread. Otherwise, it can only be used from the thread running the evaluation, e.g.
+/// inside a native Rust function or callback.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "sync")]
+/// # {
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Duration;
+/// use rhai::{Engine, EvalAltResult, EvalOptions, InterruptHandle, Scope};
+///
+/// let engine = Arc::new(Engine::new());
+/// let ast = engine.compile("let x = 0; loop { x += 1; }").unwrap();
+/// let handle = InterruptHandle::new();
+///
+/// let (runner, h) = (engine.clone(), handle.clone());
+/// let worker = thread::spawn(move || {
+///     let options = EvalOptions::new().with_interrupt(h);
+///     runner.eval_ast_with_scope_and_options::<()>(&mut Scope::new(), &ast, options)
+/// });
+///
+/// thread::sleep(Duration::from_millis(10));
+/// handle.interrupt("cancelled by user");
+///
+/// let result = worker.join().unwrap();
+///
+/// assert!(matches!(
+///     *result.expect_err("should error"),
+///     EvalAltResult::ErrorTerminated(reason, _) if reason.to_string() == "cancelled by user"
+/// ));
+///
+/// // Other evaluations are not affected
+/// assert!(engine.consume("let x = 42;").is_ok());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Shared<InterruptState>);
+
+impl InterruptHandle {
+    /// Create a new [`InterruptHandle`], controlling no evaluation yet.
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self(Default::default())
+    }
+    /// Request termination of the evaluations controlled by this handle, with a reason.
+    ///
+    /// If termination has already been requested, the reason is replaced.
+    #[inline]
+    pub fn interrupt(&self, reason: impl Into<Dynamic>) {
+        #[cfg(not(feature = "sync"))]
+        let mut value = self.0.reason.borrow_mut();
+        #[cfg(feature = "sync")]
+        let mut value = self.0.reason.write().unwrap();
+
+        *value = Some(reason.into());
+        self.0.requested.store(true, Ordering::SeqCst);
+    }
+    /// Has termination been requested?
+    #[inline(always)]
+    pub fn is_interrupted(&self) -> bool {
+        self.0.requested.load(Ordering::Relaxed)
+    }
+    /// Get the reason for termination, if requested.
+    #[inline]
+    pub fn reason(&self) -> Option<Dynamic> {
+        if !self.is_interrupted() {
+            return None;
+        }
+
+        #[cfg(not(feature = "sync"))]
+        let value = self.0.reason.borrow();
+        #[cfg(feature = "sync")]
+        let value = self.0.reason.read().unwrap();
+
+        value.clone()
+    }
+    /// Withdraw the request for termination, so that evaluations can run again.
+    #[inline]
+    pub fn reset(&self) {
+        #[cfg(not(feature = "sync"))]
+        let mut value = self.0.reason.borrow_mut();
+        #[cfg(feature = "sync")]
+        let mut value = self.0.reason.write().unwrap();
+
+        self.0.requested.store(false, Ordering::SeqCst);
+        *value = None;
+    }
+}
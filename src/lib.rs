@@ -62,25 +62,34 @@
 extern crate alloc;
 
 mod ast;
+mod capabilities;
+mod coverage;
+mod debugger;
+mod definitions;
 mod dynamic;
 mod engine;
 mod engine_api;
 mod engine_settings;
+mod eval_options;
 mod fn_args;
+mod fn_async;
 mod fn_call;
 mod fn_func;
 mod fn_native;
 mod fn_register;
+mod interrupt;
 mod module;
 mod optimize;
 pub mod packages;
 mod parse_error;
 mod parser;
 pub mod plugin;
+mod profiler;
 mod result;
 mod scope;
 #[cfg(feature = "serde")]
 mod serde_impl;
+mod stats;
 mod stdlib;
 mod syntax;
 mod token;
@@ -120,14 +129,27 @@ pub type FLOAT = f64;
 pub type FLOAT = f32;
 
 pub use ast::{FnAccess, ScriptFnMetadata, AST};
+pub use capabilities::Capabilities;
+pub use coverage::{CoverageReport, CoverageSummary, SourceCoverage};
+pub use debugger::{BreakPoint, CallStackFrame, Debugger, DebuggerCommand, DebuggerEvent};
+#[cfg(feature = "metadata")]
+pub use definitions::{Definitions, DefinitionsStyle};
 pub use dynamic::Dynamic;
 pub use engine::{Engine, EvalContext};
-pub use fn_native::{FnPtr, NativeCallContext, Shared};
+pub use eval_options::EvalOptions;
+pub use fn_args::{FromArgs, FuncArgs};
+#[cfg(feature = "async")]
+pub use fn_async::{EvalFuture, RegisterAsyncFn, ScopedEvalFuture};
+pub use fn_native::{CallableFunction, FnPtr, NativeCallContext, ResolvedFn, Shared};
 pub use fn_register::{RegisterFn, RegisterResultFn};
+pub use interrupt::InterruptHandle;
 pub use module::{FnNamespace, Module};
 pub use parse_error::{LexError, ParseError, ParseErrorType};
-pub use result::EvalAltResult;
+#[cfg(not(feature = "no_std"))]
+pub use profiler::{FnProfile, ProfileReport};
+pub use result::{Backtrace, BacktraceFrame, CalledFn, EvalAltResult};
 pub use scope::Scope;
+pub use stats::EvalStats;
 pub use syntax::Expression;
 pub use token::Position;
 pub use utils::ImmutableString;
@@ -193,6 +215,14 @@ pub use engine::{Imports, State as EvalState};
 #[cfg(not(feature = "unchecked"))]
 pub use engine::Limits;
 
+#[cfg(feature = "internals")]
+pub use engine::LanguageOptions;
+
+#[cfg(feature = "internals")]
+#[cfg(not(feature = "no_std"))]
+#[deprecated = "this type is volatile and may change"]
+pub use profiler::ProfilerState;
+
 #[cfg(feature = "internals")]
 #[deprecated = "this type is volatile and may change"]
 pub use module::NamespaceRef;
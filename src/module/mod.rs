@@ -2,7 +2,9 @@
 
 use crate::ast::{FnAccess, Ident};
 use crate::dynamic::Variant;
-use crate::fn_native::{shared_take_or_clone, CallableFunction, FnCallArgs, IteratorFn, SendSync};
+use crate::fn_native::{
+    shared_take_or_clone, CallableFunction, FnAny, FnCallArgs, IteratorFn, SendSync,
+};
 use crate::fn_register::by_value as cast_arg;
 use crate::stdlib::{
     any::TypeId,
@@ -10,14 +12,15 @@ use crate::stdlib::{
     collections::HashMap,
     fmt, format,
     iter::empty,
+    mem,
     num::NonZeroU64,
     num::NonZeroUsize,
-    ops::{Add, AddAssign, Deref, DerefMut},
+    ops::{Add, AddAssign, Deref, DerefMut, RangeInclusive},
     string::{String, ToString},
     vec::Vec,
 };
 use crate::token::Token;
-use crate::utils::{combine_hashes, StraightHasherBuilder};
+use crate::utils::{calc_variadic_fn_hash, combine_hashes, StraightHasherBuilder};
 use crate::{
     Dynamic, EvalAltResult, ImmutableString, NativeCallContext, Position, Shared, StaticVec,
 };
@@ -83,6 +86,24 @@ pub struct FuncInfo {
     pub param_types: StaticVec<TypeId>,
     /// Parameter names (if available).
     pub param_names: StaticVec<ImmutableString>,
+    /// Doc-comments (native Rust functions only).
+    ///
+    /// Doc-comments of script-defined functions are kept in the function definition.
+    pub comments: Vec<String>,
+    /// Number of operations added to the count for each call (native Rust functions only).
+    pub cost: u64,
+    /// Names of the [capabilities][crate::Capabilities] required to call the function (native Rust
+    /// functions only).
+    pub capabilities: StaticVec<ImmutableString>,
+    /// The native Rust function before it is wrapped to add its cost and check its capabilities,
+    /// if any.
+    base_func: Option<CallableFunction>,
+    /// Is this a variadic function accepting arguments of any type, registered via
+    /// [`set_variadic_fn`][Module::set_variadic_fn]?
+    pub variadic: bool,
+    /// Hash keys of the other versions of the same function registered for a different number of
+    /// arguments, e.g. for its trailing optional parameters.
+    linked_hashes: StaticVec<NonZeroU64>,
 }
 
 impl FuncInfo {
@@ -121,6 +142,79 @@ impl FuncInfo {
 
         sig
     }
+    /// Is the function a native Rust function that can be wrapped?
+    #[inline(always)]
+    fn is_wrappable(&self) -> bool {
+        self.func.is_pure() || self.func.is_method()
+    }
+    /// Wrap a native Rust function to add its cost and check its required capabilities before
+    /// each call.
+    ///
+    /// The original function is restored if there is no cost and no capability is required.
+    fn wrap_native(&mut self) {
+        if !self.is_wrappable() {
+            return;
+        }
+
+        let base = self.base_func.take().unwrap_or_else(|| self.func.clone());
+
+        if self.cost == 0 && self.capabilities.is_empty() {
+            self.func = base;
+            return;
+        }
+
+        let cost = self.cost;
+        let capabilities = self.capabilities.clone();
+        let name = self.name.clone();
+        let is_method = base.is_method();
+        let func = base.clone();
+
+        let wrapped: Box<FnAny> =
+            Box::new(move |context: NativeCallContext, args: &mut FnCallArgs| {
+                if let Some(capability) = capabilities.iter().find(|c| !context.is_granted(c)) {
+                    return EvalAltResult::ErrorPermissionDenied(
+                        name.clone(),
+                        capability.to_string(),
+                        Position::NONE,
+                    )
+                    .into();
+                }
+                if cost > 0 {
+                    context.add_cost(cost)?;
+                }
+
+                if func.is_plugin_fn() {
+                    func.get_plugin_fn().call(context, args)
+                } else {
+                    func.get_native_fn()(context, args)
+                }
+            });
+
+        self.func = if is_method {
+            CallableFunction::from_method(wrapped)
+        } else {
+            CallableFunction::from_pure(wrapped)
+        };
+        self.base_func = Some(base);
+    }
+}
+
+/// Take the names of the first `num_params` of `num_all_params` parameters, keeping the name of the
+/// return type, if any, after them.
+fn truncate_param_names(
+    param_names: &[ImmutableString],
+    num_all_params: usize,
+    num_params: usize,
+) -> StaticVec<ImmutableString> {
+    if param_names.len() > num_all_params {
+        param_names[..num_params]
+            .iter()
+            .chain(param_names.last())
+            .cloned()
+            .collect()
+    } else {
+        param_names.iter().take(num_params).cloned().collect()
+    }
 }
 
 /// A module which may contain variables, sub-modules, external Rust functions,
@@ -335,6 +429,20 @@ impl Module {
             .map(FuncInfo::gen_signature)
     }
 
+    /// Generate signatures for all the non-private functions in the [`Module`] that can be called
+    /// with a particular set of [capabilities][crate::Capabilities] granted.
+    #[inline(always)]
+    pub fn gen_fn_signatures_with_capabilities<'a>(
+        &'a self,
+        granted: &'a crate::Capabilities,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.functions
+            .values()
+            .filter(|FuncInfo { access, .. }| !access.is_private())
+            .filter(move |f| granted.is_all_granted(f.capabilities.iter().map(|c| c.as_str())))
+            .map(FuncInfo::gen_signature)
+    }
+
     /// Does a variable exist in the [`Module`]?
     ///
     /// # Example
@@ -443,7 +551,13 @@ impl Module {
                 params: num_params,
                 param_types: Default::default(),
                 param_names,
+                comments: Default::default(),
                 func: fn_def.into(),
+                cost: 0,
+                capabilities: Default::default(),
+                base_func: None,
+                variadic: false,
+                linked_hashes: Default::default(),
             },
         );
         self.indexed = false;
@@ -580,6 +694,24 @@ impl Module {
         }
     }
 
+    /// Get the hash key of a registered function, followed by those of the other versions of it
+    /// registered for fewer arguments, e.g. for its trailing optional parameters.
+    fn linked_fn_hashes(&self, hash_fn: NonZeroU64) -> StaticVec<NonZeroU64> {
+        let mut hashes: StaticVec<_> = Default::default();
+        hashes.push(hash_fn);
+
+        if let Some(f) = self.functions.get(&hash_fn) {
+            hashes.extend(f.linked_hashes.iter().cloned().filter(|hash| {
+                // Skip functions that have since been replaced
+                self.functions.get(hash).map_or(false, |linked| {
+                    linked.params < f.params && linked.linked_hashes.contains(&hash_fn)
+                })
+            }));
+        }
+
+        hashes
+    }
+
     /// Update the metadata (parameter names/types and return type) of a registered function.
     ///
     /// The [`NonZeroU64`] hash is calculated either by the function
@@ -594,14 +726,58 @@ impl Module {
     ///
     /// The _last entry_ in the list should be the _return type_ of the function.
     /// In other words, the number of entries should be one larger than the number of parameters.
-    #[inline(always)]
+    ///
+    /// Versions of the function registered for fewer arguments, e.g. for its trailing optional
+    /// parameters, are also updated.
+    #[inline]
     pub fn update_fn_metadata<'a>(
         &mut self,
         hash_fn: NonZeroU64,
         arg_names: impl AsRef<[&'a str]>,
     ) -> &mut Self {
-        if let Some(f) = self.functions.get_mut(&hash_fn) {
-            f.param_names = arg_names.as_ref().iter().map(|&n| n.into()).collect();
+        let arg_names: StaticVec<ImmutableString> =
+            arg_names.as_ref().iter().map(|&n| n.into()).collect();
+        let num_params = match self.functions.get(&hash_fn) {
+            Some(f) => f.params,
+            None => return self,
+        };
+
+        for hash in self.linked_fn_hashes(hash_fn) {
+            if let Some(f) = self.functions.get_mut(&hash) {
+                f.param_names = if hash == hash_fn {
+                    arg_names.clone()
+                } else {
+                    truncate_param_names(&arg_names, num_params, f.params)
+                };
+            }
+        }
+        self
+    }
+
+    /// Update the metadata (parameter names/types, return type and doc-comments) of a registered
+    /// function.
+    ///
+    /// See [`update_fn_metadata`][Module::update_fn_metadata] for the format of the parameter
+    /// names/types and return type.
+    ///
+    /// ## Doc-Comments
+    ///
+    /// Each doc-comment line should be a separate string slice, including the leading `///`.
+    #[inline]
+    pub fn update_fn_metadata_with_comments<'a>(
+        &mut self,
+        hash_fn: NonZeroU64,
+        arg_names: impl AsRef<[&'a str]>,
+        comments: impl AsRef<[&'a str]>,
+    ) -> &mut Self {
+        self.update_fn_metadata(hash_fn, arg_names);
+
+        let comments: Vec<String> = comments.as_ref().iter().map(|&c| c.into()).collect();
+
+        for hash in self.linked_fn_hashes(hash_fn) {
+            if let Some(f) = self.functions.get_mut(&hash) {
+                f.comments = comments.clone();
+            }
         }
         self
     }
@@ -611,18 +787,168 @@ impl Module {
     /// The [`NonZeroU64`] hash is calculated either by the function
     /// [`calc_native_fn_hash`][crate::calc_native_fn_hash] or the function
     /// [`calc_script_fn_hash`][crate::calc_script_fn_hash].
-    #[inline(always)]
+    ///
+    /// Versions of the function registered for fewer arguments, e.g. for its trailing optional
+    /// parameters, are also updated.
+    #[inline]
     pub fn update_fn_namespace(
         &mut self,
         hash_fn: NonZeroU64,
         namespace: FnNamespace,
     ) -> &mut Self {
-        if let Some(f) = self.functions.get_mut(&hash_fn) {
-            f.namespace = namespace;
+        for hash in self.linked_fn_hashes(hash_fn) {
+            if let Some(f) = self.functions.get_mut(&hash) {
+                f.namespace = namespace;
+            }
+        }
+        self.indexed = false;
+        self
+    }
+
+    /// Update the cost of a registered native Rust function, i.e. the number of operations added to
+    /// the count for each call, replacing any cost set previously.
+    ///
+    /// This is useful for making an expensive function count towards the
+    /// [maximum number of operations][crate::Engine::set_max_operations] in proportion to the work
+    /// it does. A cost which depends on the arguments can be added during a call via
+    /// [`NativeCallContext::add_cost`].
+    ///
+    /// The [`NonZeroU64`] hash is calculated by the function
+    /// [`calc_native_fn_hash`][crate::calc_native_fn_hash].
+    /// It is also returned by the `set_fn_XXX` calls.
+    ///
+    /// Versions of the function registered for fewer arguments, e.g. for its trailing optional
+    /// parameters, are also updated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Module;
+    ///
+    /// let mut module = Module::new();
+    /// let hash = module.set_fn_0("lookup", || Ok(42_i64));
+    /// module.update_fn_cost(hash, 100);
+    /// ```
+    #[inline]
+    pub fn update_fn_cost(&mut self, hash_fn: NonZeroU64, cost: u64) -> &mut Self {
+        for hash in self.linked_fn_hashes(hash_fn) {
+            if let Some(f) = self.functions.get_mut(&hash) {
+                if f.is_wrappable() {
+                    f.cost = cost;
+                    f.wrap_native();
+                }
+            }
+        }
+        self.indexed = false;
+        self
+    }
+    /// Update the cost of all registered native Rust functions with a particular name.
+    #[inline]
+    pub(crate) fn update_fn_cost_by_name(&mut self, name: &str, cost: u64) -> &mut Self {
+        let hashes: StaticVec<_> = self
+            .functions
+            .iter()
+            .filter(|(_, f)| f.name == name)
+            .map(|(&hash, _)| hash)
+            .collect();
+
+        hashes.into_iter().for_each(|hash| {
+            self.update_fn_cost(hash, cost);
+        });
+        self
+    }
+
+    /// Update the names of the [capabilities][crate::Capabilities] required to call a registered
+    /// native Rust function, replacing any set previously.
+    ///
+    /// When the function is called in an evaluation restricted to a set of capabilities, it fails
+    /// with [`ErrorPermissionDenied`][EvalAltResult::ErrorPermissionDenied] unless all of them are
+    /// granted.
+    ///
+    /// The [`NonZeroU64`] hash is calculated by the function
+    /// [`calc_native_fn_hash`][crate::calc_native_fn_hash].
+    /// It is also returned by the `set_fn_XXX` calls.
+    ///
+    /// Versions of the function registered for fewer arguments, e.g. for its trailing optional
+    /// parameters, are also updated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Module;
+    ///
+    /// let mut module = Module::new();
+    /// let hash = module.set_fn_1("read_file", |path: String| Ok(path.len() as i64));
+    /// module.update_fn_capabilities(hash, &["fs.read"]);
+    /// ```
+    #[inline]
+    pub fn update_fn_capabilities(
+        &mut self,
+        hash_fn: NonZeroU64,
+        capabilities: &[&str],
+    ) -> &mut Self {
+        for hash in self.linked_fn_hashes(hash_fn) {
+            if let Some(f) = self.functions.get_mut(&hash) {
+                if f.is_wrappable() {
+                    f.capabilities = capabilities.iter().map(|&c| c.into()).collect();
+                    f.wrap_native();
+                }
+            }
         }
         self.indexed = false;
         self
     }
+    /// Update the names of the capabilities required by all registered native Rust functions with
+    /// a particular name.
+    #[inline]
+    pub(crate) fn update_fn_capabilities_by_name(
+        &mut self,
+        name: &str,
+        capabilities: &[&str],
+    ) -> &mut Self {
+        let hashes: StaticVec<_> = self
+            .functions
+            .iter()
+            .filter(|(_, f)| f.name == name)
+            .map(|(&hash, _)| hash)
+            .collect();
+
+        hashes.into_iter().for_each(|hash| {
+            self.update_fn_capabilities(hash, capabilities);
+        });
+        self
+    }
+    /// Add to the names of the [capabilities][crate::Capabilities] required to call each native
+    /// Rust function currently in the [`Module`].
+    ///
+    /// Functions set into the [`Module`] afterwards are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Module;
+    ///
+    /// let mut module = Module::new();
+    /// module.set_fn_0("connect", || Ok(true));
+    /// module.set_fn_0("disconnect", || Ok(()));
+    /// module.set_capabilities(&["net"]);
+    /// ```
+    #[inline]
+    pub fn set_capabilities(&mut self, capabilities: &[&str]) -> &mut Self {
+        self.functions
+            .values_mut()
+            .filter(|f| f.is_wrappable())
+            .for_each(|f| {
+                capabilities.iter().for_each(|&c| {
+                    if !f.capabilities.iter().any(|x| x.as_str() == c) {
+                        f.capabilities.push(c.into());
+                    }
+                });
+                f.wrap_native();
+            });
+        self.indexed = false;
+        self
+    }
 
     /// Set a Rust function into the [`Module`], returning a hash key.
     ///
@@ -631,7 +957,7 @@ impl Module {
     /// # WARNING - Low Level API
     ///
     /// This function is very low level.
-    #[inline]
+    #[inline(always)]
     pub fn set_fn(
         &mut self,
         name: impl Into<String>,
@@ -640,11 +966,45 @@ impl Module {
         arg_names: Option<&[&str]>,
         arg_types: &[TypeId],
         func: CallableFunction,
+    ) -> NonZeroU64 {
+        self.set_fn_with_comments(name, namespace, access, arg_names, arg_types, &[], func)
+    }
+
+    /// Set a Rust function, together with its doc-comments, into the [`Module`], returning a hash key.
+    ///
+    /// If there is an existing Rust function of the same hash, it is replaced.
+    ///
+    /// Each doc-comment line should be a separate string slice, including the leading `///`.
+    ///
+    /// If the function is a plugin function with trailing optional parameters, it is registered
+    /// once for each number of arguments it accepts. The hash key of the version taking all
+    /// parameters is returned. Updating it, e.g. via
+    /// [`update_fn_capabilities`][Module::update_fn_capabilities], also updates the versions taking
+    /// fewer parameters.
+    ///
+    /// # WARNING - Low Level API
+    ///
+    /// This function is very low level.
+    #[inline]
+    pub fn set_fn_with_comments(
+        &mut self,
+        name: impl Into<String>,
+        namespace: FnNamespace,
+        access: FnAccess,
+        arg_names: Option<&[&str]>,
+        arg_types: &[TypeId],
+        comments: &[&str],
+        func: CallableFunction,
     ) -> NonZeroU64 {
         let name = name.into();
 
-        let hash_fn =
-            crate::calc_native_fn_hash(empty(), &name, arg_types.iter().cloned()).unwrap();
+        let num_optional = if func.is_plugin_fn() {
+            func.get_plugin_fn()
+                .num_optional_params()
+                .min(arg_types.len())
+        } else {
+            0
+        };
 
         let param_types = arg_types
             .into_iter()
@@ -658,26 +1018,54 @@ impl Module {
             })
             .collect::<StaticVec<_>>();
 
-        self.functions.insert(
-            hash_fn,
-            FuncInfo {
-                name,
-                namespace,
-                access,
-                params: param_types.len(),
-                param_types,
-                param_names: if let Some(p) = arg_names {
-                    p.iter().map(|&v| v.into()).collect()
-                } else {
-                    Default::default()
+        let param_names: StaticVec<ImmutableString> = if let Some(p) = arg_names {
+            p.iter().map(|&v| v.into()).collect()
+        } else {
+            Default::default()
+        };
+
+        let comments: Vec<String> = comments.iter().map(|&c| c.into()).collect();
+
+        let hashes: StaticVec<_> = ((arg_types.len() - num_optional)..=arg_types.len())
+            .map(|num_params| {
+                let hash = crate::calc_native_fn_hash(
+                    empty(),
+                    &name,
+                    arg_types[..num_params].iter().cloned(),
+                )
+                .unwrap();
+                (num_params, hash)
+            })
+            .collect();
+
+        for &(num_params, hash) in hashes.iter() {
+            self.functions.insert(
+                hash,
+                FuncInfo {
+                    name: name.clone(),
+                    namespace,
+                    access,
+                    params: num_params,
+                    param_types: param_types[..num_params].iter().cloned().collect(),
+                    param_names: truncate_param_names(&param_names, arg_types.len(), num_params),
+                    comments: comments.clone(),
+                    func: func.clone(),
+                    cost: 0,
+                    capabilities: Default::default(),
+                    base_func: None,
+                    variadic: false,
+                    linked_hashes: hashes
+                        .iter()
+                        .map(|&(_, h)| h)
+                        .filter(|&h| h != hash)
+                        .collect(),
                 },
-                func: func.into(),
-            },
-        );
+            );
+        }
 
         self.indexed = false;
 
-        hash_fn
+        hashes.last().unwrap().1
     }
 
     /// Set a Rust function taking a reference to the scripting [`Engine`][crate::Engine],
@@ -768,6 +1156,89 @@ impl Module {
         )
     }
 
+    /// Set a variadic Rust function into the [`Module`], returning a hash key.
+    ///
+    /// The function accepts any number of arguments within `arity`, each of any type.
+    /// It is registered once for each number of arguments, with all parameters of type [`Dynamic`],
+    /// and is only called when no other function matches the argument types.
+    /// The hash key of the version taking the most arguments is returned. Updating it, e.g. via
+    /// [`update_fn_capabilities`][Module::update_fn_capabilities], also updates the versions taking
+    /// fewer arguments.
+    ///
+    /// If there is a similar existing Rust function, it is replaced.
+    ///
+    /// # Function Arguments
+    ///
+    /// Arguments are passed in as a mutable slice of [`Dynamic`] values, which are owned by the
+    /// function.  Use [`FromArgs`][crate::FromArgs] to convert them into a tuple of typed values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::{Module, FnNamespace, FnAccess};
+    ///
+    /// let mut module = Module::new();
+    /// let hash = module.set_variadic_fn("sum", FnNamespace::Internal, FnAccess::Public, 1..=10,
+    ///                 |_, args| {
+    ///                     Ok(args.iter().map(|v| v.as_int().unwrap_or(0)).sum::<i64>())
+    ///                 });
+    ///
+    /// assert!(module.contains_fn(hash, true));
+    /// ```
+    #[inline]
+    pub fn set_variadic_fn<T: Variant + Clone>(
+        &mut self,
+        name: impl Into<String>,
+        namespace: FnNamespace,
+        access: FnAccess,
+        arity: RangeInclusive<usize>,
+        func: impl Fn(NativeCallContext, &mut [Dynamic]) -> Result<T, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> NonZeroU64 {
+        let name = name.into();
+
+        let f = move |ctx: NativeCallContext, args: &mut FnCallArgs| {
+            let mut args: StaticVec<_> = args.iter_mut().map(|v| mem::take(*v)).collect();
+            func(ctx, &mut args).map(Dynamic::from)
+        };
+        let func = CallableFunction::from_pure(Box::new(f));
+
+        let (min_args, max_args) = arity.into_inner();
+        let hashes: StaticVec<_> = (min_args..=max_args.max(min_args))
+            .map(|num_params| (num_params, calc_variadic_fn_hash(&name, num_params)))
+            .collect();
+
+        for &(num_params, hash) in hashes.iter() {
+            self.functions.insert(
+                hash,
+                FuncInfo {
+                    name: name.clone(),
+                    namespace,
+                    access,
+                    params: num_params,
+                    param_types: (0..num_params).map(|_| TypeId::of::<Dynamic>()).collect(),
+                    param_names: Default::default(),
+                    comments: Default::default(),
+                    func: func.clone(),
+                    cost: 0,
+                    capabilities: Default::default(),
+                    base_func: None,
+                    variadic: true,
+                    linked_hashes: hashes
+                        .iter()
+                        .map(|&(_, h)| h)
+                        .filter(|&h| h != hash)
+                        .collect(),
+                },
+            );
+        }
+
+        self.indexed = false;
+
+        hashes.last().unwrap().1
+    }
+
     /// Set a Rust function taking no parameters into the [`Module`], returning a hash key.
     ///
     /// If there is a similar existing Rust function, it is replaced.
@@ -1630,8 +2101,10 @@ impl Module {
     }
 
     /// Get an iterator to the functions in the [`Module`].
-    #[cfg(not(feature = "no_optimize"))]
-    #[cfg(not(feature = "no_function"))]
+    #[cfg(any(
+        feature = "metadata",
+        all(not(feature = "no_optimize"), not(feature = "no_function"))
+    ))]
     #[inline(always)]
     pub(crate) fn iter_fn(&self) -> impl Iterator<Item = &FuncInfo> {
         self.functions.values()
@@ -1743,7 +2216,7 @@ impl Module {
         let orig_mods_len = mods.len();
 
         // Run the script
-        engine.eval_ast_with_scope_raw(&mut scope, &mut mods, &ast, 0)?;
+        engine.eval_ast_with_scope_raw(&mut scope, &mut mods, &mut Default::default(), &ast, 0)?;
 
         // Create new module
         let mut module = Module::new();
@@ -1836,6 +2309,7 @@ impl Module {
                             params,
                             param_types,
                             func,
+                            variadic,
                             ..
                         },
                     )| {
@@ -1857,12 +2331,13 @@ impl Module {
                             //    i.e. qualifiers + function name + number of arguments.
                             // 2) Calculate a second hash with no qualifiers, empty function name,
                             //    and the actual list of argument [`TypeId`]'.s
-                            let hash_fn_args = crate::calc_native_fn_hash(
-                                empty(),
-                                "",
-                                param_types.iter().cloned(),
-                            )
-                            .unwrap();
+                            //    (or, for variadic functions, just the number of arguments)
+                            let hash_fn_args = if *variadic {
+                                calc_variadic_fn_hash("", *params)
+                            } else {
+                                crate::calc_native_fn_hash(empty(), "", param_types.iter().cloned())
+                                    .unwrap()
+                            };
                             // 3) The two hashes are combined.
                             let hash_qualified_fn =
                                 combine_hashes(hash_qualified_script, hash_fn_args);
@@ -1,5 +1,5 @@
 use crate::stdlib::{boxed::Box, ops::AddAssign, vec::Vec};
-use crate::{Engine, EvalAltResult, Module, ModuleResolver, Position, Shared};
+use crate::{Engine, EvalAltResult, Module, ModuleResolver, Position, Shared, AST};
 
 /// [Module] resolution service that holds a collection of [module][Module] resolves,
 /// to be searched in sequential order.
@@ -127,6 +127,25 @@ impl ModuleResolver for ModuleResolversCollection {
 
         EvalAltResult::ErrorModuleNotFound(path.into(), pos).into()
     }
+
+    fn resolve_ast(
+        &self,
+        engine: &Engine,
+        path: &str,
+        pos: Position,
+    ) -> Option<Result<AST, Box<EvalAltResult>>> {
+        for resolver in self.0.iter() {
+            match resolver.resolve_ast(engine, path, pos) {
+                Some(Err(err)) if matches!(*err, EvalAltResult::ErrorModuleNotFound(_, _)) => {
+                    continue
+                }
+                Some(result) => return Some(result),
+                None => continue,
+            }
+        }
+
+        None
+    }
 }
 
 impl<M: ModuleResolver + 'static> AddAssign<M> for ModuleResolversCollection {
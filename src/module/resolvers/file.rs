@@ -5,7 +5,7 @@ use crate::stdlib::{
     path::{Path, PathBuf},
     string::String,
 };
-use crate::{Engine, EvalAltResult, Module, ModuleResolver, Position, Shared};
+use crate::{Engine, EvalAltResult, Module, ModuleResolver, Position, Shared, AST};
 
 /// [Module] resolution service that loads [module][Module] script files from the file system.
 ///
@@ -174,6 +174,15 @@ impl FileModuleResolver {
             .remove_entry(path.as_ref())
             .map(|(_, v)| v);
     }
+
+    /// Construct the full file path of the script file for a module path.
+    #[inline(always)]
+    pub fn get_file_path(&self, path: &str) -> PathBuf {
+        let mut file_path = self.base_path.clone();
+        file_path.push(path);
+        file_path.set_extension(&self.extension); // Force extension
+        file_path
+    }
 }
 
 impl ModuleResolver for FileModuleResolver {
@@ -184,9 +193,7 @@ impl ModuleResolver for FileModuleResolver {
         pos: Position,
     ) -> Result<Shared<Module>, Box<EvalAltResult>> {
         // Construct the script file path
-        let mut file_path = self.base_path.clone();
-        file_path.push(path);
-        file_path.set_extension(&self.extension); // Force extension
+        let file_path = self.get_file_path(path);
 
         // See if it is cached
         {
@@ -227,4 +234,31 @@ impl ModuleResolver for FileModuleResolver {
 
         Ok(m)
     }
+
+    /// Load and compile the script file of a module, without evaluating it.
+    ///
+    /// The source of the returned [`AST`] is set to the full path of the script file.
+    fn resolve_ast(
+        &self,
+        engine: &Engine,
+        path: &str,
+        pos: Position,
+    ) -> Option<Result<AST, Box<EvalAltResult>>> {
+        let file_path = self.get_file_path(path);
+
+        Some(
+            engine
+                .compile_file(file_path.clone())
+                .map(|mut ast| {
+                    ast.set_source(Some(file_path.to_string_lossy().as_ref()));
+                    ast
+                })
+                .map_err(|err| match *err {
+                    EvalAltResult::ErrorSystem(_, err) if err.is::<IoError>() => {
+                        Box::new(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos))
+                    }
+                    _ => Box::new(EvalAltResult::ErrorInModule(path.to_string(), err, pos)),
+                }),
+        )
+    }
 }
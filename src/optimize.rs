@@ -841,7 +841,8 @@ pub fn optimize_into_ast(
     _functions: Vec<ScriptFnDef>,
     level: OptimizationLevel,
 ) -> AST {
-    let level = if cfg!(feature = "no_optimize") {
+    // Coverage is reported against the unoptimized AST
+    let level = if cfg!(feature = "no_optimize") || engine.coverage.is_some() {
         OptimizationLevel::None
     } else {
         level
@@ -868,6 +869,7 @@ pub fn optimize_into_ast(
                     #[cfg(not(feature = "no_module"))]
                     mods: Default::default(),
                     comments: Default::default(),
+                    pos: fn_def.pos,
                 })
                 .for_each(|fn_def| {
                     lib2.set_script_fn(fn_def);
@@ -197,6 +197,8 @@ mod array_functions {
         list: &mut Array,
         mapper: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut array = Array::with_capacity(max(TYPICAL_ARRAY_SIZE, list.len()));
 
         for (i, item) in list.iter().enumerate() {
@@ -213,7 +215,7 @@ mod array_functions {
                     })
                     .map_err(|err| {
                         Box::new(EvalAltResult::ErrorInFunctionCall(
-                            "map".to_string(),
+                            "map".into(),
                             ctx.source().unwrap_or("").to_string(),
                             err,
                             Position::NONE,
@@ -230,6 +232,8 @@ mod array_functions {
         list: &mut Array,
         filter: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut array = Array::with_capacity(max(TYPICAL_ARRAY_SIZE, list.len()));
 
         for (i, item) in list.iter().enumerate() {
@@ -245,7 +249,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "filter".to_string(),
+                        "filter".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -266,6 +270,8 @@ mod array_functions {
         list: &mut Array,
         filter: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         for (i, item) in list.iter().enumerate() {
             if filter
                 .call_dynamic(ctx, None, [item.clone()])
@@ -279,7 +285,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "index_of".to_string(),
+                        "index_of".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -300,6 +306,8 @@ mod array_functions {
         list: &mut Array,
         filter: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         for (i, item) in list.iter().enumerate() {
             if filter
                 .call_dynamic(ctx, None, [item.clone()])
@@ -313,7 +321,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "some".to_string(),
+                        "some".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -334,6 +342,8 @@ mod array_functions {
         list: &mut Array,
         filter: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         for (i, item) in list.iter().enumerate() {
             if !filter
                 .call_dynamic(ctx, None, [item.clone()])
@@ -347,7 +357,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "all".to_string(),
+                        "all".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -368,6 +378,8 @@ mod array_functions {
         list: &mut Array,
         reducer: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut result: Dynamic = Dynamic::UNIT;
 
         for (i, item) in list.iter().enumerate() {
@@ -383,7 +395,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "reduce".to_string(),
+                        "reduce".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -400,9 +412,11 @@ mod array_functions {
         reducer: FnPtr,
         initial: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut result = initial.call_dynamic(ctx, None, []).map_err(|err| {
             Box::new(EvalAltResult::ErrorInFunctionCall(
-                "reduce".to_string(),
+                "reduce".into(),
                 ctx.source().unwrap_or("").to_string(),
                 err,
                 Position::NONE,
@@ -422,7 +436,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "reduce".to_string(),
+                        "reduce".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -438,6 +452,8 @@ mod array_functions {
         list: &mut Array,
         reducer: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut result: Dynamic = Dynamic::UNIT;
 
         for (i, item) in list.iter().enumerate().rev() {
@@ -453,7 +469,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "reduce_rev".to_string(),
+                        "reduce_rev".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -470,9 +486,11 @@ mod array_functions {
         reducer: FnPtr,
         initial: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut result = initial.call_dynamic(ctx, None, []).map_err(|err| {
             Box::new(EvalAltResult::ErrorInFunctionCall(
-                "reduce_rev".to_string(),
+                "reduce_rev".into(),
                 ctx.source().unwrap_or("").to_string(),
                 err,
                 Position::NONE,
@@ -492,7 +510,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "reduce_rev".to_string(),
+                        "reduce_rev".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -508,6 +526,10 @@ mod array_functions {
         list: &mut Array,
         comparer: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        // Sorting takes in the order of n·log(n) comparisons
+        let len = list.len() as u64;
+        ctx.add_cost(len * (64 - len.leading_zeros()) as u64)?;
+
         list.sort_by(|x, y| {
             comparer
                 .call_dynamic(ctx, None, [x.clone(), y.clone()])
@@ -544,6 +566,8 @@ mod array_functions {
         list: &mut Array,
         filter: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut drained = Array::with_capacity(max(TYPICAL_ARRAY_SIZE, list.len()));
 
         let mut i = list.len();
@@ -563,7 +587,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "drain".to_string(),
+                        "drain".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -604,6 +628,8 @@ mod array_functions {
         list: &mut Array,
         filter: FnPtr,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(list.len() as u64)?;
+
         let mut drained = Array::with_capacity(max(TYPICAL_ARRAY_SIZE, list.len()));
 
         let mut i = list.len();
@@ -623,7 +649,7 @@ mod array_functions {
                 })
                 .map_err(|err| {
                     Box::new(EvalAltResult::ErrorInFunctionCall(
-                        "retain".to_string(),
+                        "retain".into(),
                         ctx.source().unwrap_or("").to_string(),
                         err,
                         Position::NONE,
@@ -667,6 +693,8 @@ mod array_functions {
         arr1: &mut Array,
         mut arr2: Array,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
+        ctx.add_cost(arr1.len() as u64)?;
+
         if arr1.len() != arr2.len() {
             return Ok(false.into());
         }
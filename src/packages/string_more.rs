@@ -117,13 +117,12 @@ mod string_functions {
     }
 
     #[rhai_fn(name = "index_of")]
-    pub fn index_of_char_starting_from(s: &str, ch: char, start: INT) -> INT {
-        let start = if start < 0 {
-            0
-        } else if start as usize >= s.chars().count() {
-            return -1 as INT;
-        } else {
-            s.chars().take(start as usize).collect::<String>().len()
+    pub fn index_of_char(s: &str, ch: char, start: Option<INT>) -> INT {
+        let start = match start {
+            None => 0,
+            Some(start) if start < 0 => 0,
+            Some(start) if start as usize >= s.chars().count() => return -1 as INT,
+            Some(start) => s.chars().take(start as usize).collect::<String>().len(),
         };
 
         s[start..]
@@ -131,20 +130,12 @@ mod string_functions {
             .map(|index| s[0..start + index].chars().count() as INT)
             .unwrap_or(-1 as INT)
     }
-    #[rhai_fn(name = "index_of")]
-    pub fn index_of_char(s: &str, ch: char) -> INT {
-        s.find(ch)
-            .map(|index| s[0..index].chars().count() as INT)
-            .unwrap_or(-1 as INT)
-    }
-    #[rhai_fn(name = "index_of")]
-    pub fn index_of_string_starting_from(s: &str, find: ImmutableString, start: INT) -> INT {
-        let start = if start < 0 {
-            0
-        } else if start as usize >= s.chars().count() {
-            return -1 as INT;
-        } else {
-            s.chars().take(start as usize).collect::<String>().len()
+    pub fn index_of(s: &str, find: ImmutableString, start: Option<INT>) -> INT {
+        let start = match start {
+            None => 0,
+            Some(start) if start < 0 => 0,
+            Some(start) if start as usize >= s.chars().count() => return -1 as INT,
+            Some(start) => s.chars().take(start as usize).collect::<String>().len(),
         };
 
         s[start..]
@@ -152,14 +143,9 @@ mod string_functions {
             .map(|index| s[0..start + index].chars().count() as INT)
             .unwrap_or(-1 as INT)
     }
-    #[rhai_fn(name = "index_of")]
-    pub fn index_of(s: &str, find: ImmutableString) -> INT {
-        s.find(find.as_str())
-            .map(|index| s[0..index].chars().count() as INT)
-            .unwrap_or(-1 as INT)
-    }
 
-    pub fn sub_string(s: &str, start: INT, len: INT) -> ImmutableString {
+    pub fn sub_string(s: &str, start: INT, len: Option<INT>) -> ImmutableString {
+        let len = len.unwrap_or(s.len() as INT);
         let offset = if s.is_empty() || len <= 0 {
             return "".to_string().into();
         } else if start < 0 {
@@ -186,14 +172,9 @@ mod string_functions {
             .collect::<String>()
             .into()
     }
-    #[rhai_fn(name = "sub_string")]
-    pub fn sub_string_starting_from(s: &str, start: INT) -> ImmutableString {
-        let len = s.len() as INT;
-        sub_string(s, start, len)
-    }
 
-    #[rhai_fn(name = "crop")]
-    pub fn crop(s: &mut ImmutableString, start: INT, len: INT) {
+    pub fn crop(s: &mut ImmutableString, start: INT, len: Option<INT>) {
+        let len = len.unwrap_or(s.len() as INT);
         let offset = if s.is_empty() || len <= 0 {
             s.make_mut().clear();
             return;
@@ -218,10 +199,6 @@ mod string_functions {
         copy.clear();
         copy.extend(chars.iter().skip(offset).take(len));
     }
-    #[rhai_fn(name = "crop")]
-    pub fn crop_string_starting_from(s: &mut ImmutableString, start: INT) {
-        crop(s, start, s.len() as INT);
-    }
 
     #[rhai_fn(name = "replace")]
     pub fn replace(s: &mut ImmutableString, find: ImmutableString, sub: ImmutableString) {
@@ -120,6 +120,11 @@ pub enum ParseErrorType {
     PropertyExpected,
     /// Missing a variable name after the `let`, `const`, `for` or `catch` keywords.
     VariableExpected,
+    /// A variable is defined with the same name as another variable already defined, when
+    /// variable shadowing is disallowed. Wrapped value is the variable name.
+    VariableExists(String),
+    /// A variable is not defined, under strict variables mode. Wrapped value is the variable name.
+    VariableUndefined(String),
     /// An identifier is a reserved keyword.
     Reserved(String),
     /// Missing an expression. Wrapped value is the expression type.
@@ -168,6 +173,9 @@ pub enum ParseErrorType {
     LiteralTooLarge(String, usize),
     /// Break statement not inside a loop.
     LoopBreak,
+    /// A language feature is used that is disallowed by the [`Engine`][crate::Engine].
+    /// Wrapped value is the name of the feature.
+    FeatureDisabled(String),
 }
 
 impl ParseErrorType {
@@ -191,6 +199,8 @@ impl ParseErrorType {
             Self::DuplicatedSwitchCase => "Duplicated switch case",
             Self::PropertyExpected => "Expecting name of a property",
             Self::VariableExpected => "Expecting name of a variable",
+            Self::VariableExists(_) => "Variable already defined",
+            Self::VariableUndefined(_) => "Undefined variable",
             Self::Reserved(_) => "Invalid use of reserved keyword",
             Self::ExprExpected(_) => "Expecting an expression",
             Self::FnMissingName => "Expecting function name in function declaration",
@@ -204,7 +214,8 @@ impl ParseErrorType {
             Self::AssignmentToInvalidLHS(_) => "Expression cannot be assigned to",
             Self::ExprTooDeep => "Expression exceeds maximum complexity",
             Self::LiteralTooLarge(_, _) => "Literal exceeds maximum limit",
-            Self::LoopBreak => "Break statement should only be used inside a loop"
+            Self::LoopBreak => "Break statement should only be used inside a loop",
+            Self::FeatureDisabled(_) => "Language feature is disabled"
         }
     }
 }
@@ -255,6 +266,11 @@ impl fmt::Display for ParseErrorType {
 
             Self::Reserved(s) => write!(f, "'{}' is a reserved keyword", s),
 
+            Self::VariableExists(s) => write!(f, "Variable '{}' is already defined", s),
+            Self::VariableUndefined(s) => write!(f, "Variable '{}' is not defined", s),
+
+            Self::FeatureDisabled(s) => write!(f, "Use of {} is disabled", s),
+
             _ => f.write_str(self.desc()),
         }
     }
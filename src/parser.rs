@@ -2,7 +2,7 @@
 
 use crate::ast::{BinaryExpr, CustomExpr, Expr, FnCallExpr, Ident, ReturnType, ScriptFnDef, Stmt};
 use crate::dynamic::{AccessMode, Union};
-use crate::engine::KEYWORD_THIS;
+use crate::engine::{KEYWORD_EVAL, KEYWORD_THIS};
 use crate::module::NamespaceRef;
 use crate::optimize::optimize_into_ast;
 use crate::optimize::OptimizationLevel;
@@ -41,6 +41,8 @@ type FunctionsLib = HashMap<NonZeroU64, ScriptFnDef, StraightHasherBuilder>;
 struct ParseState<'e> {
     /// Reference to the scripting [`Engine`].
     engine: &'e Engine,
+    /// The [`Scope`] that the script is compiled with, if at global level.
+    scope: Option<&'e Scope<'e>>,
     /// Hash that uniquely identifies a script.
     script_hash: u64,
     /// Interned strings.
@@ -58,6 +60,9 @@ struct ParseState<'e> {
     /// All consequent calls to `access_var` will not be affected
     #[cfg(not(feature = "no_closure"))]
     allow_capture: bool,
+    /// Are variables not found captured from the enclosing scope, as in closures?
+    #[cfg(not(feature = "no_closure"))]
+    is_closure: bool,
     /// Encapsulates a local stack with imported [module][crate::Module] names.
     #[cfg(not(feature = "no_module"))]
     modules: StaticVec<ImmutableString>,
@@ -83,6 +88,7 @@ impl<'e> ParseState<'e> {
     ) -> Self {
         Self {
             engine,
+            scope: None,
             script_hash,
             #[cfg(not(feature = "unchecked"))]
             max_expr_depth,
@@ -93,6 +99,8 @@ impl<'e> ParseState<'e> {
             externals: Default::default(),
             #[cfg(not(feature = "no_closure"))]
             allow_capture: true,
+            #[cfg(not(feature = "no_closure"))]
+            is_closure: false,
             strings: HashMap::with_capacity(64),
             stack: Vec::with_capacity(16),
             entry_stack_len: 0,
@@ -145,6 +153,54 @@ impl<'e> ParseState<'e> {
         }
     }
 
+    /// Find explicitly declared variable by name in the [`ParseState`], searching in reverse order,
+    /// without capturing it as an external variable.
+    ///
+    /// Returns the offset to be deducted from `Stack::len`,
+    /// i.e. the top element of the [`ParseState`] is offset 1.
+    ///
+    /// Returns `None` when the variable name is not found in the `stack`.
+    #[inline]
+    fn find_var(&self, name: &str) -> Option<NonZeroUsize> {
+        self.stack
+            .iter()
+            .rev()
+            // Do not go beyond empty variable names
+            .take_while(|(n, _)| !n.is_empty())
+            .enumerate()
+            .find(|(_, (n, _))| *n == name)
+            .and_then(|(i, _)| NonZeroUsize::new(i + 1))
+    }
+
+    /// Under strict variables mode, make sure that a variable not found in the [`ParseState`] is
+    /// defined elsewhere, i.e. in the [`Scope`] that the script is compiled with, or as an external
+    /// variable of the [`Engine`].
+    ///
+    /// Variables in closures are checked when they are captured from the enclosing scope.
+    fn ensure_var_defined(&self, name: &str, pos: Position) -> Result<(), ParseError> {
+        if !self.engine.options.strict_var {
+            return Ok(());
+        }
+
+        #[cfg(not(feature = "no_closure"))]
+        if self.is_closure {
+            return Ok(());
+        }
+
+        // Variables defined by custom syntax cannot be tracked
+        if self
+            .stack
+            .iter()
+            .any(|(n, _)| n.is_empty() || n.as_str() == name)
+            || self.scope.map_or(false, |scope| scope.contains(name))
+            || self.engine.external_vars.contains(name)
+        {
+            Ok(())
+        } else {
+            Err(PERR::VariableUndefined(name.to_string()).into_err(pos))
+        }
+    }
+
     /// Find a module by name in the [`ParseState`], searching in reverse.
     ///
     /// Returns the offset to be deducted from `Stack::len`,
@@ -317,6 +373,10 @@ fn parse_fn_call(
     #[cfg(not(feature = "unchecked"))]
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
 
+    if namespace.is_none() && id == KEYWORD_EVAL && !state.engine.options.allow_eval {
+        return Err(PERR::FeatureDisabled(format!("'{}'", id)).into_err(settings.pos));
+    }
+
     let (token, token_pos) = input.peek().unwrap();
 
     let mut args = StaticVec::new();
@@ -929,6 +989,7 @@ fn parse_primary(
     input: &mut TokenStream,
     state: &mut ParseState,
     lib: &mut FunctionsLib,
+    is_property: bool,
     mut settings: ParseSettings,
 ) -> Result<Expr, ParseError> {
     #[cfg(not(feature = "unchecked"))]
@@ -972,17 +1033,29 @@ fn parse_primary(
         Token::LeftParen => parse_paren_expr(input, state, lib, settings.level_up())?,
 
         // If statement is allowed to act as expressions
+        Token::If if settings.allow_if_expr && !state.engine.options.allow_if_expr => {
+            return Err(PERR::FeatureDisabled("if-expressions".into()).into_err(settings.pos))
+        }
         Token::If if settings.allow_if_expr => Expr::Stmt(
             Box::new(vec![parse_if(input, state, lib, settings.level_up())?].into()),
             settings.pos,
         ),
         // Switch statement is allowed to act as expressions
+        Token::Switch if settings.allow_switch_expr && !state.engine.options.allow_switch_expr => {
+            return Err(PERR::FeatureDisabled("switch-expressions".into()).into_err(settings.pos))
+        }
         Token::Switch if settings.allow_switch_expr => Expr::Stmt(
             Box::new(vec![parse_switch(input, state, lib, settings.level_up())?].into()),
             settings.pos,
         ),
         // | ...
         #[cfg(not(feature = "no_function"))]
+        Token::Pipe | Token::Or
+            if settings.allow_anonymous_fn && !state.engine.options.allow_anonymous_fn =>
+        {
+            return Err(PERR::FeatureDisabled("anonymous functions".into()).into_err(settings.pos))
+        }
+        #[cfg(not(feature = "no_function"))]
         Token::Pipe | Token::Or if settings.allow_anonymous_fn => {
             let mut new_state = ParseState::new(
                 state.engine,
@@ -993,6 +1066,11 @@ fn parse_primary(
                 state.max_function_expr_depth,
             );
 
+            #[cfg(not(feature = "no_closure"))]
+            {
+                new_state.is_closure = true;
+            }
+
             let settings = ParseSettings {
                 allow_if_expr: true,
                 allow_switch_expr: true,
@@ -1008,9 +1086,13 @@ fn parse_primary(
             let (expr, func) = parse_anon_fn(input, &mut new_state, lib, settings)?;
 
             #[cfg(not(feature = "no_closure"))]
-            new_state.externals.iter().for_each(|(closure, pos)| {
-                state.access_var(closure, *pos);
-            });
+            new_state.externals.iter().try_for_each(|(closure, pos)| {
+                if state.access_var(closure, *pos).is_none() {
+                    state.ensure_var_defined(closure, *pos)
+                } else {
+                    Ok(())
+                }
+            })?;
 
             lib.insert(
                 // Qualifiers (none) + function name + number of arguments.
@@ -1067,6 +1149,11 @@ fn parse_primary(
                 // Normal variable access
                 _ => {
                     let index = state.access_var(&s, settings.pos);
+
+                    if !is_property && index.is_none() {
+                        state.ensure_var_defined(&s, settings.pos)?;
+                    }
+
                     let var_name_def = Ident {
                         name: state.get_interned_string(s),
                         pos: settings.pos,
@@ -1103,13 +1190,19 @@ fn parse_primary(
                     if !settings.is_function_scope {
                         let msg = format!("'{}' can only be used in functions", s);
                         return Err(LexError::ImproperSymbol(s, msg).into_err(settings.pos));
-                    } else {
-                        let var_name_def = Ident {
-                            name: state.get_interned_string(s),
-                            pos: settings.pos,
-                        };
-                        Expr::Variable(Box::new((None, None, var_name_def)))
                     }
+
+                    #[cfg(not(feature = "no_function"))]
+                    if !state.engine.options.allow_implicit_this {
+                        let feature = format!("'{}'", s);
+                        return Err(PERR::FeatureDisabled(feature).into_err(settings.pos));
+                    }
+
+                    let var_name_def = Ident {
+                        name: state.get_interned_string(s),
+                        pos: settings.pos,
+                    };
+                    Expr::Variable(Box::new((None, None, var_name_def)))
                 }
                 _ if is_valid_identifier(s.chars()) => {
                     return Err(PERR::Reserved(s).into_err(settings.pos));
@@ -1222,7 +1315,7 @@ fn parse_primary(
                     state.allow_capture = false;
                 }
 
-                let rhs = parse_primary(input, state, lib, settings.level_up())?;
+                let rhs = parse_primary(input, state, lib, true, settings.level_up())?;
                 make_dot_expr(state, expr, rhs, tail_pos)?
             }
             // Unknown postfix operator
@@ -1341,7 +1434,7 @@ fn parse_unary(
         Token::Bang => {
             let pos = eat_token(input, Token::Bang);
             let mut args = StaticVec::new();
-            let expr = parse_primary(input, state, lib, settings.level_up())?;
+            let expr = parse_primary(input, state, lib, false, settings.level_up())?;
             args.push(expr);
 
             let op = "!";
@@ -1359,7 +1452,7 @@ fn parse_unary(
         // <EOF>
         Token::EOF => Err(PERR::UnexpectedEOF.into_err(settings.pos)),
         // All other tokens
-        _ => parse_primary(input, state, lib, settings.level_up()),
+        _ => parse_primary(input, state, lib, false, settings.level_up()),
     }
 }
 
@@ -2203,9 +2296,9 @@ fn parse_for(
     settings.pos = eat_token(input, Token::For);
 
     // for name ...
-    let name = match input.next().unwrap() {
+    let (name, name_pos) = match input.next().unwrap() {
         // Variable name
-        (Token::Identifier(s), _) => s,
+        (Token::Identifier(s), pos) => (s, pos),
         // Reserved keyword
         (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
             return Err(PERR::Reserved(s).into_err(pos));
@@ -2216,6 +2309,10 @@ fn parse_for(
         (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
     };
 
+    if !state.engine.options.allow_shadowing && state.find_var(&name).is_some() {
+        return Err(PERR::VariableExists(name).into_err(name_pos));
+    }
+
     // for name in ...
     match input.next().unwrap() {
         (Token::In, _) => (),
@@ -2269,6 +2366,10 @@ fn parse_let(
         (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
     };
 
+    if !state.engine.options.allow_shadowing && state.find_var(&name).is_some() {
+        return Err(PERR::VariableExists(name).into_err(pos));
+    }
+
     // let name = ...
     let expr = if match_token(input, Token::Equals).0 {
         // let name = expr
@@ -2584,6 +2685,11 @@ fn parse_stmt(
         Token::LeftBrace => Ok(parse_block(input, state, lib, settings.level_up())?),
 
         // fn ...
+        #[cfg(not(feature = "no_function"))]
+        Token::Fn | Token::Private if !state.engine.options.allow_functions => {
+            Err(PERR::FeatureDisabled("function definitions".into()).into_err(settings.pos))
+        }
+
         #[cfg(not(feature = "no_function"))]
         Token::Fn if !settings.is_global => Err(PERR::WrongFnDefinition.into_err(settings.pos)),
 
@@ -2640,6 +2746,13 @@ fn parse_stmt(
 
         Token::If => parse_if(input, state, lib, settings.level_up()),
         Token::Switch => parse_switch(input, state, lib, settings.level_up()),
+
+        Token::While | Token::Loop | Token::Do | Token::For
+            if !state.engine.options.allow_looping =>
+        {
+            Err(PERR::FeatureDisabled("loops".into()).into_err(settings.pos))
+        }
+
         Token::While | Token::Loop => parse_while_loop(input, state, lib, settings.level_up()),
         Token::Do => parse_do(input, state, lib, settings.level_up()),
         Token::For => parse_for(input, state, lib, settings.level_up()),
@@ -2693,6 +2806,11 @@ fn parse_stmt(
         Token::Let => parse_let(input, state, lib, ReadWrite, false, settings.level_up()),
         Token::Const => parse_let(input, state, lib, ReadOnly, false, settings.level_up()),
 
+        #[cfg(not(feature = "no_module"))]
+        Token::Import if !state.engine.options.allow_import => {
+            Err(PERR::FeatureDisabled("'import'".into()).into_err(settings.pos))
+        }
+
         #[cfg(not(feature = "no_module"))]
         Token::Import => parse_import(input, state, lib, settings.level_up()),
 
@@ -2758,8 +2876,16 @@ fn parse_try_catch(
     };
 
     // try { body } catch ( var ) { catch_block }
+    let prev_stack_len = state.stack.len();
+
+    if let Some(Ident { ref name, .. }) = var_def {
+        state.stack.push((name.clone(), AccessMode::ReadWrite));
+    }
+
     let catch_body = parse_block(input, state, lib, settings.level_up())?;
 
+    state.stack.truncate(prev_stack_len);
+
     Ok(Stmt::TryCatch(
         Box::new((body, var_def, catch_body)),
         settings.pos,
@@ -2780,13 +2906,13 @@ fn parse_fn(
     #[cfg(not(feature = "unchecked"))]
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
 
-    let (token, pos) = input.next().unwrap();
+    let (token, name_pos) = input.next().unwrap();
 
     let name = token
         .into_function_name_for_override()
         .map_err(|t| match t {
-            Token::Reserved(s) => PERR::Reserved(s).into_err(pos),
-            _ => PERR::FnMissingName.into_err(pos),
+            Token::Reserved(s) => PERR::Reserved(s).into_err(name_pos),
+            _ => PERR::FnMissingName.into_err(name_pos),
         })?;
 
     match input.peek().unwrap() {
@@ -2862,6 +2988,7 @@ fn parse_fn(
         #[cfg(not(feature = "no_module"))]
         mods: Default::default(),
         comments,
+        pos: name_pos,
     })
 }
 
@@ -3022,6 +3149,7 @@ fn parse_anon_fn(
         #[cfg(not(feature = "no_module"))]
         mods: Default::default(),
         comments: Default::default(),
+        pos: settings.pos,
     };
 
     let expr = Expr::FnPointer(fn_name, settings.pos);
@@ -3053,6 +3181,7 @@ impl Engine {
             #[cfg(not(feature = "no_function"))]
             self.max_function_expr_depth(),
         );
+        state.scope = Some(scope);
 
         let settings = ParseSettings {
             allow_if_expr: false,
@@ -3090,6 +3219,7 @@ impl Engine {
         &self,
         script_hash: u64,
         input: &mut TokenStream,
+        scope: &Scope,
     ) -> Result<(Vec<Stmt>, Vec<ScriptFnDef>), ParseError> {
         let mut statements = Vec::with_capacity(16);
         let mut functions = HashMap::with_capacity_and_hasher(16, StraightHasherBuilder);
@@ -3102,6 +3232,7 @@ impl Engine {
             #[cfg(not(feature = "no_function"))]
             self.max_function_expr_depth(),
         );
+        state.scope = Some(scope);
 
         while !input.peek().unwrap().0.is_eof() {
             let settings = ParseSettings {
@@ -3163,7 +3294,7 @@ impl Engine {
         scope: &Scope,
         optimization_level: OptimizationLevel,
     ) -> Result<AST, ParseError> {
-        let (statements, lib) = self.parse_global_level(script_hash, input)?;
+        let (statements, lib) = self.parse_global_level(script_hash, input, scope)?;
 
         Ok(
             // Optimize AST
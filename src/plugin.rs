@@ -1,5 +1,6 @@
 //! Module defining macros for developing _plugins_.
 
+pub use crate::dynamic::Variant;
 pub use crate::fn_native::{CallableFunction, FnCallArgs};
 pub use crate::stdlib::{any::TypeId, boxed::Box, format, mem, string::ToString, vec as new_vec};
 pub use crate::{
@@ -12,6 +13,17 @@ pub use rhai_codegen::*;
 #[cfg(features = "no_module")]
 pub use rhai_codegen::{export_fn, register_exported_fn};
 
+/// Trait implemented by a custom type that can register itself, together with its API,
+/// into an [`Engine`].
+///
+/// This trait is usually implemented via the `#[derive(CustomType)]` procedural macro.
+/// Use [`Engine::build_type`] to register the type.
+#[cfg(not(feature = "no_object"))]
+pub trait CustomType: Variant + Clone {
+    /// Register the type and its API into an [`Engine`].
+    fn build(engine: &mut Engine);
+}
+
 /// Trait implemented by a _plugin function_.
 ///
 /// This trait should not be used directly.
@@ -30,6 +42,15 @@ pub trait PluginFunction {
     /// Is this plugin function variadic?
     fn is_variadic(&self) -> bool;
 
+    /// Number of trailing optional parameters (i.e. `Option<T>`) which may be omitted in calls.
+    ///
+    /// The function can be called with any number of arguments from
+    /// `input_types().len() - num_optional_params()` up to `input_types().len()`.
+    #[inline(always)]
+    fn num_optional_params(&self) -> usize {
+        0
+    }
+
     /// Convert a plugin function into a boxed trait object.
     fn clone_boxed(&self) -> Box<dyn PluginFunction>;
 
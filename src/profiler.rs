@@ -0,0 +1,310 @@
+//! Module defining the execution profiler.
+#![cfg(not(feature = "no_std"))]
+
+use crate::fn_native::{Locked, Shared};
+use crate::stdlib::{
+    boxed::Box,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    string::{String, ToString},
+    vec::Vec,
+};
+use crate::utils::get_hasher;
+use crate::{Engine, ImmutableString};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stdlib::time::{Duration, Instant};
+
+#[cfg(target_arch = "wasm32")]
+use instant::{Duration, Instant};
+
+/// Name of the root frame in folded stacks.
+const ROOT_FRAME: &str = "<main>";
+
+/// Profiling information of a function.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FnProfile {
+    /// Hash key of the function, distinct for functions of the same name from different
+    /// namespaces or sources.
+    pub hash: u64,
+    /// Name of the function, qualified by its namespace (if any).
+    pub name: ImmutableString,
+    /// Source of the function (e.g. the id of the module defining it), empty if none.
+    pub source: ImmutableString,
+    /// Is this a native Rust function?
+    pub is_native: bool,
+    /// Number of calls.
+    pub calls: u64,
+    /// Total time spent in the function, including calls to other functions.
+    pub inclusive_time: Duration,
+    /// Total time spent in the function, excluding calls to other functions.
+    pub exclusive_time: Duration,
+    /// Number of operations performed in the function, including calls to other functions.
+    pub inclusive_operations: u64,
+    /// Number of operations performed in the function, excluding calls to other functions.
+    pub exclusive_operations: u64,
+}
+
+/// Report of the execution profiler.
+///
+/// Obtained via [`Engine::profile_report`].
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Profiling information of functions, keyed by function hash and source.
+    functions: HashMap<u64, FnProfile>,
+    /// Exclusive time spent in each call stack, keyed by the `;`-separated function names.
+    stacks: HashMap<String, Duration>,
+}
+
+impl ProfileReport {
+    /// Is the report empty?
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+    /// Get an iterator over the profiling information of all functions called, in no particular order.
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = &FnProfile> {
+        self.functions.values()
+    }
+    /// Get the profiling information of a function by hash key.
+    #[inline(always)]
+    pub fn get(&self, hash: u64) -> Option<&FnProfile> {
+        self.functions.get(&hash)
+    }
+    /// Find the profiling information of all functions with a particular name.
+    #[inline(always)]
+    pub fn find<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FnProfile> + 'a {
+        self.functions.values().filter(move |f| f.name == name)
+    }
+    /// Get the profiling information of all functions called, sorted by exclusive time
+    /// (longest first).
+    pub fn sorted_by_exclusive_time(&self) -> Vec<&FnProfile> {
+        let mut list: Vec<_> = self.functions.values().collect();
+        list.sort_by(|a, b| {
+            b.exclusive_time
+                .cmp(&a.exclusive_time)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        list
+    }
+    /// Export the call stacks in the _folded stacks_ format understood by flame-graph tools
+    /// such as [`inferno`](https://github.com/jonhoo/inferno) and
+    /// [`flamegraph.pl`](https://github.com/brendangregg/FlameGraph).
+    ///
+    /// Each line contains a `;`-separated call stack followed by the exclusive time (in microseconds)
+    /// spent in it.
+    pub fn folded_stacks(&self) -> String {
+        let mut stacks: Vec<_> = self.stacks.iter().collect();
+        stacks.sort();
+
+        stacks
+            .into_iter()
+            .map(|(stack, time)| format!("{} {}\n", stack, time.as_micros()))
+            .collect()
+    }
+    /// Record a function call.
+    fn record(
+        &mut self,
+        func: ProfiledFn,
+        stack: &str,
+        frame: &Frame,
+        time: Duration,
+        operations: u64,
+    ) {
+        let ProfiledFn {
+            hash,
+            name,
+            source,
+            is_native,
+        } = func;
+        let source = source.unwrap_or("");
+
+        let exclusive_time = time.checked_sub(frame.child_time).unwrap_or_default();
+        let exclusive_operations = operations.saturating_sub(frame.child_operations);
+
+        // Functions with the same hash from different sources are profiled separately
+        let hash = if source.is_empty() {
+            hash
+        } else {
+            let s = &mut get_hasher();
+            hash.hash(s);
+            source.hash(s);
+            s.finish()
+        };
+
+        let profile = self.functions.entry(hash).or_insert_with(|| FnProfile {
+            hash,
+            name: name.into(),
+            source: source.into(),
+            is_native,
+            calls: 0,
+            inclusive_time: Default::default(),
+            exclusive_time: Default::default(),
+            inclusive_operations: 0,
+            exclusive_operations: 0,
+        });
+
+        profile.calls += 1;
+        profile.inclusive_time += time;
+        profile.exclusive_time += exclusive_time;
+        profile.inclusive_operations += operations;
+        profile.exclusive_operations += exclusive_operations;
+
+        *self.stacks.entry(stack.to_string()).or_default() += exclusive_time;
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<30} {:>10} {:>14} {:>14} {:>12} {:>12}",
+            "function", "calls", "incl. (us)", "excl. (us)", "incl. ops", "excl. ops"
+        )?;
+
+        for p in self.sorted_by_exclusive_time() {
+            writeln!(
+                f,
+                "{:<30} {:>10} {:>14} {:>14} {:>12} {:>12}",
+                match (p.is_native, p.source.is_empty()) {
+                    (true, true) => p.name.to_string(),
+                    (true, false) => format!("{} [{}]", p.name, p.source),
+                    (false, true) => format!("{} (script)", p.name),
+                    (false, false) => format!("{} (script) [{}]", p.name, p.source),
+                },
+                p.calls,
+                p.inclusive_time.as_micros(),
+                p.exclusive_time.as_micros(),
+                p.inclusive_operations,
+                p.exclusive_operations
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A function whose call is recorded by the profiler.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProfiledFn<'a> {
+    /// Hash key of the function.
+    pub hash: u64,
+    /// Name of the function.
+    pub name: &'a str,
+    /// Source of the function, if any.
+    pub source: Option<&'a str>,
+    /// Is the function a native Rust function?
+    pub is_native: bool,
+}
+
+/// A function call being profiled.
+#[derive(Debug, Clone)]
+struct Frame {
+    /// Time when the function is called.
+    start: Instant,
+    /// Value of the operations counter when the function is called.
+    start_operations: u64,
+    /// Time spent in calls to other functions.
+    child_time: Duration,
+    /// Number of operations performed in calls to other functions.
+    child_operations: u64,
+    /// Length of the call stack path before this function is added.
+    stack_len: usize,
+}
+
+/// _(INTERNALS)_ State of the execution profiler during an evaluation.
+/// Exported under the `internals` feature only.
+///
+/// # WARNING
+///
+/// This type is volatile and may change.
+#[derive(Debug, Clone)]
+pub struct ProfilerState {
+    /// Function calls being profiled.
+    frames: Vec<Frame>,
+    /// Current call stack, with `;`-separated function names.
+    stack: String,
+}
+
+impl Default for ProfilerState {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            frames: Default::default(),
+            stack: ROOT_FRAME.into(),
+        }
+    }
+}
+
+impl Engine {
+    /// Start profiling a function call.
+    ///
+    /// Takes the profiler state and the operations counter from the [`State`][crate::engine::State]
+    /// because the rest of it may be borrowed.
+    pub(crate) fn profiler_enter(
+        &self,
+        profiler: &mut Option<Box<ProfilerState>>,
+        operations: u64,
+        name: &str,
+    ) {
+        let profiler = profiler.get_or_insert_with(Default::default);
+
+        profiler.frames.push(Frame {
+            start: Instant::now(),
+            start_operations: operations,
+            child_time: Default::default(),
+            child_operations: 0,
+            stack_len: profiler.stack.len(),
+        });
+        profiler.stack.push(';');
+        profiler.stack.push_str(name);
+    }
+    /// Finish profiling a function call.
+    pub(crate) fn profiler_exit(
+        &self,
+        profiler: &mut Option<Box<ProfilerState>>,
+        operations: u64,
+        func: ProfiledFn,
+    ) {
+        let report = match self.profiler {
+            Some(ref report) => report,
+            None => return,
+        };
+
+        let profiler = match profiler {
+            Some(profiler) => profiler,
+            None => return,
+        };
+        let frame = match profiler.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+
+        let time = frame.start.elapsed();
+        let operations = operations.saturating_sub(frame.start_operations);
+
+        {
+            #[cfg(not(feature = "sync"))]
+            let mut report = report.borrow_mut();
+            #[cfg(feature = "sync")]
+            let mut report = report.write().unwrap();
+
+            report.record(func, &profiler.stack, &frame, time, operations);
+        }
+
+        profiler.stack.truncate(frame.stack_len);
+
+        if let Some(parent) = profiler.frames.last_mut() {
+            parent.child_time += time;
+            parent.child_operations += operations;
+        }
+    }
+}
+
+/// Make a new, empty, shared [`ProfileReport`].
+#[inline(always)]
+pub(crate) fn new_report() -> Shared<Locked<ProfileReport>> {
+    Shared::new(Locked::new(Default::default()))
+}
@@ -4,7 +4,10 @@ use crate::stdlib::{
     boxed::Box,
     error::Error,
     fmt,
+    ops::Deref,
     string::{String, ToString},
+    time::Duration,
+    vec::Vec,
 };
 use crate::{Dynamic, ImmutableString, ParseErrorType, Position, INT};
 
@@ -31,16 +34,26 @@ pub enum EvalAltResult {
 
     /// Usage of an unknown variable. Wrapped value is the variable name.
     ErrorVariableNotFound(String, Position),
+    /// Definition of a variable that is rejected by the callback registered via
+    /// [`Engine::on_def_var`][crate::Engine::on_def_var]. Wrapped value is the variable name.
+    ErrorForbiddenVariable(String, Position),
     /// Call to an unknown function. Wrapped value is the function signature.
     ErrorFunctionNotFound(String, Position),
     /// An error has occurred inside a called function.
-    /// Wrapped values are the function name, function source, and the interior error.
-    ErrorInFunctionCall(String, String, Box<EvalAltResult>, Position),
+    /// Wrapped values are the function called (its name and the types of its arguments),
+    /// function source, and the interior error.
+    ///
+    /// Each function call in the call stack adds one level of wrapping.
+    /// Use [`backtrace`][EvalAltResult::backtrace] to get all the function calls.
+    ErrorInFunctionCall(CalledFn, String, Box<EvalAltResult>, Position),
     /// Usage of an unknown [module][crate::Module]. Wrapped value is the [module][crate::Module] name.
     ErrorModuleNotFound(String, Position),
     /// An error has occurred while loading a [module][crate::Module].
     /// Wrapped value are the [module][crate::Module] name and the interior error.
     ErrorInModule(String, Box<EvalAltResult>, Position),
+    /// Call to a function requiring a [capability][crate::Capabilities] that is not granted.
+    /// Wrapped values are the function name and the capability required.
+    ErrorPermissionDenied(String, String, Position),
     /// Access to `this` that is not bound.
     ErrorUnboundThis(Position),
     /// Data is not of the required type.
@@ -72,6 +85,8 @@ pub enum EvalAltResult {
     ErrorArithmetic(String, Position),
     /// Number of operations over maximum limit.
     ErrorTooManyOperations(Position),
+    /// Evaluation time over maximum limit. Wrapped value is the time elapsed.
+    ErrorTimeout(Duration, Position),
     /// [Modules][crate::Module] over maximum limit.
     ErrorTooManyModules(Position),
     /// Call stack over maximum limit.
@@ -98,9 +113,10 @@ impl EvalAltResult {
             #[allow(deprecated)]
             Self::ErrorSystem(_, s) => s.description(),
             Self::ErrorParsing(p, _) => p.desc(),
-            Self::ErrorInFunctionCall(_,_, _, _) => "Error in called function",
+            Self::ErrorInFunctionCall(_, _, _, _) => "Error in called function",
             Self::ErrorInModule(_, _, _) => "Error in module",
             Self::ErrorFunctionNotFound(_, _) => "Function not found",
+            Self::ErrorPermissionDenied(_, _, _) => "Permission denied",
             Self::ErrorUnboundThis(_) => "'this' is not bound",
             Self::ErrorMismatchDataType(_, _, _) => "Data type is incorrect",
             Self::ErrorIndexingType(_, _) => {
@@ -118,6 +134,7 @@ impl EvalAltResult {
             Self::ErrorStringBounds(_, _, _) => "String index out of bounds",
             Self::ErrorFor(_) => "For loop expects an array, object map, or range",
             Self::ErrorVariableNotFound(_, _) => "Variable not found",
+            Self::ErrorForbiddenVariable(_, _) => "Forbidden variable name",
             Self::ErrorModuleNotFound(_, _) => "Module not found",
             Self::ErrorDataRace(_, _) => "Data race detected when accessing variable",
             Self::ErrorAssignmentToConstant(_, _) => "Cannot assign to a constant",
@@ -126,6 +143,7 @@ impl EvalAltResult {
             Self::ErrorDotExpr(_, _) => "Malformed dot expression",
             Self::ErrorArithmetic(_, _) => "Arithmetic error",
             Self::ErrorTooManyOperations(_) => "Too many operations",
+            Self::ErrorTimeout(_, _) => "Script timed out",
             Self::ErrorTooManyModules(_) => "Too many modules imported",
             Self::ErrorStackOverflow(_) => "Stack overflow",
             Self::ErrorDataTooLarge(_, _) => "Data size exceeds maximum limit",
@@ -152,7 +170,9 @@ impl fmt::Display for EvalAltResult {
             Self::ErrorParsing(p, _) => write!(f, "Syntax error: {}", p)?,
 
             #[cfg(not(feature = "no_function"))]
-            Self::ErrorInFunctionCall(s, src, err, _) if crate::engine::is_anonymous_fn(s) => {
+            Self::ErrorInFunctionCall(s, src, err, _)
+                if crate::engine::is_anonymous_fn(s.name()) =>
+            {
                 write!(f, "{}, in call to closure", err)?;
                 if !src.is_empty() {
                     write!(f, " @ '{}'", src)?;
@@ -172,10 +192,15 @@ impl fmt::Display for EvalAltResult {
 
             Self::ErrorFunctionNotFound(s, _)
             | Self::ErrorVariableNotFound(s, _)
+            | Self::ErrorForbiddenVariable(s, _)
             | Self::ErrorDataRace(s, _) => write!(f, "{}: {}", desc, s)?,
 
             Self::ErrorModuleNotFound(s, _) => write!(f, "{}: '{}'", desc, s)?,
 
+            Self::ErrorPermissionDenied(s, c, _) => {
+                write!(f, "{}: function {} requires capability '{}'", desc, s, c)?
+            }
+
             Self::ErrorDotExpr(s, _) if !s.is_empty() => write!(f, "{}", s)?,
 
             Self::ErrorIndexingType(_, _)
@@ -242,6 +267,7 @@ impl fmt::Display for EvalAltResult {
                 index, max
             )?,
             Self::ErrorDataTooLarge(typ, _) => write!(f, "{} exceeds maximum limit", typ)?,
+            Self::ErrorTimeout(elapsed, _) => write!(f, "{} after {:?}", desc, elapsed)?,
         }
 
         // Do not write any position if None
@@ -284,6 +310,7 @@ impl EvalAltResult {
             Self::ErrorFunctionNotFound(_, _)
             | Self::ErrorInFunctionCall(_, _, _, _)
             | Self::ErrorInModule(_, _, _)
+            | Self::ErrorPermissionDenied(_, _, _)
             | Self::ErrorUnboundThis(_)
             | Self::ErrorMismatchDataType(_, _, _)
             | Self::ErrorArrayBounds(_, _, _)
@@ -291,6 +318,7 @@ impl EvalAltResult {
             | Self::ErrorIndexingType(_, _)
             | Self::ErrorFor(_)
             | Self::ErrorVariableNotFound(_, _)
+            | Self::ErrorForbiddenVariable(_, _)
             | Self::ErrorModuleNotFound(_, _)
             | Self::ErrorDataRace(_, _)
             | Self::ErrorAssignmentToConstant(_, _)
@@ -301,6 +329,7 @@ impl EvalAltResult {
             | Self::ErrorRuntime(_, _) => true,
 
             Self::ErrorTooManyOperations(_)
+            | Self::ErrorTimeout(_, _)
             | Self::ErrorTooManyModules(_)
             | Self::ErrorStackOverflow(_)
             | Self::ErrorDataTooLarge(_, _)
@@ -321,6 +350,7 @@ impl EvalAltResult {
             Self::ErrorParsing(_, _) => true,
 
             Self::ErrorTooManyOperations(_)
+            | Self::ErrorTimeout(_, _)
             | Self::ErrorTooManyModules(_)
             | Self::ErrorStackOverflow(_)
             | Self::ErrorDataTooLarge(_, _) => true,
@@ -342,6 +372,7 @@ impl EvalAltResult {
             | Self::ErrorFunctionNotFound(_, pos)
             | Self::ErrorInFunctionCall(_, _, _, pos)
             | Self::ErrorInModule(_, _, pos)
+            | Self::ErrorPermissionDenied(_, _, pos)
             | Self::ErrorUnboundThis(pos)
             | Self::ErrorMismatchDataType(_, _, pos)
             | Self::ErrorArrayBounds(_, _, pos)
@@ -349,6 +380,7 @@ impl EvalAltResult {
             | Self::ErrorIndexingType(_, pos)
             | Self::ErrorFor(pos)
             | Self::ErrorVariableNotFound(_, pos)
+            | Self::ErrorForbiddenVariable(_, pos)
             | Self::ErrorModuleNotFound(_, pos)
             | Self::ErrorDataRace(_, pos)
             | Self::ErrorAssignmentToConstant(_, pos)
@@ -357,6 +389,7 @@ impl EvalAltResult {
             | Self::ErrorDotExpr(_, pos)
             | Self::ErrorArithmetic(_, pos)
             | Self::ErrorTooManyOperations(pos)
+            | Self::ErrorTimeout(_, pos)
             | Self::ErrorTooManyModules(pos)
             | Self::ErrorStackOverflow(pos)
             | Self::ErrorDataTooLarge(_, pos)
@@ -375,6 +408,7 @@ impl EvalAltResult {
             | Self::ErrorFunctionNotFound(_, pos)
             | Self::ErrorInFunctionCall(_, _, _, pos)
             | Self::ErrorInModule(_, _, pos)
+            | Self::ErrorPermissionDenied(_, _, pos)
             | Self::ErrorUnboundThis(pos)
             | Self::ErrorMismatchDataType(_, _, pos)
             | Self::ErrorArrayBounds(_, _, pos)
@@ -382,6 +416,7 @@ impl EvalAltResult {
             | Self::ErrorIndexingType(_, pos)
             | Self::ErrorFor(pos)
             | Self::ErrorVariableNotFound(_, pos)
+            | Self::ErrorForbiddenVariable(_, pos)
             | Self::ErrorModuleNotFound(_, pos)
             | Self::ErrorDataRace(_, pos)
             | Self::ErrorAssignmentToConstant(_, pos)
@@ -390,6 +425,7 @@ impl EvalAltResult {
             | Self::ErrorDotExpr(_, pos)
             | Self::ErrorArithmetic(_, pos)
             | Self::ErrorTooManyOperations(pos)
+            | Self::ErrorTimeout(_, pos)
             | Self::ErrorTooManyModules(pos)
             | Self::ErrorStackOverflow(pos)
             | Self::ErrorDataTooLarge(_, pos)
@@ -408,6 +444,226 @@ impl EvalAltResult {
         }
         self
     }
+    /// Get the [backtrace][Backtrace] of this error, listing all the function calls leading to
+    /// it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let err = engine
+    ///     .consume("fn foo(x) { bar(x, true) }\nfn bar(x, y) { baz(y) }\nfoo(\"hello\");")
+    ///     .expect_err("should error");
+    ///
+    /// let backtrace = err.backtrace();
+    ///
+    /// assert_eq!(backtrace.frames()[0].fn_name, "foo");
+    /// assert_eq!(backtrace.frames()[1].fn_name, "bar");
+    ///
+    /// assert_eq!(
+    ///     backtrace.to_string(),
+    ///     "Traceback (most recent call last):\n\
+    ///      \x20 at line 3, position 1, in call to foo(string)\n\
+    ///      \x20 at line 1, position 13, in call to bar(string, bool)\n\
+    ///      Function not found: baz (bool) (line 2, position 16)"
+    /// );
+    /// # }
+    /// ```
+    pub fn backtrace(&self) -> Backtrace<'_> {
+        let mut frames = Vec::new();
+        let mut err = self;
+
+        while let Self::ErrorInFunctionCall(fn_name, source, inner, pos) = err {
+            frames.push(BacktraceFrame {
+                fn_name: fn_name.name().to_string(),
+                arg_types: fn_name.arg_types().to_vec(),
+                source: if source.is_empty() {
+                    None
+                } else {
+                    Some(source.clone())
+                },
+                pos: *pos,
+            });
+            err = inner;
+        }
+
+        Backtrace { frames, error: err }
+    }
+}
+
+/// A function called in [`EvalAltResult::ErrorInFunctionCall`]: its name, together with the
+/// types of the arguments it was called with.
+///
+/// It dereferences to, displays as and compares equal to the function name.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub struct CalledFn {
+    /// Name of the function.
+    name: String,
+    /// Names of the types of the arguments.
+    arg_types: Vec<String>,
+}
+
+impl CalledFn {
+    /// Create a new [`CalledFn`].
+    #[inline(always)]
+    pub fn new(name: impl Into<String>, arg_types: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            name: name.into(),
+            arg_types: arg_types.into_iter().collect(),
+        }
+    }
+    /// Name of the function.
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Names of the types of the arguments.
+    ///
+    /// Empty if not recorded, e.g. when created from the function name only.
+    #[inline(always)]
+    pub fn arg_types(&self) -> &[String] {
+        &self.arg_types
+    }
+    /// Set the names of the types of the arguments if not yet recorded.
+    #[inline(always)]
+    pub(crate) fn fill_arg_types(&mut self, arg_types: impl FnOnce() -> Vec<String>) {
+        if self.arg_types.is_empty() {
+            self.arg_types = arg_types();
+        }
+    }
+}
+
+impl From<String> for CalledFn {
+    #[inline(always)]
+    fn from(name: String) -> Self {
+        Self {
+            name,
+            arg_types: Default::default(),
+        }
+    }
+}
+
+impl From<&str> for CalledFn {
+    #[inline(always)]
+    fn from(name: &str) -> Self {
+        name.to_string().into()
+    }
+}
+
+impl Deref for CalledFn {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.name
+    }
+}
+
+impl PartialEq<str> for CalledFn {
+    #[inline(always)]
+    fn eq(&self, other: &str) -> bool {
+        self.name == other
+    }
+}
+
+impl PartialEq<&str> for CalledFn {
+    #[inline(always)]
+    fn eq(&self, other: &&str) -> bool {
+        self.name == *other
+    }
+}
+
+impl PartialEq<String> for CalledFn {
+    #[inline(always)]
+    fn eq(&self, other: &String) -> bool {
+        &self.name == other
+    }
+}
+
+impl fmt::Display for CalledFn {
+    #[inline(always)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// A function call in the [backtrace][Backtrace] of an [`EvalAltResult`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BacktraceFrame {
+    /// Name of the function.
+    pub fn_name: String,
+    /// Names of the types of the arguments.
+    pub arg_types: Vec<String>,
+    /// Source of the function, if any.
+    pub source: Option<String>,
+    /// Position of the function call.
+    pub pos: Position,
+}
+
+impl fmt::Display for BacktraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.pos.is_none() {
+            write!(f, "at {}, ", self.pos)?;
+        }
+
+        #[cfg(not(feature = "no_function"))]
+        if crate::engine::is_anonymous_fn(&self.fn_name) {
+            f.write_str("in call to closure")?;
+        } else {
+            write!(f, "in call to {}", self.fn_name)?;
+        }
+        #[cfg(feature = "no_function")]
+        write!(f, "in call to {}", self.fn_name)?;
+
+        write!(f, "({})", self.arg_types.join(", "))?;
+
+        if let Some(ref source) = self.source {
+            write!(f, " @ '{}'", source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Backtrace of an [`EvalAltResult`], obtained via [`EvalAltResult::backtrace`].
+///
+/// The [`Display`][fmt::Display] implementation formats it in the style of a Python traceback.
+#[derive(Debug, Clone)]
+pub struct Backtrace<'a> {
+    /// Function calls, outermost first.
+    frames: Vec<BacktraceFrame>,
+    /// The error that occurred in the innermost function call.
+    error: &'a EvalAltResult,
+}
+
+impl<'a> Backtrace<'a> {
+    /// Get the function calls, outermost first.
+    #[inline(always)]
+    pub fn frames(&self) -> &[BacktraceFrame] {
+        &self.frames
+    }
+    /// Get the error that occurred in the innermost function call.
+    #[inline(always)]
+    pub fn error(&self) -> &'a EvalAltResult {
+        self.error
+    }
+}
+
+impl fmt::Display for Backtrace<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Traceback (most recent call last):")?;
+
+        for frame in &self.frames {
+            writeln!(f, "  {}", frame)?;
+        }
+
+        write!(f, "{}", self.error)
+    }
 }
 
 impl<T> From<EvalAltResult> for Result<T, Box<EvalAltResult>> {
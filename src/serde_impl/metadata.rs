@@ -89,7 +89,7 @@ struct FnMetadata {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub return_type: Option<String>,
     pub signature: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub doc_comments: Vec<String>,
 }
 
@@ -125,8 +125,8 @@ impl From<&crate::module::FuncInfo> for FnMetadata {
                 FnType::Native
             },
             num_params: info.params,
-            params: if let Some(ref names) = info.param_names {
-                names
+            params: if !info.param_names.is_empty() {
+                info.param_names
                     .iter()
                     .take(info.params)
                     .map(|s| {
@@ -142,8 +142,8 @@ impl From<&crate::module::FuncInfo> for FnMetadata {
             } else {
                 vec![]
             },
-            return_type: if let Some(ref names) = info.param_names {
-                names
+            return_type: if !info.param_names.is_empty() {
+                info.param_names
                     .last()
                     .map(|s| s.to_string())
                     .or_else(|| Some("()".to_string()))
@@ -151,11 +151,14 @@ impl From<&crate::module::FuncInfo> for FnMetadata {
                 None
             },
             signature: info.gen_signature(),
+            #[cfg(not(feature = "no_function"))]
             doc_comments: if info.func.is_script() {
                 info.func.get_fn_def().comments.clone()
             } else {
-                Default::default()
+                info.comments.clone()
             },
+            #[cfg(feature = "no_function")]
+            doc_comments: info.comments.clone(),
         }
     }
 }
@@ -178,11 +181,7 @@ impl From<crate::ScriptFnMetadata<'_>> for FnMetadata {
                 .collect(),
             return_type: Some("Dynamic".to_string()),
             signature: info.to_string(),
-            doc_comments: if info.comments.is_empty() {
-                None
-            } else {
-                Some(info.comments.iter().map(|s| s.to_string()).collect())
-            },
+            doc_comments: info.comments.iter().map(|s| s.to_string()).collect(),
         }
     }
 }
@@ -244,7 +243,11 @@ impl Engine {
             .map(|f| f.into())
             .for_each(|info| global.functions.push(info));
 
-        if let Some(ast) = ast {
+        #[cfg(feature = "no_function")]
+        let _ = ast;
+
+        #[cfg(not(feature = "no_function"))]
+        {
             ast.iter_functions()
                 .map(|f| f.into())
                 .for_each(|info| global.functions.push(info));
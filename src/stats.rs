@@ -0,0 +1,61 @@
+//! Module defining the resource usage report of an evaluation.
+
+use crate::stdlib::{fmt, time::Duration};
+
+/// A report on the resources used by an evaluation.
+///
+/// Filled in by evaluations run with [`EvalOptions::with_stats`][crate::EvalOptions::with_stats],
+/// whether the evaluation succeeds or not.
+///
+/// Sizes are measured in the same way as they are checked against the limits set via
+/// [`Engine::set_max_string_size`][crate::Engine::set_max_string_size],
+/// [`Engine::set_max_array_size`][crate::Engine::set_max_array_size] and
+/// [`Engine::set_max_map_size`][crate::Engine::set_max_map_size], i.e. on the results of all
+/// expressions and statements evaluated, plus the values of variables after op-assignments such as
+/// `x += y`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct EvalStats {
+    /// Number of operations performed.
+    pub operations: u64,
+    /// Maximum level of nested function calls reached.
+    /// Not available under `no_function`.
+    #[cfg(not(feature = "no_function"))]
+    pub max_call_depth: usize,
+    /// Number of modules loaded.
+    /// Not available under `no_module`.
+    #[cfg(not(feature = "no_module"))]
+    pub modules: usize,
+    /// Length of the largest string, in bytes.
+    pub max_string_size: usize,
+    /// Largest number of items in an array, including those in all arrays nested within it.
+    /// Not available under `no_index`.
+    #[cfg(not(feature = "no_index"))]
+    pub max_array_size: usize,
+    /// Largest number of items in an object map, including those in all object maps nested within it.
+    /// Not available under `no_object`.
+    #[cfg(not(feature = "no_object"))]
+    pub max_map_size: usize,
+    /// Wall-clock time taken by the evaluation, as measured by the [`Engine`][crate::Engine]'s
+    /// clock. [`None`] if there is no clock, e.g. under `no_std`, and always [`None`] under
+    /// `unchecked`.
+    pub elapsed: Option<Duration>,
+}
+
+impl fmt::Display for EvalStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "operations: {}", self.operations)?;
+        #[cfg(not(feature = "no_function"))]
+        write!(f, ", max call depth: {}", self.max_call_depth)?;
+        #[cfg(not(feature = "no_module"))]
+        write!(f, ", modules: {}", self.modules)?;
+        write!(f, ", max string size: {}", self.max_string_size)?;
+        #[cfg(not(feature = "no_index"))]
+        write!(f, ", max array size: {}", self.max_array_size)?;
+        #[cfg(not(feature = "no_object"))]
+        write!(f, ", max map size: {}", self.max_map_size)?;
+        if let Some(elapsed) = self.elapsed {
+            write!(f, ", elapsed: {:?}", elapsed)?;
+        }
+        Ok(())
+    }
+}
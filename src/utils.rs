@@ -102,6 +102,24 @@ pub fn calc_script_fn_hash<'a>(
     calc_fn_hash(modules, fn_name, Some(num), empty())
 }
 
+/// Marker type standing for each parameter of a variadic function in its hash key.
+struct VariadicParam;
+
+/// Calculate a [`NonZeroU64`] hash key from the name and the number of parameters of a variadic
+/// function registered via [`Module::set_variadic_fn`][crate::Module::set_variadic_fn].
+///
+/// Variadic functions are keyed apart from functions taking [`Dynamic`][crate::Dynamic]
+/// parameters, so that only they accept arguments of any type.
+#[inline(always)]
+pub(crate) fn calc_variadic_fn_hash(fn_name: &str, num: usize) -> NonZeroU64 {
+    calc_native_fn_hash(
+        empty(),
+        fn_name,
+        (0..num).map(|_| TypeId::of::<VariadicParam>()),
+    )
+    .unwrap()
+}
+
 /// Calculate a [`NonZeroU64`] hash key from a namespace-qualified function name and parameter types.
 ///
 /// Module names are passed in via `&str` references from an iterator.
@@ -491,6 +509,12 @@ impl PartialOrd<ImmutableString> for String {
 }
 
 impl ImmutableString {
+    /// Number of references to the same string.
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub(crate) fn strong_count(&self) -> usize {
+        Shared::strong_count(&self.0)
+    }
     /// Consume the [`ImmutableString`] and convert it into a [`String`].
     /// If there are other references to the same string, a cloned copy is returned.
     #[inline(always)]
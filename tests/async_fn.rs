@@ -0,0 +1,294 @@
+#![cfg(feature = "async")]
+use rhai::{Engine, EvalAltResult, RegisterAsyncFn, Scope, INT};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+/// Waker of a minimal single-threaded executor.
+#[derive(Default)]
+struct Signal {
+    woken: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Wake for Signal {
+    fn wake(self: Arc<Self>) {
+        *self.woken.lock().unwrap() = true;
+        self.condvar.notify_one();
+    }
+}
+
+/// Run futures to completion on the current thread, returning their outputs and the number of
+/// times the executor was yielded to.
+fn block_on_all<F: Future + Unpin>(mut futures: Vec<F>) -> (Vec<F::Output>, usize) {
+    let signal = Arc::new(Signal::default());
+    let waker = Waker::from(signal.clone());
+    let cx = &mut Context::from_waker(&waker);
+
+    let mut outputs: Vec<_> = futures.iter().map(|_| None).collect();
+    let mut yields = 0;
+
+    while outputs.iter().any(Option::is_none) {
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                if let Poll::Ready(value) = Pin::new(future).poll(cx) {
+                    *output = Some(value);
+                }
+            }
+        }
+
+        if outputs.iter().any(Option::is_none) {
+            yields += 1;
+            let mut woken = signal.woken.lock().unwrap();
+            while !*woken {
+                woken = signal.condvar.wait(woken).unwrap();
+            }
+            *woken = false;
+        }
+    }
+
+    (outputs.into_iter().map(Option::unwrap).collect(), yields)
+}
+
+/// A mock future which is pending for a number of polls before completing.
+struct Delay<T>(usize, Option<T>);
+
+impl<T: Unpin> Future for Delay<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        if self.0 == 0 {
+            Poll::Ready(self.1.take().unwrap())
+        } else {
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// A mock future which completes only after a [`Gate`] is opened.
+#[derive(Clone, Default)]
+struct Gate(Arc<Mutex<(bool, Option<Waker>)>>);
+
+impl Gate {
+    fn open(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.0 = true;
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for Gate {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.0.lock().unwrap();
+        if state.0 {
+            Poll::Ready(())
+        } else {
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+fn make_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine.register_async_fn(
+        "fetch",
+        |x: INT| async move { Ok(Delay(3, Some(x * 10)).await) },
+    );
+    engine.register_async_fn("join", |s: String, n: INT| async move {
+        Ok(format!("{}{}", s, Delay(1, Some(n)).await))
+    });
+
+    engine
+}
+
+#[test]
+fn test_async_fn() -> Result<(), Box<EvalAltResult>> {
+    let engine = Arc::new(make_engine());
+    let ast = engine.compile("let x = fetch(1); x + fetch(2)")?;
+
+    let (results, yields) = block_on_all(vec![engine.clone().eval_ast_async::<INT>(&ast)]);
+
+    assert_eq!(*results[0].as_ref().unwrap(), 30);
+    // The executor is yielded to while each call is pending
+    assert!(yields >= 6);
+
+    let (results, _) = block_on_all(vec![engine.clone().eval_ast_async::<bool>(&ast)]);
+    assert!(matches!(
+        **results[0].as_ref().expect_err("should error"),
+        EvalAltResult::ErrorMismatchOutputType(_, _, _)
+    ));
+
+    // Arguments are passed by value and typed
+    let ast = engine.compile(r#"join("x", 42)"#)?;
+    let (results, _) = block_on_all(vec![engine.clone().eval_ast_async::<String>(&ast)]);
+    assert_eq!(results[0].as_ref().unwrap(), "x42");
+
+    // Async functions are not available to normal evaluation
+    assert!(engine.eval::<INT>("fetch(1)").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_async_fn_concurrent() -> Result<(), Box<EvalAltResult>> {
+    let gate = Gate::default();
+    let mut engine = Engine::new();
+
+    let g = gate.clone();
+    engine.register_async_fn("wait", move || {
+        let gate = g.clone();
+        async move {
+            gate.await;
+            Ok(1 as INT)
+        }
+    });
+    let g = gate.clone();
+    engine.register_async_fn("open", move || {
+        let gate = g.clone();
+        async move {
+            Delay(2, Some(())).await;
+            gate.open();
+            Ok(2 as INT)
+        }
+    });
+
+    let engine = Arc::new(engine);
+    let waiting = engine.compile("wait() + 40")?;
+    let opening = engine.compile("open() + 40")?;
+
+    // Both evaluations make progress on a single thread
+    let (results, _) = block_on_all(vec![
+        engine.clone().eval_ast_async::<INT>(&waiting),
+        engine.clone().eval_ast_async::<INT>(&opening),
+    ]);
+
+    assert_eq!(*results[0].as_ref().unwrap(), 41);
+    assert_eq!(*results[1].as_ref().unwrap(), 42);
+
+    Ok(())
+}
+
+#[test]
+fn test_async_fn_drop() -> Result<(), Box<EvalAltResult>> {
+    let engine = Arc::new(make_engine());
+    let ast = engine.compile("let x = 0; loop { x += 1; }")?;
+
+    let mut future = engine.clone().eval_ast_async::<INT>(&ast);
+    let waker = Waker::from(Arc::new(Signal::default()));
+    assert!(Pin::new(&mut future)
+        .poll(&mut Context::from_waker(&waker))
+        .is_pending());
+
+    // Dropping the future terminates the evaluation and waits for its thread,
+    // releasing the engine
+    drop(future);
+    assert_eq!(Arc::strong_count(&engine), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_async_fn_drop_pending() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    let gate = Gate::default();
+
+    // A call which never completes
+    let g = gate.clone();
+    engine.register_async_fn("wait", move || {
+        let gate = g.clone();
+        async move {
+            gate.await;
+            Ok(())
+        }
+    });
+
+    let engine = Arc::new(engine);
+    let ast = engine.compile("wait(); loop {}")?;
+
+    let mut future = engine.clone().eval_ast_async::<()>(&ast);
+    let waker = Waker::from(Arc::new(Signal::default()));
+    let cx = &mut Context::from_waker(&waker);
+
+    // Poll until the call is pending
+    for _ in 0..500 {
+        assert!(Pin::new(&mut future).poll(cx).is_pending());
+        if Arc::strong_count(&gate.0) > 2 {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // The pending call is cancelled, ending the evaluation
+    drop(future);
+    assert_eq!(Arc::strong_count(&engine), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_async_fn_scope() -> Result<(), Box<EvalAltResult>> {
+    let engine = Arc::new(make_engine());
+    let ast = engine.compile("let y = fetch(x); x += 1; y")?;
+
+    let mut scope = Scope::new();
+    scope.push("x", 4 as INT);
+
+    let (mut results, _) = block_on_all(vec![engine
+        .clone()
+        .eval_ast_with_scope_async::<INT>(scope, &ast)]);
+    let (scope, result) = results.remove(0);
+
+    assert_eq!(result?, 40);
+    assert_eq!(scope.get_value::<INT>("x").unwrap(), 5);
+    assert_eq!(scope.get_value::<INT>("y").unwrap(), 40);
+
+    // The scope is handed back also when the evaluation fails
+    let ast = engine.compile(r#"x = 0; throw "oops";"#)?;
+    let (mut results, _) = block_on_all(vec![engine
+        .clone()
+        .eval_ast_with_scope_async::<()>(scope, &ast)]);
+    let (scope, result) = results.remove(0);
+
+    assert!(result.is_err());
+    assert_eq!(scope.get_value::<INT>("x").unwrap(), 0);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "unchecked"))]
+#[test]
+fn test_async_fn_limits() -> Result<(), Box<EvalAltResult>> {
+    let progress = Arc::new(AtomicUsize::new(0));
+
+    let mut engine = make_engine();
+    engine.set_max_operations(500);
+
+    let p = progress.clone();
+    engine.on_progress(move |_| {
+        p.fetch_add(1, Ordering::Relaxed);
+        None
+    });
+
+    let engine = Arc::new(engine);
+    let ast = engine.compile("let x = 0; loop { x += fetch(1); }")?;
+
+    let (results, _) = block_on_all(vec![engine.clone().eval_ast_async::<INT>(&ast)]);
+
+    assert!(matches!(
+        **results[0].as_ref().expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+    assert!(progress.load(Ordering::Relaxed) > 0);
+
+    Ok(())
+}
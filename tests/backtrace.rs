@@ -0,0 +1,177 @@
+#![cfg(not(feature = "no_function"))]
+use rhai::{Engine, EvalAltResult, FnPtr, Position, INT};
+use std::any::{type_name, TypeId};
+
+#[test]
+fn test_backtrace_script_functions() {
+    let engine = Engine::new();
+
+    let err = engine
+        .consume(
+            r#"
+                fn inner(x, y) { throw x + y; }
+                fn middle(x) { inner(x, "!") }
+                fn outer() { middle("oops") }
+                outer();
+            "#,
+        )
+        .expect_err("should error");
+
+    let backtrace = err.backtrace();
+    let frames: Vec<_> = backtrace
+        .frames()
+        .iter()
+        .map(|f| (f.fn_name.as_str(), f.pos))
+        .collect();
+
+    assert_eq!(
+        frames,
+        vec![
+            ("outer", Position::new(5, 17)),
+            ("middle", Position::new(4, 30)),
+            ("inner", Position::new(3, 32)),
+        ]
+    );
+    assert!(matches!(
+        backtrace.error(),
+        EvalAltResult::ErrorRuntime(_, pos) if *pos == Position::new(2, 34)
+    ));
+    assert!(backtrace.frames().iter().all(|f| f.source.is_none()));
+    assert_eq!(backtrace.frames()[2].arg_types, vec!["string", "string"]);
+
+    assert_eq!(
+        backtrace.to_string(),
+        [
+            "Traceback (most recent call last):",
+            "  at line 5, position 17, in call to outer()",
+            "  at line 4, position 30, in call to middle(string)",
+            "  at line 3, position 32, in call to inner(string, string)",
+            "Runtime error: oops! (line 2, position 34)",
+        ]
+        .join("\n")
+    );
+
+    // Errors outside functions have no frames
+    let err = engine
+        .consume("let x = true;\nfoo(x);")
+        .expect_err("should error");
+    assert!(err.backtrace().frames().is_empty());
+    assert_eq!(
+        err.backtrace().to_string(),
+        "Traceback (most recent call last):\nFunction not found: foo (bool) (line 2, position 1)"
+    );
+}
+
+#[test]
+fn test_backtrace_native_callbacks() {
+    let mut engine = Engine::new();
+
+    #[allow(deprecated)]
+    engine.register_raw_fn(
+        "apply",
+        &[TypeId::of::<FnPtr>(), TypeId::of::<INT>()],
+        |context, args| {
+            let fn_ptr = std::mem::take(args[0]).cast::<FnPtr>();
+            fn_ptr.call_dynamic(context, None, [std::mem::take(args[1])])
+        },
+    );
+
+    let mut ast = engine
+        .compile(
+            r#"
+                fn check(x) { if x > 0 { throw "positive"; } x }
+                fn run(x) { apply(Fn("check"), x) }
+                run(1)
+            "#,
+        )
+        .unwrap();
+    ast.set_source(Some("rules"));
+
+    let err = engine.eval_ast::<INT>(&ast).expect_err("should error");
+    let backtrace = err.backtrace();
+    let frames = backtrace.frames();
+
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].fn_name, "run");
+    assert_eq!(frames[0].source.as_deref(), Some("rules"));
+    assert_eq!(frames[1].fn_name, "apply");
+    assert_eq!(frames[1].pos, Position::new(3, 29));
+    assert_eq!(frames[1].arg_types, vec!["Fn", type_name::<INT>()]);
+    assert_eq!(frames[2].fn_name, "check");
+    // Functions called back from native Rust functions are reported at the call site
+    assert_eq!(frames[2].pos, Position::new(3, 29));
+    assert_eq!(frames[2].arg_types, vec![type_name::<INT>()]);
+    assert!(matches!(
+        backtrace.error(),
+        EvalAltResult::ErrorRuntime(_, _)
+    ));
+
+    assert!(backtrace.to_string().contains(&format!(
+        "\n  at line 3, position 29, in call to check({}) @ 'rules'\n",
+        type_name::<INT>()
+    )));
+}
+
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_backtrace_self_reporting() {
+    let engine = Engine::new();
+
+    // Native functions that report their own call are not recorded twice
+    let err = engine
+        .consume("fn fail(x) { throw x; }\n[1, 2].map(|x| fail(x));")
+        .expect_err("should error");
+    let names: Vec<_> = err
+        .backtrace()
+        .frames()
+        .iter()
+        .map(|f| f.fn_name.clone())
+        .collect();
+
+    assert_eq!(names.len(), 3);
+    assert_eq!(names[0], "map");
+    assert_eq!(names[2], "fail");
+    assert_eq!(err.backtrace().frames()[0].pos, Position::new(2, 8));
+    assert_eq!(err.backtrace().frames()[0].arg_types, vec!["array", "Fn"]);
+}
+
+#[cfg(not(feature = "no_module"))]
+#[test]
+fn test_backtrace_module_callbacks() {
+    use rhai::{FnAccess, FnNamespace, Module};
+
+    let mut engine = Engine::new();
+
+    let mut module = Module::new();
+    module.set_raw_fn(
+        "apply",
+        FnNamespace::Internal,
+        FnAccess::Public,
+        &[TypeId::of::<FnPtr>()],
+        |context, args| {
+            let fn_ptr = std::mem::take(args[0]).cast::<FnPtr>();
+            fn_ptr.call_dynamic(context, None, [])
+        },
+    );
+    engine.register_static_module("util", module.into());
+
+    let err = engine
+        .consume("fn fail() { throw 42; }\nutil::apply(Fn(\"fail\"));")
+        .expect_err("should error");
+    let backtrace = err.backtrace();
+    let frames: Vec<_> = backtrace
+        .frames()
+        .iter()
+        .map(|f| (f.fn_name.as_str(), f.arg_types.len(), f.pos))
+        .collect();
+
+    // Calls to functions in modules are recorded, and callbacks are reported at the call site
+    assert_eq!(
+        frames,
+        vec![
+            ("apply", 1, Position::new(2, 7)),
+            ("fail", 0, Position::new(2, 7))
+        ]
+    );
+}
@@ -0,0 +1,230 @@
+#![cfg(not(feature = "no_module"))]
+use rhai::{
+    Capabilities, Engine, EvalAltResult, EvalOptions, ImmutableString, Module, RegisterFn, Scope,
+    INT,
+};
+
+fn make_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    let mut fs = Module::new();
+    let hash = fs.set_fn_1("read_file", |path: ImmutableString| Ok(path.len() as INT));
+    fs.update_fn_capabilities(hash, &["fs.read"]);
+    let hash = fs.set_fn_1("write_file", |path: ImmutableString| Ok(path.len() as INT));
+    fs.update_fn_capabilities(hash, &["fs.write"]);
+    engine.register_global_module(fs.into());
+
+    let mut net = Module::new();
+    net.set_fn_0("ping", || Ok(true));
+    net.set_fn_1("fetch", |url: ImmutableString| Ok(url.len() as INT));
+    net.set_capabilities(&["net"]);
+    engine.register_static_module("net", net.into());
+
+    engine.register_fn("add", |x: INT, y: INT| x + y);
+
+    engine
+}
+
+#[test]
+fn test_capabilities() -> Result<(), Box<EvalAltResult>> {
+    let engine = make_engine();
+    let scope = &mut Scope::new();
+
+    let ast = engine.compile(r#"read_file("data.txt") + net::fetch("http://x")"#)?;
+
+    // Unrestricted
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 16);
+
+    let granted = Capabilities::new().grant("fs.read");
+
+    assert_eq!(
+        engine.eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &engine.compile(r#"add(read_file("data.txt"), 1)"#)?,
+            EvalOptions::new().with_capabilities(granted.clone())
+        )?,
+        9
+    );
+
+    let err = engine
+        .eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &ast,
+            EvalOptions::new().with_capabilities(granted.clone()),
+        )
+        .expect_err("should error");
+    assert!(matches!(
+        *err,
+        EvalAltResult::ErrorPermissionDenied(f, c, _) if f == "fetch" && c == "net"
+    ));
+
+    let err = engine
+        .eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &engine.compile(r#"let x = write_file("data.txt");"#)?,
+            EvalOptions::new().with_capabilities(granted),
+        )
+        .expect_err("should error");
+    assert!(matches!(
+        *err,
+        EvalAltResult::ErrorPermissionDenied(ref f, ref c, _) if f == "write_file" && c == "fs.write"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Permission denied: function write_file requires capability 'fs.write'"
+    );
+
+    // Hierarchical capabilities
+    let granted = Capabilities::new().grant("fs").grant("net");
+    assert_eq!(
+        engine.eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &ast,
+            EvalOptions::new().with_capabilities(granted)
+        )?,
+        16
+    );
+
+    // Permission errors can be caught
+    let err = engine
+        .eval_ast_with_scope_and_options::<()>(
+            scope,
+            &engine.compile(
+                r#"
+                    try {
+                        net::ping();
+                    } catch (err) {
+                        throw "caught: " + err;
+                    }
+                "#,
+            )?,
+            EvalOptions::new().with_capabilities(Capabilities::new()),
+        )
+        .expect_err("should error");
+    assert!(matches!(
+        *err,
+        EvalAltResult::ErrorRuntime(v, _)
+            if v.to_string().starts_with("caught: Permission denied: function ping")
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_capabilities_engine() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = make_engine();
+    let scope = &mut Scope::new();
+
+    engine.set_fn_capabilities("add", &["math"]);
+    let ast = engine.compile("add(1, 2)")?;
+
+    assert!(matches!(
+        *engine
+            .eval_ast_with_scope_and_options::<INT>(
+                scope,
+                &ast,
+                EvalOptions::new().with_capabilities(Capabilities::new())
+            )
+            .expect_err("should error"),
+        EvalAltResult::ErrorPermissionDenied(f, c, _) if f == "add" && c == "math"
+    ));
+
+    engine.set_fn_capabilities("add", &[]);
+    assert_eq!(
+        engine.eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &ast,
+            EvalOptions::new().with_capabilities(Capabilities::new())
+        )?,
+        3
+    );
+
+    // Capabilities and costs apply together
+    engine.set_fn_capabilities("add", &["math"]);
+    engine.set_fn_cost("add", 10);
+    let granted: Capabilities = vec!["math"].into_iter().collect();
+    assert_eq!(
+        engine.eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &ast,
+            EvalOptions::new().with_capabilities(granted)
+        )?,
+        3
+    );
+    assert!(engine
+        .eval_ast_with_scope_and_options::<INT>(
+            scope,
+            &ast,
+            EvalOptions::new().with_capabilities(Capabilities::new())
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_capabilities_signatures() {
+    let engine = make_engine();
+
+    let all = engine.gen_fn_signatures(true);
+    assert!(all.iter().any(|s| s.starts_with("read_file(")));
+    assert!(all.iter().any(|s| s.starts_with("net::ping(")));
+
+    let granted = Capabilities::new().grant("fs.read");
+    let signatures = engine.gen_fn_signatures_with_capabilities(true, &granted);
+
+    assert!(signatures.iter().any(|s| s.starts_with("read_file(")));
+    assert!(signatures.iter().any(|s| s.starts_with("add(")));
+    assert!(!signatures.iter().any(|s| s.starts_with("write_file(")));
+    assert!(!signatures.iter().any(|s| s.starts_with("net::")));
+
+    assert_eq!(granted.to_string(), "[fs.read]");
+}
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_capabilities_callbacks() -> Result<(), Box<EvalAltResult>> {
+    let engine = make_engine();
+    let scope = &mut Scope::new();
+
+    let ast = engine.compile(
+        r#"
+            fn total(files) { files.map(|f| read_file(f)).reduce(|sum, v| sum + v, || 0) }
+        "#,
+    )?;
+
+    let files: rhai::Array = vec!["a.txt".into(), "bb.txt".into()];
+
+    let granted = Capabilities::new().grant("fs.read");
+    assert_eq!(
+        engine.call_fn_with_options::<INT>(
+            scope,
+            &ast,
+            "total",
+            (files.clone(),),
+            EvalOptions::new().with_capabilities(granted)
+        )?,
+        11
+    );
+
+    // Restrictions also apply to script functions called back from native Rust functions
+    let err = engine
+        .call_fn_with_options::<INT>(
+            scope,
+            &ast,
+            "total",
+            (files,),
+            EvalOptions::new().with_capabilities(Capabilities::new()),
+        )
+        .expect_err("should error");
+
+    let mut err = &*err;
+    while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = err {
+        err = inner;
+    }
+    assert!(matches!(err, EvalAltResult::ErrorPermissionDenied(f, _, _) if f == "read_file"));
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use rhai::{Engine, EvalAltResult, Position, INT};
+
+#[test]
+fn test_coverage_disabled() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert!(!engine.is_coverage());
+    engine.consume("let x = 1 + 2;")?;
+    assert!(engine.coverage_report().is_none());
+
+    engine.set_coverage(true);
+    assert!(engine.coverage_report().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_coverage_statements_and_branches() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_coverage(true);
+
+    let mut ast = engine.compile(
+        r"
+            let x = 0;
+            if value > 10 && value < 100 {
+                x = 1;
+            } else {
+                x = 2;
+            }
+            switch value {
+                1 => x += 10,
+                42 => x += 20,
+                _ => x += 30
+            }
+            x
+        ",
+    )?;
+    ast.set_source(Some("rules.rhai"));
+
+    let mut scope = rhai::Scope::new();
+    scope.push("value", 42 as INT);
+    assert_eq!(engine.eval_ast_with_scope::<INT>(&mut scope, &ast)?, 21);
+
+    let report = engine.coverage_report().unwrap();
+    assert!(report.get(None).is_none());
+    let coverage = report.get(Some("rules.rhai")).unwrap();
+
+    assert_eq!(coverage.statement_hits(Position::new(2, 13)), Some(1));
+    assert_eq!(coverage.statement_hits(Position::new(4, 19)), Some(1));
+    assert_eq!(coverage.statement_hits(Position::new(6, 19)), Some(0));
+
+    // if
+    assert_eq!(
+        coverage.branch_hits(Position::new(3, 13), Position::new(3, 42)),
+        Some(1)
+    );
+    assert_eq!(
+        coverage.branch_hits(Position::new(3, 13), Position::new(5, 20)),
+        Some(0)
+    );
+    // &&
+    assert_eq!(
+        coverage.branch_hits(Position::new(3, 27), Position::new(3, 36)),
+        Some(1)
+    );
+    assert_eq!(
+        coverage.branch_hits(Position::new(3, 27), Position::NONE),
+        Some(0)
+    );
+    // switch
+    assert_eq!(
+        coverage.branch_hits(Position::new(8, 13), Position::new(10, 25)),
+        Some(1)
+    );
+    assert_eq!(
+        coverage.branch_hits(Position::new(8, 13), Position::new(9, 24)),
+        Some(0)
+    );
+
+    let summary = report.summary();
+    assert_eq!(summary.branches, 7);
+    assert_eq!(summary.branches_hit, 3);
+
+    // Results accumulate across evaluations
+    scope.set_value("value", 1 as INT);
+    assert_eq!(engine.eval_ast_with_scope::<INT>(&mut scope, &ast)?, 12);
+
+    let report = engine.coverage_report().unwrap();
+    let coverage = report.get(Some("rules.rhai")).unwrap();
+    assert_eq!(coverage.statement_hits(Position::new(2, 13)), Some(2));
+    assert_eq!(coverage.statement_hits(Position::new(6, 19)), Some(1));
+    assert_eq!(
+        coverage.branch_hits(Position::new(3, 27), Position::NONE),
+        Some(1)
+    );
+    assert_eq!(report.summary().branches_hit, 6);
+
+    let lcov = report.to_lcov();
+    assert!(lcov.starts_with("TN:\nSF:rules.rhai\n"));
+    assert!(lcov.contains("BRDA:8,2,0,1\n"));
+    assert!(lcov.contains("BRF:7\nBRH:6\n"));
+    assert!(lcov.contains("DA:4,1\n"));
+    assert!(lcov.contains("DA:13,2\n"));
+    assert!(lcov.ends_with("LF:9\nLH:8\nend_of_record\n"));
+
+    let json = report.to_json();
+    assert!(json.starts_with(
+        r#"{"statements":9,"statements_hit":8,"branches":7,"branches_hit":6,"sources":[{"source":"rules.rhai","#
+    ));
+    assert!(json.contains(r#""lines_missed":[11],"uncovered_branches":[[[8,13],[11,24]]]}"#));
+
+    engine.clear_coverage_report();
+    assert!(engine.coverage_report().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_coverage_functions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_coverage(true);
+
+    let ast = engine.compile(
+        r"
+            fn used(x) { x * 2 }
+            fn unused(x) {
+                x + 1
+            }
+            used(21)
+        ",
+    )?;
+
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let report = engine.coverage_report().unwrap();
+    let coverage = report.get(None).unwrap();
+
+    assert_eq!(coverage.statement_hits(Position::new(2, 28)), Some(1));
+    assert_eq!(coverage.statement_hits(Position::new(4, 19)), Some(0));
+    assert!(report.to_lcov().contains("SF:<script>\n"));
+
+    let _: INT = engine.call_fn(&mut Default::default(), &ast, "unused", (1 as INT,))?;
+
+    let report = engine.coverage_report().unwrap();
+    let coverage = report.get(None).unwrap();
+    assert_eq!(coverage.statement_hits(Position::new(4, 19)), Some(1));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_optimize"))]
+#[test]
+fn test_coverage_unoptimized() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(rhai::OptimizationLevel::Full);
+    engine.set_coverage(true);
+
+    // The optimizer would otherwise eliminate the dead branch
+    engine.consume("const DEBUG = false;\nif DEBUG { print(42); }")?;
+
+    let report = engine.coverage_report().unwrap();
+    let coverage = report.get(None).unwrap();
+
+    assert_eq!(coverage.statement_hits(Position::new(2, 12)), Some(0));
+    assert_eq!(
+        coverage.branch_hits(Position::new(2, 1), Position::NONE),
+        Some(1)
+    );
+
+    Ok(())
+}
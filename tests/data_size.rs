@@ -273,3 +273,118 @@ fn test_max_map_size() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_max_memory() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_max_memory(10_000);
+
+    assert_eq!(engine.max_memory(), 10_000);
+
+    // Many medium-sized strings
+    assert!(matches!(
+        *engine
+            .consume(
+                r#"
+                    let a = "x"; let b = "y"; let c = "z";
+                    for i in range(0, 100) { a += "0123456789"; b += a; c += b; }
+                "#
+            )
+            .expect_err("should error"),
+        EvalAltResult::ErrorDataTooLarge(_, _)
+    ));
+
+    // Shared strings are counted only once
+    engine.consume(
+        r#"
+            let s = "0123456789";
+            s += s; s += s; s += s; s += s; s += s;
+            let t1 = s; let t2 = s; let t3 = s; let t4 = s;
+            let t5 = s; let t6 = s; let t7 = s; let t8 = s;
+        "#,
+    )?;
+
+    #[cfg(not(feature = "no_index"))]
+    #[cfg(not(feature = "no_object"))]
+    assert!(matches!(
+        *engine
+            .consume("let x = []; for i in range(0, 1000) { x.push([i]); }")
+            .expect_err("should error"),
+        EvalAltResult::ErrorDataTooLarge(_, _)
+    ));
+    #[cfg(not(feature = "no_index"))]
+    assert!(matches!(
+        *engine
+            .consume("let x = []; for i in range(0, 1000) { push(x, [i]); }")
+            .expect_err("should error"),
+        EvalAltResult::ErrorDataTooLarge(_, _)
+    ));
+    #[cfg(not(feature = "no_index"))]
+    assert!(matches!(
+        *engine
+            .consume("let x = [[]]; for i in range(0, 1000) { x[0] += [i]; }")
+            .expect_err("should error"),
+        EvalAltResult::ErrorDataTooLarge(_, _)
+    ));
+
+    // Memory of variables which are replaced or go out of scope is released
+    engine.consume(
+        r#"
+            let x = "0123456789";
+            for i in range(0, 100) {
+                let y = x; y += y; y += y; y += y; y += y; y += y;
+                x = sub_string(y, 0, 10);
+            }
+        "#,
+    )?;
+
+    // Memory held by calling functions is counted
+    #[cfg(not(feature = "no_function"))]
+    engine.consume(
+        r#"
+            fn grow(n) { let t = "0123456789"; for i in range(0, n) { t += t; } t }
+            grow(9);
+        "#,
+    )?;
+    #[cfg(not(feature = "no_function"))]
+    assert!(matches!(
+        *engine
+            .consume(
+                r#"
+                    fn grow(n) { let t = "0123456789"; for i in range(0, n) { t += t; } t }
+                    let x = "0123456789";
+                    for i in range(0, 9) { x += x; }
+                    grow(9);
+                "#
+            )
+            .expect_err("should error"),
+        EvalAltResult::ErrorDataTooLarge(_, _)
+    ));
+
+    // Values captured in closures are counted
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    #[cfg(not(feature = "no_index"))]
+    #[cfg(not(feature = "no_object"))]
+    assert!(matches!(
+        *engine
+            .consume(
+                r#"
+                    let fns = [];
+                    for i in range(0, 100) {
+                        let s = "0123456789" + i;
+                        s += s; s += s;
+                        fns.push(|| s);
+                    }
+                "#
+            )
+            .expect_err("should error"),
+        EvalAltResult::ErrorDataTooLarge(_, _)
+    ));
+
+    engine.set_max_memory(0);
+    #[cfg(not(feature = "no_index"))]
+    engine.consume("let x = []; for i in range(0, 1000) { push(x, [i]); }")?;
+
+    Ok(())
+}
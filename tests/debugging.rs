@@ -0,0 +1,289 @@
+use rhai::{BreakPoint, DebuggerCommand, DebuggerEvent, Engine, EvalAltResult, INT};
+use std::sync::{Arc, RwLock};
+
+#[test]
+fn test_debugging_break_points() -> Result<(), Box<EvalAltResult>> {
+    let log = Arc::new(RwLock::new(Vec::<String>::new()));
+    let logger = log.clone();
+
+    let mut engine = Engine::new();
+
+    engine.register_debugger(
+        |debugger| {
+            debugger.add_break_point(BreakPoint::AtPosition {
+                source: None,
+                line: 5,
+                enabled: true,
+            });
+            debugger.add_break_point(BreakPoint::AtPosition {
+                source: Some("other".into()),
+                line: 6,
+                enabled: true,
+            });
+        },
+        move |context, event, pos| {
+            let x = context.eval_expression("x")?;
+            logger
+                .write()
+                .unwrap()
+                .push(format!("{:?} {:?} x={}", event, pos, x));
+            Ok(DebuggerCommand::Continue)
+        },
+    );
+
+    let mut ast = engine.compile(
+        r"
+            let x = 0;
+            while x < 2 {
+                x += 1;
+                x += 10;
+                x -= 10;
+            }
+        ",
+    )?;
+    ast.set_source(Some("test"));
+
+    engine.consume_ast(&ast)?;
+
+    assert_eq!(
+        *log.read().unwrap(),
+        vec!["BreakPoint(0) 5:19 x=1", "BreakPoint(0) 5:19 x=2"]
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_debugging_stepping() -> Result<(), Box<EvalAltResult>> {
+    let log = Arc::new(RwLock::new(Vec::<(usize, usize)>::new()));
+    let logger = log.clone();
+
+    let mut engine = Engine::new();
+
+    engine.register_debugger(
+        |debugger| {
+            debugger.break_at_next_statement();
+        },
+        move |context, _, pos| {
+            let mut log = logger.write().unwrap();
+            log.push((pos.line().unwrap(), context.call_level()));
+
+            Ok(match log.len() {
+                // Step over the call to `foo` at line 6
+                1 => DebuggerCommand::StepOver,
+                // Step into the call to `foo` at line 7
+                2 => DebuggerCommand::StepInto,
+                // Step out of `foo`
+                3 => DebuggerCommand::StepOut,
+                _ => DebuggerCommand::Continue,
+            })
+        },
+    );
+
+    engine.consume(
+        r"
+            fn foo(x) {
+                let y = x + 1;
+                y * 2
+            }
+            let a = foo(1);
+            let b = foo(a);
+            let c = a + b;
+            c
+        ",
+    )?;
+
+    assert_eq!(*log.read().unwrap(), vec![(6, 0), (7, 0), (3, 1), (8, 0)]);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_debugging_call_stack() -> Result<(), Box<EvalAltResult>> {
+    let log = Arc::new(RwLock::new(Vec::<String>::new()));
+    let logger = log.clone();
+
+    let mut engine = Engine::new();
+
+    engine.register_debugger(
+        |debugger| {
+            debugger.add_break_point(BreakPoint::AtFunctionName {
+                name: "bar".into(),
+                enabled: true,
+            });
+        },
+        move |context, event, _| {
+            assert!(matches!(event, DebuggerEvent::BreakPoint(0)));
+
+            let debugger = context.debugger().unwrap();
+            let mut log = logger.write().unwrap();
+
+            log.extend(debugger.call_stack().iter().map(|f| f.to_string()));
+
+            // The caller's scope is a copy
+            let frame = &debugger.call_stack()[0];
+            log.push(format!("{:?}", frame.scope.get_value::<INT>("z")));
+
+            // Evaluate in the live scope of `bar`
+            let x = context.eval_expression("x * 100")?;
+            log.push(x.to_string());
+
+            Ok(DebuggerCommand::Continue)
+        },
+    );
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn bar(x) { x + 1 }
+                fn foo(x) { let z = x * 2; bar(z) }
+                foo(21)
+            "
+        )?,
+        43
+    );
+
+    assert_eq!(
+        *log.read().unwrap(),
+        vec![
+            "foo(21) (line 4, position 17)",
+            "bar(42) (line 3, position 44)",
+            "Some(42)",
+            "4200"
+        ]
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_debugging_native_callbacks() -> Result<(), Box<EvalAltResult>> {
+    let log = Arc::new(RwLock::new(Vec::<String>::new()));
+    let logger = log.clone();
+    let inits = Arc::new(RwLock::new(0));
+    let counter = inits.clone();
+
+    let mut engine = Engine::new();
+
+    engine.register_debugger(
+        move |debugger| {
+            *counter.write().unwrap() += 1;
+            debugger.add_break_point(BreakPoint::AtFunctionName {
+                name: "bar".into(),
+                enabled: true,
+            });
+        },
+        move |context, _, pos| {
+            let debugger = context.debugger().unwrap();
+            let frames: Vec<_> = debugger
+                .call_stack()
+                .iter()
+                .map(|f| f.fn_name.to_string())
+                .collect();
+
+            // The scope of `foo` is copied before calling `map`
+            assert!(debugger.call_stack()[0].scope.contains("a"));
+
+            logger.write().unwrap().push(format!(
+                "{} {} {}",
+                pos.line().unwrap(),
+                frames.len(),
+                frames.last().unwrap()
+            ));
+
+            // Step into the closure called back from `map`
+            Ok(DebuggerCommand::StepInto)
+        },
+    );
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn bar(x) { x + 1 }
+                fn foo(a) {
+                    a.map(|x| bar(x))
+                }
+                foo([1, 2]).len()
+            "
+        )?,
+        2
+    );
+
+    // The debugger is shared with functions called back from native functions
+    assert_eq!(*inits.read().unwrap(), 1);
+
+    // Break-points and stepping also apply within functions called back from native functions
+    let log = log.read().unwrap();
+    assert_eq!(log.len(), 3);
+    assert_eq!(log[0], "2 3 bar");
+    assert!(log[1].starts_with("4 2 anon$"));
+    assert_eq!(log[2], "2 3 bar");
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_debugging_errors() -> Result<(), Box<EvalAltResult>> {
+    let log = Arc::new(RwLock::new(Vec::<String>::new()));
+    let logger = log.clone();
+
+    let mut engine = Engine::new();
+
+    engine.register_debugger(
+        |debugger| {
+            debugger.set_break_on_error(true);
+        },
+        move |_, event, pos| {
+            if let DebuggerEvent::Error(err) = event {
+                logger.write().unwrap().push(format!("{} @ {:?}", err, pos));
+            }
+
+            Ok(DebuggerCommand::Continue)
+        },
+    );
+
+    engine.consume(
+        r#"
+            try {
+                throw "oops";
+            } catch (err) {
+                print(err);
+            }
+        "#,
+    )?;
+
+    assert!(matches!(
+        *engine
+            .consume("let x = 1;\nx.foo();")
+            .expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(_, _)
+    ));
+
+    assert_eq!(log.read().unwrap().len(), 2);
+    assert_eq!(
+        log.read().unwrap()[0],
+        "Runtime error: oops (line 3, position 17) @ 3:17"
+    );
+    assert!(log.read().unwrap()[1].starts_with("Function not found: foo"));
+
+    // The debugger can abort execution
+    engine.register_debugger(
+        |debugger| {
+            debugger.break_at_next_statement();
+        },
+        |_, _, pos| Err(EvalAltResult::ErrorTerminated("stopped".into(), pos).into()),
+    );
+
+    assert!(matches!(
+        *engine.consume("let x = 1;").expect_err("should error"),
+        EvalAltResult::ErrorTerminated(_, _)
+    ));
+
+    Ok(())
+}
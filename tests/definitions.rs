@@ -0,0 +1,98 @@
+#![cfg(feature = "metadata")]
+
+use rhai::plugin::*;
+use rhai::{DefinitionsStyle, Engine, Module, INT};
+
+#[export_module]
+mod math_module {
+    use rhai::INT;
+
+    pub const LIMIT: INT = 100;
+
+    /// Add two numbers.
+    pub fn add(x: INT, y: INT) -> INT {
+        x + y
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Point {
+    x: INT,
+}
+
+fn make_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine
+        .register_type_with_name::<Point>("Point")
+        .register_fn("new_point", |x: INT| Point { x })
+        .register_get_set(
+            "x",
+            |p: &mut Point| p.x,
+            |p: &mut Point, value: INT| p.x = value,
+        )
+        .register_fn("shift", |p: &mut Point, dx: INT| p.x += dx);
+
+    engine.register_static_module("Math", exported_module!(math_module).into());
+
+    engine
+}
+
+#[test]
+fn test_definitions_rhai() {
+    let engine = make_engine();
+    let output = engine.definitions().include_packages(false).write_rhai();
+
+    assert!(output.starts_with("module static;\n"));
+    assert!(output.contains("fn new_point(_: int) -> ?;"));
+    assert!(output
+        .contains("type Point {\n    get x: ?;\n    set x: int;\n    fn shift(_: int) -> ?;\n}"));
+    assert!(output.contains("module Math {\n"));
+    assert!(output.contains("    const LIMIT: int;"));
+    assert!(output.contains("    /// Add two numbers.\n    fn add(x: int, y: int) -> int;"));
+
+    // Functions in packages are not included
+    assert!(!output.contains("fn abs("));
+    assert!(engine.definitions().write_rhai().contains("fn abs("));
+}
+
+#[test]
+fn test_definitions_typescript() {
+    let engine = make_engine();
+    let output = engine
+        .definitions()
+        .include_packages(false)
+        .write(DefinitionsStyle::TypeScript);
+
+    assert!(
+        output.contains("declare interface Point {\n    x: any;\n    shift(_: number): any;\n}")
+    );
+    assert!(output.contains("declare function new_point(_: number): any;"));
+    assert!(output.contains("declare namespace Math {\n"));
+    assert!(output.contains("    const LIMIT: number;"));
+    assert!(output
+        .contains("    /// Add two numbers.\n    function add(x: number, y: number): number;"));
+}
+
+#[test]
+fn test_definitions_custom_module() {
+    let mut engine = Engine::new();
+
+    let mut module = Module::new();
+    module.set_var("NAME", "hello");
+    module.set_fn_with_comments(
+        "greet",
+        FnNamespace::Internal,
+        FnAccess::Public,
+        Some(&["name: &str", "String"]),
+        &[std::any::TypeId::of::<ImmutableString>()],
+        &["/// Greet someone."],
+        CallableFunction::from_pure(Box::new(|_, args| Ok(args[0].clone()))),
+    );
+    engine.register_static_module("Greetings", module.into());
+
+    let output = engine.definitions().include_packages(false).write_rhai();
+
+    assert!(output.contains("    const NAME: string;"));
+    assert!(output.contains("    /// Greet someone.\n    fn greet(name: string) -> string;"));
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "metadata")]
+
+use rhai::plugin::*;
+use rhai::{Engine, EvalAltResult, Module, INT};
+use serde_json::Value;
+
+#[export_module]
+mod doc_module {
+    use rhai::INT;
+
+    /// Add two numbers.
+    ///
+    /// Returns the sum.
+    pub fn add(x: INT, y: INT) -> INT {
+        x + y
+    }
+
+    pub fn no_docs(x: INT) -> INT {
+        x
+    }
+}
+
+#[export_fn]
+/** Multiply
+    two numbers. */
+fn multiply(x: INT, y: INT) -> INT {
+    x * y
+}
+
+fn find_fn<'a>(functions: &'a Value, name: &str) -> &'a Value {
+    functions
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["name"] == name)
+        .expect("function should exist")
+}
+
+#[test]
+fn test_metadata_doc_comments() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let mut module = exported_module!(doc_module);
+    set_exported_fn!(module, "multiply", multiply);
+
+    let hash = module.set_fn_with_comments(
+        "manual",
+        FnNamespace::Internal,
+        FnAccess::Public,
+        None,
+        &[],
+        &["/// Manually documented."],
+        CallableFunction::from_pure(Box::new(|_, _| Ok(Dynamic::UNIT))),
+    );
+    let hash2 = module.set_fn_0("updated", || Ok(42 as INT));
+    module.update_fn_metadata_with_comments(hash2, &["INT"], &["/// Updated later."]);
+    assert_ne!(hash, hash2);
+
+    engine.register_static_module("docs", module.into());
+
+    let json = engine.gen_fn_metadata_to_json(false).unwrap();
+    let metadata: Value = serde_json::from_str(&json).unwrap();
+    let functions = &metadata["modules"]["docs"]["functions"];
+
+    assert_eq!(
+        find_fn(functions, "add")["docComments"],
+        serde_json::json!(["/// Add two numbers.", "///", "/// Returns the sum."])
+    );
+    assert_eq!(
+        find_fn(functions, "multiply")["docComments"],
+        serde_json::json!(["/** Multiply\n    two numbers. */"])
+    );
+    assert_eq!(
+        find_fn(functions, "manual")["docComments"],
+        serde_json::json!(["/// Manually documented."])
+    );
+    assert_eq!(
+        find_fn(functions, "updated")["docComments"],
+        serde_json::json!(["/// Updated later."])
+    );
+    assert!(find_fn(functions, "no_docs").get("docComments").is_none());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_metadata_script_doc_comments() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let ast = engine.compile(
+        "
+            /// Say hello.
+            fn hello(name) { name }
+        ",
+    )?;
+
+    let json = engine
+        .gen_fn_metadata_with_ast_to_json(&ast, false)
+        .unwrap();
+    let metadata: Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(
+        find_fn(&metadata["functions"], "hello")["docComments"],
+        serde_json::json!(["/// Say hello."])
+    );
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use rhai::{
+    CallableFunction, Dynamic, Engine, EvalAltResult, ImmutableString, Position, ResolvedFn, INT,
+};
+use std::any::TypeId;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn make_engine(calls: Arc<AtomicUsize>) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.on_missing_fn(move |name, namespace, arg_types, _| {
+        calls.fetch_add(1, Ordering::Relaxed);
+
+        match (name, namespace, arg_types) {
+            ("double", None, [t]) if *t == TypeId::of::<INT>() => {
+                let func = CallableFunction::from_pure(Box::new(|_, args| {
+                    Ok((args[0].as_int().unwrap() * 2).into())
+                }));
+                Ok(Some(ResolvedFn::Function(func)))
+            }
+            ("len_of", Some("remote"), [t]) if *t == TypeId::of::<ImmutableString>() => {
+                let func = CallableFunction::from_pure(Box::new(|_, args| {
+                    Ok((args[0].as_str().unwrap().len() as INT).into())
+                }));
+                Ok(Some(ResolvedFn::Function(func)))
+            }
+            ("answer", _, []) => Ok(Some(ResolvedFn::Value((42 as INT).into()))),
+            ("items", _, _) => {
+                let iter: fn(Dynamic) -> Box<dyn Iterator<Item = Dynamic>> =
+                    |_| Box::new(std::iter::empty());
+                Ok(Some(ResolvedFn::Function(iter.into())))
+            }
+            ("broken", _, _) => Err(EvalAltResult::ErrorRuntime(
+                "service unavailable".into(),
+                Position::NONE,
+            )
+            .into()),
+            _ => Ok(None),
+        }
+    });
+
+    engine
+}
+
+#[test]
+fn test_missing_fn() -> Result<(), Box<EvalAltResult>> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = make_engine(calls.clone());
+
+    // The resolved function is cached for the rest of the evaluation
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; for i in range(0, 5) { x += double(i); } x")?,
+        20
+    );
+    assert_eq!(calls.swap(0, Ordering::Relaxed), 1);
+
+    #[cfg(not(feature = "no_object"))]
+    {
+        assert_eq!(engine.eval::<INT>("let x = 21; x.double()")?, 42);
+        assert_eq!(calls.swap(0, Ordering::Relaxed), 1);
+
+        // Property getters are not resolved
+        assert!(engine.eval::<INT>("let x = 21; x.answer").is_err());
+        assert_eq!(calls.swap(0, Ordering::Relaxed), 0);
+    }
+
+    // Values are used directly
+    assert_eq!(engine.eval::<INT>("answer() + answer()")?, 84);
+    assert_eq!(calls.swap(0, Ordering::Relaxed), 2);
+
+    // Unresolved functions are still not found
+    assert!(matches!(
+        *engine.eval::<INT>(r#"double("hello")"#).expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("double (")
+    ));
+
+    // Only native Rust functions can be resolved
+    let err = engine.eval::<INT>("items(1)").expect_err("should error");
+    assert!(matches!(*err, EvalAltResult::ErrorRuntime(_, _)));
+    assert!(err
+        .to_string()
+        .contains("'items' cannot be resolved to an iterator"));
+
+    let err = engine
+        .eval::<INT>("let x = 1;\nbroken(x)")
+        .expect_err("should error");
+    assert!(matches!(*err, EvalAltResult::ErrorRuntime(_, _)));
+    assert_eq!(err.position(), Position::new(2, 1));
+
+    // Registered and built-in functions do not invoke the callback
+    calls.store(0, Ordering::Relaxed);
+    assert_eq!(engine.eval::<INT>("let x = 40; x + abs(-2)")?, 42);
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_module"))]
+#[test]
+fn test_missing_fn_namespace() -> Result<(), Box<EvalAltResult>> {
+    use rhai::Module;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut engine = make_engine(calls.clone());
+
+    engine.register_static_module("remote", Module::new().into());
+
+    assert_eq!(
+        engine.eval::<INT>(r#"remote::len_of("hello") + remote::len_of("world!")"#)?,
+        11
+    );
+    assert_eq!(calls.swap(0, Ordering::Relaxed), 1);
+
+    assert_eq!(engine.eval::<INT>("remote::answer()")?, 42);
+
+    assert!(matches!(
+        *engine.eval::<INT>("remote::double(21)").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("remote::double (")
+    ));
+
+    Ok(())
+}
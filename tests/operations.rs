@@ -1,5 +1,5 @@
 #![cfg(not(feature = "unchecked"))]
-use rhai::{Engine, EvalAltResult, INT};
+use rhai::{Engine, EvalAltResult, EvalOptions, INT};
 
 #[test]
 fn test_max_operations() -> Result<(), Box<EvalAltResult>> {
@@ -134,3 +134,366 @@ fn test_max_operations_progress() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_max_duration() -> Result<(), Box<EvalAltResult>> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // A fake clock that advances by one millisecond every time it is read
+    let ticks = Arc::new(AtomicU64::new(0));
+    let clock = ticks.clone();
+
+    let mut engine = Engine::new();
+    engine
+        .set_clock(move || Duration::from_millis(clock.fetch_add(1, Ordering::Relaxed)))
+        .set_max_duration(Duration::from_millis(50));
+
+    assert_eq!(engine.max_duration(), Some(Duration::from_millis(50)));
+
+    engine.eval::<()>("let x = 0; while x < 5 { x += 1; }")?;
+
+    assert!(matches!(
+        *engine
+            .eval::<()>("let x = 0; loop { x += 1; }")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTimeout(elapsed, _) if elapsed == Duration::from_millis(51)
+    ));
+
+    // Timeouts cannot be caught
+    assert!(matches!(
+        *engine
+            .eval::<()>("let x = 0; try { loop { x += 1; } } catch { }")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTimeout(_, _)
+    ));
+
+    // Per-call timeouts override the engine's limit
+    let ast = engine.compile("for x in range(0, 100) {}")?;
+
+    engine.eval_ast_with_scope_and_options::<()>(
+        &mut Default::default(),
+        &ast,
+        EvalOptions::new().with_timeout(Duration::from_secs(1)),
+    )?;
+    assert!(matches!(
+        *engine
+            .eval_ast_with_scope_and_options::<()>(
+                &mut Default::default(),
+                &ast,
+                EvalOptions::new().with_timeout(Duration::from_millis(5)),
+            )
+            .expect_err("should error"),
+        EvalAltResult::ErrorTimeout(elapsed, _) if elapsed == Duration::from_millis(6)
+    ));
+
+    #[cfg(not(feature = "no_function"))]
+    {
+        let ast = engine.compile("fn spin(n) { for x in range(0, n) {} n }")?;
+
+        assert_eq!(
+            engine.call_fn_with_options::<INT>(
+                &mut Default::default(),
+                &ast,
+                "spin",
+                (100 as INT,),
+                EvalOptions::new().with_timeout(Duration::from_secs(1)),
+            )?,
+            100
+        );
+        assert!(matches!(
+            *engine
+                .call_fn_with_options::<INT>(
+                    &mut Default::default(),
+                    &ast,
+                    "spin",
+                    (100 as INT,),
+                    EvalOptions::new().with_timeout(Duration::from_millis(5)),
+                )
+                .expect_err("should error"),
+            EvalAltResult::ErrorTimeout(_, _)
+        ));
+    }
+
+    // Functions called back from native functions run within the same deadline
+    #[cfg(not(feature = "no_index"))]
+    #[cfg(not(feature = "no_object"))]
+    #[cfg(not(feature = "no_function"))]
+    assert!(matches!(
+        *engine
+            .eval::<()>("[1].map(|x| { loop {} });")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTimeout(_, _)
+    ));
+
+    engine.set_max_duration(Duration::from_secs(0));
+    assert_eq!(engine.max_duration(), None);
+
+    let before = ticks.load(Ordering::Relaxed);
+    engine.eval::<()>("for x in range(0, 1000) {}")?;
+    assert_eq!(ticks.load(Ordering::Relaxed), before);
+
+    Ok(())
+}
+
+#[test]
+fn test_interrupt_handle() -> Result<(), Box<EvalAltResult>> {
+    use rhai::RegisterFn;
+
+    let mut engine = Engine::new();
+    let handle = engine.interrupt_handle();
+
+    assert!(!handle.is_interrupted());
+    assert!(handle.reason().is_none());
+
+    let cancel = engine.interrupt_handle();
+    engine.register_fn("cancel", move |reason: &str| cancel.interrupt(reason));
+
+    let result = engine.eval::<INT>("let x = 0; loop { x += 1; if x == 10 { cancel(\"stop\"); } }");
+
+    assert!(matches!(
+        *result.expect_err("should error"),
+        EvalAltResult::ErrorTerminated(reason, _) if reason.to_string() == "stop"
+    ));
+    assert!(handle.is_interrupted());
+    assert_eq!(handle.reason().unwrap().to_string(), "stop");
+
+    // Cannot be caught
+    handle.reset();
+    assert!(engine
+        .eval::<INT>("try { cancel(\"again\"); 1 } catch { 2 }")
+        .is_err());
+
+    handle.reset();
+    assert!(!handle.is_interrupted());
+    assert_eq!(engine.eval::<INT>("40 + 2")?, 42);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_interrupt_handle_native_callbacks() -> Result<(), Box<EvalAltResult>> {
+    use rhai::{FnPtr, RegisterFn};
+    use std::any::TypeId;
+
+    let mut engine = Engine::new();
+
+    let cancel = engine.interrupt_handle();
+    engine.register_fn("cancel", move || cancel.interrupt(()));
+
+    #[allow(deprecated)]
+    engine.register_raw_fn("apply", &[TypeId::of::<FnPtr>()], |context, args| {
+        let fn_ptr = std::mem::take(args[0]).cast::<FnPtr>();
+        fn_ptr.call_dynamic(context, None, [])
+    });
+
+    let result = engine.eval::<INT>(
+        r#"
+            fn spin() { let x = 0; loop { x += 1; if x == 10 { cancel(); } } }
+            apply(Fn("spin"))
+        "#,
+    );
+
+    assert!(matches!(
+        *result.expect_err("should error"),
+        EvalAltResult::ErrorTerminated(_, _)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_interrupt_handle_per_evaluation() -> Result<(), Box<EvalAltResult>> {
+    use rhai::{InterruptHandle, RegisterFn, Scope};
+
+    let mut engine = Engine::new();
+    let handle = InterruptHandle::new();
+
+    let cancel = handle.clone();
+    engine.register_fn("cancel", move || cancel.interrupt(42 as INT));
+
+    let options = || EvalOptions::new().with_interrupt(handle.clone());
+
+    let ast = engine.compile("let x = 0; loop { x += 1; if x == 10 { cancel(); } }")?;
+
+    assert!(matches!(
+        *engine
+            .eval_ast_with_scope_and_options::<()>(&mut Scope::new(), &ast, options())
+            .expect_err("should error"),
+        EvalAltResult::ErrorTerminated(reason, _) if reason.as_int().unwrap() == 42
+    ));
+
+    // Only evaluations started with the handle are terminated
+    assert!(!engine.interrupt_handle().is_interrupted());
+    assert_eq!(engine.eval::<INT>("40 + 2")?, 42);
+
+    let ast = engine.compile("40 + 2")?;
+    assert!(engine
+        .eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options())
+        .is_err());
+
+    handle.reset();
+    assert_eq!(
+        engine.eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options())?,
+        42
+    );
+
+    // Also applies to `eval` and to functions called back from native functions
+    let ast = engine.compile(r#"eval("cancel(); 0")"#)?;
+    assert!(engine
+        .eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options())
+        .is_err());
+    handle.reset();
+
+    #[cfg(not(feature = "no_index"))]
+    #[cfg(not(feature = "no_object"))]
+    #[cfg(not(feature = "no_function"))]
+    {
+        let ast = engine.compile("[1, 2].map(|x| { cancel(); x }); 0")?;
+        assert!(matches!(
+            *engine
+                .eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options())
+                .expect_err("should error"),
+            EvalAltResult::ErrorTerminated(_, _)
+        ));
+        handle.reset();
+
+        let ast =
+            engine.compile("fn spin(n) { let x = 0; loop { x += 1; if x == n { cancel(); } } }")?;
+        assert!(engine
+            .call_fn_with_options::<()>(&mut Scope::new(), &ast, "spin", (10 as INT,), options())
+            .is_err());
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn test_interrupt_handle_threads() -> Result<(), Box<EvalAltResult>> {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    let engine = Arc::new(Engine::new());
+    let handle = engine.interrupt_handle();
+
+    let runner = engine.clone();
+    let worker = thread::spawn(move || runner.consume("let x = 0; loop { x += 1; }"));
+
+    thread::sleep(Duration::from_millis(10));
+    handle.interrupt(42 as INT);
+
+    assert!(matches!(
+        *worker.join().unwrap().expect_err("should error"),
+        EvalAltResult::ErrorTerminated(reason, _) if reason.as_int().unwrap() == 42
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_fn_cost() -> Result<(), Box<EvalAltResult>> {
+    use rhai::{FnAccess, FnNamespace, ImmutableString, Module, RegisterFn};
+    use std::any::TypeId;
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(500);
+
+    engine.register_fn("query", || 42 as INT);
+    engine.set_fn_cost("query", 100);
+
+    assert_eq!(engine.eval::<INT>("query() + query()")?, 84);
+    assert!(matches!(
+        *engine
+            .eval::<INT>("query() + query() + query() + query() + query()")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    engine.set_fn_cost("query", 0);
+    engine.eval::<INT>("query() + query() + query() + query() + query()")?;
+
+    let mut module = Module::new();
+    let hash = module.set_fn_1("double", |x: INT| Ok(x * 2));
+    module.update_fn_cost(hash, 100).update_fn_cost(hash, 300);
+    engine.register_global_module(module.into());
+
+    assert_eq!(engine.eval::<INT>("double(21)")?, 42);
+    assert!(matches!(
+        *engine
+            .eval::<INT>("double(double(21))")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    let mut module = Module::new();
+    module.set_raw_fn(
+        "lookup",
+        FnNamespace::Global,
+        FnAccess::Public,
+        &[TypeId::of::<ImmutableString>()],
+        |context, args| {
+            let len = args[0].clone().cast::<ImmutableString>().len();
+            context.add_cost(len as u64 * 10)?;
+            Ok(len as INT)
+        },
+    );
+    engine.register_global_module(module.into());
+
+    assert_eq!(engine.eval::<INT>(r#"lookup("hello")"#)?, 5);
+    assert!(matches!(
+        *engine
+            .eval::<INT>(r#"lookup("hello, world! hello, world! hello, world! hello, world!")"#)
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_fn_cost_array() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(500);
+
+    assert_eq!(
+        engine.eval::<INT>("let x = [1, 2, 3].map(|v| v * 2); x[2]")?,
+        6
+    );
+
+    // The cost of the array length is counted even if the mapper is trivial
+    assert!(matches!(
+        *engine
+            .consume("let x = []; x.pad(400, 0); x.map(|v| v);")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    // Operations performed in callbacks are counted
+    assert!(matches!(
+        *engine
+            .consume("let x = [1, 2, 3]; x.map(|v| { let y = 0; for i in range(0, 1000) { y += i; } y });")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    // The cost of sorting grows as n·log(n)
+    assert_eq!(
+        engine.eval::<INT>("let x = [3, 1, 2]; x.sort(|a, b| a - b); x[0]")?,
+        1
+    );
+    assert!(matches!(
+        *engine
+            .consume("let x = []; x.pad(64, 0); x.sort(|a, b| 0);")
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,305 @@
+use rhai::{Engine, EvalAltResult, ParseErrorType, Position, Scope, INT};
+
+#[test]
+fn test_options_expressions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.set_allow_if_expression(false);
+    assert!(!engine.allow_if_expression());
+
+    assert_eq!(engine.eval::<INT>("let x = 1; if x > 0 { x = 42; } x")?, 42);
+
+    let err = engine
+        .compile("let x = if true { 42 } else { 0 };")
+        .expect_err("should error");
+    assert_eq!(
+        *err.0,
+        ParseErrorType::FeatureDisabled("if-expressions".into())
+    );
+    assert_eq!(err.1, Position::new(1, 9));
+    assert_eq!(
+        err.to_string(),
+        "Use of if-expressions is disabled (line 1, position 9)"
+    );
+
+    engine.set_allow_switch_expression(false);
+
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; switch 2 { 1 => x = 1, 2 => x = 42, _ => () } x")?,
+        42
+    );
+    assert!(matches!(
+        *engine
+            .compile("let x = switch 2 { 1 => 1, _ => 0 };")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FeatureDisabled(_)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_options_looping() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.set_allow_looping(false);
+
+    for script in &[
+        "let x = 0; while x < 10 { x += 1; }",
+        "let x = 0; loop { x += 1; }",
+        "let x = 0; do { x += 1; } while x < 10;",
+        "let x = 0; for i in range(0, 10) { x += i; }",
+    ] {
+        let err = engine.compile(script).expect_err("should error");
+        assert_eq!(*err.0, ParseErrorType::FeatureDisabled("loops".into()));
+        assert_eq!(err.1, Position::new(1, 12));
+    }
+
+    engine.set_allow_looping(true);
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; while x < 10 { x += 1; } x")?,
+        10
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_options_shadowing() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert_eq!(engine.eval::<INT>("let x = 40; let x = x + 2; x")?, 42);
+
+    engine.set_allow_shadowing(false);
+
+    let err = engine
+        .compile("let x = 40; let x = x + 2; x")
+        .expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::VariableExists("x".into()));
+    assert_eq!(err.1, Position::new(1, 17));
+
+    assert!(matches!(
+        *engine
+            .compile("let x = 40; { const x = 2; }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::VariableExists(_)
+    ));
+    assert!(matches!(
+        *engine
+            .compile("let i = 0; for i in range(0, 10) { }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::VariableExists(_)
+    ));
+
+    // Variables in sibling blocks do not shadow each other
+    assert_eq!(
+        engine.eval::<INT>("let y = 0; { let x = 40; y += x; } { let x = 2; y += x; } y")?,
+        42
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_options_eval() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.set_allow_eval(false);
+
+    let err = engine
+        .compile(r#"let x = 1; eval("x + 41")"#)
+        .expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::FeatureDisabled("'eval'".into()));
+    assert_eq!(err.1, Position::new(1, 12));
+
+    engine.set_allow_eval(true);
+    assert_eq!(engine.eval::<INT>(r#"let x = 1; eval("x + 41")"#)?, 42);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_options_functions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.set_allow_functions(false);
+
+    let err = engine
+        .compile("fn foo(x) { x + 1 } foo(41)")
+        .expect_err("should error");
+    assert_eq!(
+        *err.0,
+        ParseErrorType::FeatureDisabled("function definitions".into())
+    );
+    assert_eq!(err.1, Position::new(1, 1));
+    assert!(matches!(
+        *engine
+            .compile("private fn foo(x) { x + 1 }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FeatureDisabled(_)
+    ));
+
+    // Anonymous functions are controlled separately
+    assert_eq!(engine.eval::<INT>("let f = |x| x + 1; f.call(41)")?, 42);
+
+    engine.set_allow_functions(true);
+    engine.set_allow_anonymous_fn(false);
+
+    assert_eq!(engine.eval::<INT>("fn foo(x) { x + 1 } foo(41)")?, 42);
+
+    let err = engine
+        .compile("let f = |x| x + 1;")
+        .expect_err("should error");
+    assert_eq!(
+        *err.0,
+        ParseErrorType::FeatureDisabled("anonymous functions".into())
+    );
+    assert_eq!(err.1, Position::new(1, 9));
+
+    engine.set_allow_anonymous_fn(true);
+    engine.set_allow_implicit_this(false);
+
+    assert!(matches!(
+        *engine
+            .compile("fn inc() { this += 1; }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FeatureDisabled(_)
+    ));
+    assert_eq!(engine.eval::<INT>("fn inc(x) { x + 1 } inc(41)")?, 42);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_module"))]
+#[test]
+fn test_options_import() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.set_allow_import(false);
+
+    let err = engine
+        .compile(r#"let x = 1; import "hello" as h;"#)
+        .expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::FeatureDisabled("'import'".into()));
+    assert_eq!(err.1, Position::new(1, 12));
+
+    Ok(())
+}
+
+#[test]
+fn test_options_strict_var() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert!(engine.compile("let x = if y { z } else { w };").is_ok());
+
+    engine.set_strict_variables(true);
+    assert!(engine.strict_variables());
+
+    let err = engine
+        .compile("let x = 40; y + 2")
+        .expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::VariableUndefined("y".into()));
+    assert_eq!(err.1, Position::new(1, 13));
+    assert_eq!(
+        err.to_string(),
+        "Variable 'y' is not defined (line 1, position 13)"
+    );
+
+    let mut scope = Scope::new();
+    scope.push("y", 0 as INT);
+
+    assert!(engine
+        .compile_with_scope(&scope, "let x = 40; y + 2")
+        .is_ok());
+    assert_eq!(engine.eval_with_scope::<INT>(&mut scope, "y = 42; y")?, 42);
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; for i in range(0, 5) { x += i; } x")?,
+        10
+    );
+    assert_eq!(
+        engine.eval::<INT>(r#"let x = 0; try { throw 42; } catch (err) { x = err; } x"#)?,
+        42
+    );
+    assert!(engine.compile("{ let x = 42; } x").is_err());
+
+    // Scripts passed to eval see the variables in scope
+    assert_eq!(engine.eval::<INT>(r#"let x = 40; eval("x + 2")"#)?, 42);
+
+    #[cfg(not(feature = "no_object"))]
+    assert_eq!(engine.eval::<INT>("let x = #{ a: 42 }; x.a")?, 42);
+
+    #[cfg(not(feature = "no_module"))]
+    {
+        use rhai::{module_resolvers::StaticModuleResolver, Module};
+
+        let mut module = Module::new();
+        module.set_var("answer", 42 as INT);
+        let mut resolver = StaticModuleResolver::new();
+        resolver.insert("hello", module);
+        engine.set_module_resolver(resolver);
+
+        assert_eq!(engine.eval::<INT>(r#"import "hello" as h; h::answer"#)?, 42);
+    }
+
+    // Externally resolved variables
+    engine.on_var(|name, _, _| match name {
+        "MYSTIC_NUMBER" => Ok(Some((42 as INT).into())),
+        _ => Ok(None),
+    });
+    assert!(engine.compile("MYSTIC_NUMBER").is_err());
+
+    engine.register_external_var("MYSTIC_NUMBER");
+    assert_eq!(engine.eval::<INT>("MYSTIC_NUMBER")?, 42);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_options_strict_var_functions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.set_strict_variables(true);
+
+    assert_eq!(
+        engine.eval::<INT>("fn foo(x) { let y = x + 1; y } foo(41)")?,
+        42
+    );
+
+    // Global variables are not visible inside functions
+    assert!(matches!(
+        *engine
+            .compile("let y = 1; fn foo(x) { x + y }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::VariableUndefined(_)
+    ));
+
+    #[cfg(not(feature = "no_closure"))]
+    {
+        assert_eq!(
+            engine.eval::<INT>("let y = 40; let f = |x| x + y; f.call(2)")?,
+            42
+        );
+
+        let err = engine
+            .compile("let f = |x| x + y;")
+            .expect_err("should error");
+        assert_eq!(*err.0, ParseErrorType::VariableUndefined("y".into()));
+        assert_eq!(err.1, Position::new(1, 17));
+
+        assert!(engine
+            .compile("let y = 0; let f = |x| { let g = |z| x + y + z + w; g };")
+            .is_err());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,191 @@
+#![cfg(not(feature = "no_std"))]
+use rhai::{Engine, EvalAltResult, RegisterFn, INT};
+
+#[test]
+fn test_profiling_disabled() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert!(!engine.is_profiling());
+    engine.consume("let x = 1 + 2;")?;
+    assert!(engine.profile_report().is_none());
+
+    engine.set_profiling(true);
+    assert!(engine.profile_report().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_profiling_functions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine
+        .register_fn("work", |x: INT| (0..x).sum::<INT>())
+        .set_profiling(true);
+
+    let script = r"
+        fn inner(x) { work(x) }
+        fn outer(n) {
+            let total = 0;
+            for i in range(0, n) { total += inner(i); }
+            total
+        }
+        outer(5) + outer(3)
+    ";
+
+    assert_eq!(engine.eval::<INT>(script)?, 11);
+
+    let report = engine.profile_report().unwrap();
+
+    let outer = report.find("outer").next().unwrap();
+    let inner = report.find("inner").next().unwrap();
+    let work = report.find("work").next().unwrap();
+
+    assert!(!outer.is_native);
+    assert!(work.is_native);
+
+    assert_eq!(outer.calls, 2);
+    assert_eq!(inner.calls, 8);
+    assert_eq!(work.calls, 8);
+    assert_eq!(report.get(work.hash), Some(work));
+
+    assert!(outer.exclusive_time <= outer.inclusive_time);
+    assert!(inner.inclusive_time <= outer.inclusive_time);
+    assert!(outer.exclusive_operations < outer.inclusive_operations);
+    assert!(inner.inclusive_operations < outer.inclusive_operations);
+    assert_eq!(
+        outer.inclusive_operations,
+        outer.exclusive_operations
+            + report.find("range").next().unwrap().inclusive_operations
+            + inner.inclusive_operations
+    );
+
+    let stacks = report.folded_stacks();
+    let lines: Vec<_> = stacks.lines().collect();
+    assert!(lines.iter().any(|l| l.starts_with("<main>;outer ")));
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with("<main>;outer;inner;work ")));
+    assert!(lines
+        .iter()
+        .all(|l| l.rsplit(' ').next().unwrap().parse::<u128>().is_ok()));
+
+    // Results accumulate across evaluations
+    engine.eval::<INT>(script)?;
+    let report = engine.profile_report().unwrap();
+    assert_eq!(report.find("outer").next().unwrap().calls, 4);
+    assert!(report.to_string().contains("outer (script)"));
+
+    engine.clear_profile_report();
+    assert!(engine.profile_report().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_profiling_recursion() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_profiling(true);
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }
+                fib(6)
+            "
+        )?,
+        8
+    );
+
+    let report = engine.profile_report().unwrap();
+    let fib = report.find("fib").next().unwrap();
+
+    assert_eq!(fib.calls, 25);
+    assert!(report.folded_stacks().contains("<main>;fib;fib;fib "));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_profiling_callbacks() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine
+        .register_fn("work", |x: INT| (0..x).sum::<INT>())
+        .set_profiling(true);
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn slow(x) { let y = 0; for i in range(0, x) { y += work(i); } y }
+                let a = [10, 20, 30].map(|x| slow(x));
+                a[0] + a[1] + a[2]
+            "
+        )?,
+        5320
+    );
+
+    let report = engine.profile_report().unwrap();
+
+    let map = report.find("map").next().unwrap();
+    let slow = report.find("slow").next().unwrap();
+
+    assert!(map.is_native);
+    assert_eq!(slow.calls, 3);
+
+    // Time spent in callbacks is counted under the native function calling them
+    assert!(slow.inclusive_time <= map.inclusive_time);
+    assert!(slow.inclusive_operations < map.inclusive_operations);
+    assert!(map.exclusive_operations < slow.inclusive_operations);
+    assert!(report
+        .folded_stacks()
+        .lines()
+        .any(|l| l.starts_with("<main>;map;") && l.contains(";slow;work ")));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_module"))]
+#[test]
+fn test_profiling_modules() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_profiling(true);
+
+    for (name, value) in &[("a", 1), ("b", 2)] {
+        let ast = engine.compile(&format!("fn value() {{ {} }}", value))?;
+        let mut module = rhai::Module::eval_ast_as_new(Default::default(), &ast, &engine)?;
+        module.set_id(Some(*name));
+        engine.register_static_module(*name, module.into());
+    }
+
+    assert_eq!(
+        engine.eval::<INT>("fn value() { 3 } a::value() + b::value() + b::value() + value()")?,
+        8
+    );
+
+    let report = engine.profile_report().unwrap();
+
+    // Functions of the same name from different sources are profiled separately
+    let mut calls: Vec<_> = report
+        .find("value")
+        .map(|f| (f.source.to_string(), f.calls))
+        .collect();
+    calls.sort();
+
+    assert_eq!(
+        calls,
+        vec![
+            ("".to_string(), 1),
+            ("a".to_string(), 1),
+            ("b".to_string(), 2)
+        ]
+    );
+
+    Ok(())
+}
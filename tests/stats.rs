@@ -0,0 +1,173 @@
+use rhai::{Engine, EvalAltResult, EvalOptions, EvalStats, Scope, INT};
+
+#[test]
+fn test_stats_eval() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    // A fake clock that advances by one millisecond every time it is read
+    #[cfg(not(feature = "unchecked"))]
+    {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::time::Duration;
+
+        let ticks = AtomicU64::new(0);
+        engine.set_clock(move || Duration::from_millis(ticks.fetch_add(1, Ordering::Relaxed)));
+    }
+
+    let ast = engine.compile(
+        r#"
+            let s = "hello";
+            s += ", world!";
+            let x = 0;
+            while x < 10 { x += 1; }
+            x
+        "#,
+    )?;
+
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result = engine.eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options);
+
+    assert_eq!(result?, 10);
+    assert!(stats.operations > 10);
+    assert_eq!(stats.max_string_size, 13);
+    #[cfg(not(feature = "unchecked"))]
+    assert_eq!(stats.elapsed, Some(std::time::Duration::from_millis(1)));
+    #[cfg(feature = "unchecked")]
+    assert_eq!(stats.elapsed, None);
+
+    #[cfg(not(feature = "no_function"))]
+    assert_eq!(stats.max_call_depth, 0);
+
+    // Statistics are also reported for failed evaluations
+    let ast = engine.compile(r#"let s = "hi"; s += s; s += s; throw s;"#)?;
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result = engine.eval_ast_with_scope_and_options::<()>(&mut Scope::new(), &ast, options);
+
+    assert!(matches!(
+        *result.expect_err("should error"),
+        EvalAltResult::ErrorRuntime(_, _)
+    ));
+    assert!(stats.operations > 0);
+    assert_eq!(stats.max_string_size, 8);
+
+    // Statistics are not kept across evaluations
+    let ast = engine.compile("42")?;
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let _ = engine.eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options);
+    assert_eq!(stats.max_string_size, 0);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_stats_collections() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let ast = engine.compile(
+        r#"
+            let a = [1, 2, 3];
+            a.push([4, 5]);
+            let m = #{ x: 1, y: a, z: #{ w: 2 } };
+            m.len()
+        "#,
+    )?;
+
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result = engine.eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options);
+
+    assert_eq!(result?, 3);
+    assert_eq!(stats.max_array_size, 5);
+    assert_eq!(stats.max_map_size, 2);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_stats_functions() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let ast = engine.compile(
+        r"
+            fn fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }
+            fib(6)
+        ",
+    )?;
+
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result = engine.eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options);
+    assert_eq!(result?, 8);
+    assert_eq!(stats.max_call_depth, 6);
+
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result =
+        engine.call_fn_with_options::<INT>(&mut Scope::new(), &ast, "fib", (6 as INT,), options);
+    assert_eq!(result?, 8);
+    assert_eq!(stats.max_call_depth, 5);
+
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result =
+        engine.call_fn_with_options::<INT>(&mut Scope::new(), &ast, "fib", (1 as INT,), options);
+    assert_eq!(result?, 1);
+    assert_eq!(stats.max_call_depth, 0);
+    assert!(stats.to_string().starts_with("operations: "));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_stats_callbacks() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let ast = engine.compile(
+        r#"
+            fn double(x) { x * 2 }
+            let a = [1, 2].map(|x| { let s = "hello, world!"; double(x) });
+            a.len()
+        "#,
+    )?;
+
+    // Resources used by functions called back from native functions are also recorded
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result = engine.eval_ast_with_scope_and_options::<INT>(&mut Scope::new(), &ast, options);
+    assert_eq!(result?, 2);
+    assert_eq!(stats.max_string_size, 13);
+    assert_eq!(stats.max_call_depth, 2);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_module"))]
+#[test]
+fn test_stats_modules() -> Result<(), Box<EvalAltResult>> {
+    use rhai::{module_resolvers::StaticModuleResolver, Module};
+
+    let mut engine = Engine::new();
+
+    let mut resolver = StaticModuleResolver::new();
+    resolver.insert("hello", Module::new());
+    engine.set_module_resolver(resolver);
+
+    let ast = engine.compile(r#"import "hello" as h1; import "hello" as h2;"#)?;
+    let mut stats = EvalStats::default();
+    let options = EvalOptions::new().with_stats(&mut stats);
+    let result = engine.eval_ast_with_scope_and_options::<()>(&mut Scope::new(), &ast, options);
+
+    result?;
+    assert_eq!(stats.modules, 2);
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use rhai::{Engine, EvalAltResult, Position, Scope, INT};
+use std::sync::{Arc, Mutex};
 
 #[test]
 fn test_var_scope() -> Result<(), Box<EvalAltResult>> {
@@ -94,3 +95,79 @@ fn test_var_resolver() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_var_def_callback() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let defined = Arc::new(Mutex::new(Vec::new()));
+    let log = defined.clone();
+
+    engine.on_def_var(move |name, is_const, level, context| {
+        match name {
+            // Do not shadow host-provided variables
+            _ if context.scope().contains(name) && level == 0 => Ok(false),
+            // Reserved prefix
+            _ if name.starts_with("__") => Err(EvalAltResult::ErrorRuntime(
+                format!("reserved name: {}", name).into(),
+                Position::NONE,
+            )
+            .into()),
+            _ => {
+                log.lock()
+                    .unwrap()
+                    .push((name.to_string(), is_const, level));
+                Ok(true)
+            }
+        }
+    });
+
+    let mut scope = Scope::new();
+    scope.push("config", 42 as INT);
+
+    assert_eq!(
+        engine.eval_with_scope::<INT>(
+            &mut scope,
+            "let x = 1; const y = 2; for i in range(0, 2) { let z = i; x += z; } config + x + y"
+        )?,
+        46
+    );
+    assert_eq!(
+        *defined.lock().unwrap(),
+        vec![
+            ("x".to_string(), false, 0),
+            ("y".to_string(), true, 0),
+            ("i".to_string(), false, 0),
+            ("z".to_string(), false, 2),
+            ("z".to_string(), false, 2),
+        ]
+    );
+
+    // Variables defined at global level are kept in the scope
+    assert!(scope.contains("x"));
+    scope.rewind(1);
+
+    let err = engine
+        .eval_with_scope::<INT>(&mut scope, "let x = 1;\nlet config = 0; config")
+        .expect_err("should error");
+    assert!(matches!(
+        *err,
+        EvalAltResult::ErrorForbiddenVariable(ref name, _) if name == "config"
+    ));
+    assert_eq!(err.position(), Position::new(2, 5));
+
+    assert!(matches!(
+        *engine
+            .eval_with_scope::<INT>(&mut scope, "let __x = 1; __x")
+            .expect_err("should error"),
+        EvalAltResult::ErrorRuntime(_, pos) if pos == Position::new(1, 5)
+    ));
+
+    // Shadowing in inner blocks is allowed
+    assert_eq!(
+        engine.eval_with_scope::<INT>(&mut scope, "{ let config = 1; config }")?,
+        1
+    );
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+#![cfg(not(feature = "no_index"))]
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnNamespace, FromArgs, Module, RegisterFn, INT};
+
+#[test]
+fn test_variadic_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_variadic_fn("total", 1..=12, |_, args| {
+        Ok(args.iter().map(|v| v.as_int().unwrap_or(0)).sum::<INT>())
+    });
+
+    assert_eq!(engine.eval::<INT>("total(1)")?, 1);
+    assert_eq!(
+        engine.eval::<INT>("total(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12)")?,
+        78
+    );
+    assert_eq!(engine.eval::<INT>(r#"total(1, "x", true, 2)"#)?, 3);
+    assert!(matches!(
+        *engine.eval::<INT>("total()").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(_, _)
+    ));
+    assert!(matches!(
+        *engine
+            .eval::<INT>("total(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13)")
+            .expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(_, _)
+    ));
+
+    // Functions registered for the actual argument types take precedence
+    engine.register_fn("total", |x: INT, y: INT| x * y);
+
+    assert_eq!(engine.eval::<INT>("total(6, 7)")?, 42);
+    assert_eq!(engine.eval::<INT>("total(6, 7, 8)")?, 21);
+    assert_eq!(engine.eval::<INT>(r#"total(6, "x")"#)?, 6);
+
+    // Variadic functions do not override built-in operators
+    engine.register_variadic_fn("+", 2..=2, |_, _| Ok(0 as INT));
+
+    assert_eq!(engine.eval::<INT>("1 + 2")?, 3);
+    assert_eq!(engine.eval::<INT>("1 + ()")?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_variadic_fn_dynamic_params() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    // Functions taking `Dynamic` parameters only match arguments of type `Dynamic`
+    engine.register_fn("kind", |_: Dynamic| "any");
+
+    assert!(matches!(
+        *engine.eval::<String>("kind(42)").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(_, _)
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_module"))]
+fn test_variadic_fn_module() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let mut module = Module::new();
+    module.set_variadic_fn(
+        "describe",
+        FnNamespace::Internal,
+        rhai::FnAccess::Public,
+        0..=3,
+        |_, args| Ok(args.len() as INT),
+    );
+    engine.register_static_module("report", module.into());
+
+    assert_eq!(engine.eval::<INT>("report::describe()")?, 0);
+    assert_eq!(engine.eval::<INT>(r#"report::describe(1, "a", ())"#)?, 3);
+    assert!(engine.eval::<INT>("report::describe(1, 2, 3, 4)").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_tuple_conversion() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_variadic_fn("split_at", 2..=2, |_, args| {
+        let (s, n) = <(String, INT)>::from_args(args).ok_or("expects a string and a number")?;
+        let (left, right) = s.split_at(n as usize);
+        let result: Dynamic = (left.to_string(), right.to_string()).into();
+        Ok(result)
+    });
+
+    assert_eq!(
+        engine.eval::<String>(r#"let x = split_at("hello", 2); x[1] + x[0]"#)?,
+        "llohe"
+    );
+    assert!(engine.eval::<Array>(r#"split_at(2, "hello")"#).is_err());
+
+    let mut array = engine.eval::<Array>(r#"[1, "x", true]"#)?;
+    assert_eq!(
+        <(INT, String, bool)>::from_args(&mut array),
+        Some((1, "x".to_string(), true))
+    );
+
+    let mut array = engine.eval::<Array>("[1, 2]")?;
+    assert_eq!(<(INT,)>::from_args(&mut array), None);
+
+    #[cfg(not(feature = "no_function"))]
+    {
+        let result: INT = engine.call_fn(
+            &mut Default::default(),
+            &engine.compile("fn sum(a, b, c, d, e, f, g, h) { a + b + c + d + e + f + g + h }")?,
+            "sum",
+            vec![1 as INT; 8],
+        )?;
+        assert_eq!(result, 8);
+    }
+
+    Ok(())
+}